@@ -0,0 +1,46 @@
+#![no_main]
+#![feature(float_interpolation)]
+#![allow(dead_code, unused_imports)]
+
+use libfuzzer_sys::fuzz_target;
+
+/* The application is a binary crate, so the modules the reader of signature
+ * containers depends on are mounted here straight from its sources. */
+#[path = "../../src/path/mod.rs"]
+mod path;
+#[path = "../../src/export/mod.rs"]
+mod export;
+
+/// A stand-in for the screen module of the application, which depends on the
+/// Windows API, holding the only part of it the mounted modules use.
+mod screen {
+	use serde::{Serialize, Deserialize};
+
+	/// A rectangular region of the screen of the device.
+	#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+	pub struct Region {
+		pub x: f64,
+		pub y: f64,
+		pub width: f64,
+		pub height: f64,
+	}
+	impl Region {
+		/// Whether the given point lies inside of this region.
+		pub fn contains(&self, x: f64, y: f64) -> bool {
+			x >= self.x && x < self.x + self.width
+				&& y >= self.y && y < self.y + self.height
+		}
+	}
+}
+
+fuzz_target!(|data: &[u8]| {
+	/* Whatever the data, reading it must come to an end without panicking,
+	 * both when read whole and when read an event at a time. */
+	let _ = export::read_container(data);
+
+	if let Ok(reader) = export::ContainerReader::new(data) {
+		for event in reader {
+			if event.is_err() { break }
+		}
+	}
+});
@@ -0,0 +1,454 @@
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+use stu::{Event, Capability, Transform, Information};
+use crate::path::EventPath;
+use crate::export::DeviceUnits;
+
+/// The bytes every container starts with.
+const MAGIC: [u8; 4] = *b"HCSG";
+
+/// The version of the format containers are written in.
+const VERSION: u8 = 1;
+
+/// The flags of a run of events in which the pen was touching the screen.
+const TOUCHING: u8 = 0x01;
+
+/// The flags of a run of events in which the pen was hovering over the screen.
+const HOVERING: u8 = 0x02;
+
+/// The byte marking the end of the runs of events in a container.
+const END: u8 = 0xff;
+
+/// The largest number of events a single run may hold. Runs are far shorter
+/// than this in practice, and the limit keeps corrupt containers from making
+/// us allocate absurd amounts of memory.
+const MAX_RUN: u64 = 1 << 20;
+
+/// The longest a variable length integer may be, in bytes.
+const MAX_VARINT: usize = 10;
+
+/// The header of a signature container, identifying the device the signature
+/// was captured with.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ContainerHeader {
+	/// The vendor identification number of the device.
+	pub vendor: u16,
+	/// The product identification number of the device.
+	pub product: u16,
+	/// The device identification number of the device.
+	pub device: u16,
+	/// The units of the input grid of the device, as seen in the view, which
+	/// the positions and pressures are stored in.
+	pub units: DeviceUnits,
+}
+impl ContainerHeader {
+	/// The header of a signature captured by the device with the given
+	/// information and capabilities, viewed in the given orientation.
+	pub fn new(info: &Information, caps: &Capability, transform: Transform) -> Self {
+		Self {
+			vendor: info.vendor(),
+			product: info.product(),
+			device: info.device(),
+			units: DeviceUnits::new(caps, transform),
+		}
+	}
+}
+
+/// Writes all of the events in the given path as a signature container.
+///
+/// A container starts with the `HCSG` magic bytes and the version of its
+/// format, followed by the identity of the device and the dimensions of its
+/// input grid. Then come the runs of consecutive events that agree on whether
+/// the pen was touching and hovering over the screen, each one with its flags
+/// and number of events, followed by the channels of the run one after the
+/// other: the horizontal and vertical positions and the pressure, in units of
+/// the device, and the time, in microseconds. Every value is stored as the
+/// difference from the value before it, zigzag encoded into a variable length
+/// integer. A single `0xff` byte ends the container.
+///
+/// Positions and pressures get rounded to the nearest unit of the device, and
+/// times get cut down to whole microseconds. The path is usually filtered and
+/// seen through the orientation of the view by the time it gets here, so its
+/// positions fall between units of the device, and what is read back is only
+/// within half a unit of what was written.
+pub fn write_container<W: Write>(
+	mut writer: W,
+	header: &ContainerHeader,
+	path: &EventPath) -> std::io::Result<()> {
+
+	let mut buffer = Vec::new();
+	buffer.extend_from_slice(&MAGIC);
+	buffer.push(VERSION);
+	for value in [header.vendor, header.product, header.device] {
+		buffer.extend_from_slice(&value.to_le_bytes());
+	}
+	for value in [header.units.width, header.units.height, header.units.pressure] {
+		write_varint(&mut buffer, u64::from(value));
+	}
+
+	let start = path.events().next().map(Event::time);
+	let mut previous = [0i64; 4];
+
+	let events = path.events().copied().collect::<Vec<_>>();
+	for run in events.chunk_by(|a, b| flags(a) == flags(b)) {
+		buffer.push(flags(&run[0]));
+		write_varint(&mut buffer, run.len() as u64);
+
+		let channels: [&dyn Fn(&Event) -> i64; 4] = [
+			&|event| (event.x() * f64::from(header.units.width)).round() as i64,
+			&|event| (event.y() * f64::from(header.units.height)).round() as i64,
+			&|event| (event.pressure() * f64::from(header.units.pressure)).round() as i64,
+			&|event| start
+				.map(|start| event.time().saturating_duration_since(start))
+				.unwrap_or_default()
+				.as_micros() as i64,
+		];
+		for (channel, previous) in channels.iter().zip(previous.iter_mut()) {
+			for event in run {
+				let value = channel(event);
+				write_varint(&mut buffer, zigzag(value.wrapping_sub(*previous)));
+				*previous = value;
+			}
+		}
+
+		/* Keep the memory used by long signatures in check. */
+		if buffer.len() >= 1 << 16 {
+			writer.write_all(&buffer)?;
+			buffer.clear();
+		}
+	}
+	buffer.push(END);
+
+	writer.write_all(&buffer)?;
+	writer.flush()
+}
+
+/// Reads a whole signature container into a path.
+pub fn read_container<R: Read>(reader: R) -> Result<(ContainerHeader, EventPath), ContainerError> {
+	let mut container = ContainerReader::new(reader)?;
+	let path = container.by_ref().collect::<Result<EventPath, _>>()?;
+
+	Ok((container.header, path))
+}
+
+/// A reader of the events in a signature container, which reads the container
+/// as the events are asked for, a run of events at a time.
+///
+/// Times are counted from the moment the container started being read.
+pub struct ContainerReader<R> {
+	/// The source of the container.
+	reader: R,
+	/// The header of the container.
+	header: ContainerHeader,
+	/// The events of the current run that have yet to be returned.
+	pending: VecDeque<Event>,
+	/// The last value of each of the channels.
+	previous: [i64; 4],
+	/// The moment the first event is taken to have happened at.
+	base: Instant,
+	/// Whether the end of the container, or an error, has been reached.
+	done: bool,
+}
+impl<R: Read> ContainerReader<R> {
+	/// Starts reading a container, reading its header.
+	pub fn new(mut reader: R) -> Result<Self, ContainerError> {
+		let mut magic = [0; 4];
+		read_exact(&mut reader, &mut magic)?;
+		if magic != MAGIC {
+			return Err(ContainerError::NotContainer)
+		}
+		let version = read_u8(&mut reader)?;
+		if version != VERSION {
+			return Err(ContainerError::UnsupportedVersion(version))
+		}
+
+		let mut identity = [0u16; 3];
+		for value in &mut identity {
+			let mut bytes = [0; 2];
+			read_exact(&mut reader, &mut bytes)?;
+			*value = u16::from_le_bytes(bytes);
+		}
+		let mut units = [0u32; 3];
+		for value in &mut units {
+			*value = std::convert::TryFrom::try_from(read_varint(&mut reader)?)
+				.map_err(|_| ContainerError::Corrupt)?;
+		}
+		let [vendor, product, device] = identity;
+		let [width, height, pressure] = units;
+
+		Ok(Self {
+			reader,
+			header: ContainerHeader {
+				vendor,
+				product,
+				device,
+				units: DeviceUnits { width, height, pressure },
+			},
+			pending: VecDeque::new(),
+			previous: [0; 4],
+			base: Instant::now(),
+			done: false,
+		})
+	}
+
+	/// Reads the next run of events into the pending events, returning whether
+	/// there was one.
+	fn read_run(&mut self) -> Result<bool, ContainerError> {
+		let flags = read_u8(&mut self.reader)?;
+		if flags == END {
+			return Ok(false)
+		}
+		if flags & !(TOUCHING | HOVERING) != 0 {
+			return Err(ContainerError::Corrupt)
+		}
+
+		let count = read_varint(&mut self.reader)?;
+		if count == 0 || count > MAX_RUN {
+			return Err(ContainerError::Corrupt)
+		}
+		let count = count as usize;
+
+		let mut channels = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+		for (values, previous) in channels.iter_mut().zip(self.previous.iter_mut()) {
+			for _ in 0..count {
+				let value = previous.wrapping_add(unzigzag(read_varint(&mut self.reader)?));
+				values.push(value);
+				*previous = value;
+			}
+		}
+
+		let units = self.header.units;
+		let normalize = |value: i64, range: u32| if range > 0 {
+			value as f64 / f64::from(range)
+		} else {
+			0.0
+		};
+		let [x, y, pressure, time] = &channels;
+		for i in 0..count {
+			let time = Duration::from_micros(time[i].max(0) as u64);
+			self.pending.push_back(Event::new(
+				self.base.checked_add(time).ok_or(ContainerError::Corrupt)?,
+				normalize(x[i], units.width),
+				normalize(y[i], units.height),
+				normalize(pressure[i], units.pressure),
+				flags & TOUCHING != 0,
+				flags & HOVERING != 0));
+		}
+
+		Ok(true)
+	}
+}
+impl<R: Read> Iterator for ContainerReader<R> {
+	type Item = Result<Event, ContainerError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while self.pending.is_empty() {
+			if self.done { return None }
+			match self.read_run() {
+				Ok(true) => {},
+				Ok(false) => self.done = true,
+				Err(what) => {
+					self.done = true;
+					return Some(Err(what))
+				}
+			}
+		}
+
+		self.pending.pop_front().map(Ok)
+	}
+}
+
+/// The flags of the run the given event is part of.
+fn flags(event: &Event) -> u8 {
+	let mut flags = 0;
+	if event.touching() { flags |= TOUCHING }
+	if event.hovering() { flags |= HOVERING }
+
+	flags
+}
+
+/// Maps signed integers to unsigned ones, so that values close to zero get
+/// small encodings regardless of their sign.
+fn zigzag(value: i64) -> u64 {
+	((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Undoes the mapping done by [`zigzag()`].
+///
+/// [`zigzag()`]: zigzag
+fn unzigzag(value: u64) -> i64 {
+	((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Writes a variable length integer, seven bits at a time, starting from the
+/// least significant ones, with the high bit of every byte but the last set.
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+	while value >= 0x80 {
+		buffer.push((value as u8 & 0x7f) | 0x80);
+		value >>= 7;
+	}
+	buffer.push(value as u8);
+}
+
+/// Reads a variable length integer written by [`write_varint()`].
+///
+/// [`write_varint()`]: write_varint
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64, ContainerError> {
+	let mut value = 0u64;
+	for i in 0..MAX_VARINT {
+		let byte = read_u8(reader)?;
+		let bits = u64::from(byte & 0x7f);
+		if i == MAX_VARINT - 1 && bits > 1 {
+			return Err(ContainerError::Corrupt)
+		}
+
+		value |= bits << (7 * i);
+		if byte & 0x80 == 0 {
+			return Ok(value)
+		}
+	}
+
+	Err(ContainerError::Corrupt)
+}
+
+/// Reads a single byte.
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8, ContainerError> {
+	let mut byte = [0];
+	read_exact(reader, &mut byte)?;
+
+	Ok(byte[0])
+}
+
+/// Fills the given buffer, treating running out of data as a truncated
+/// container.
+fn read_exact<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<(), ContainerError> {
+	reader.read_exact(buffer).map_err(|what| match what.kind() {
+		std::io::ErrorKind::UnexpectedEof => ContainerError::Truncated,
+		_ => ContainerError::Io(what)
+	})
+}
+
+/// Enumeration of the reasons for which a signature container may fail to be
+/// read.
+#[derive(Debug, thiserror::Error)]
+pub enum ContainerError {
+	/// The container could not be read.
+	#[error("could not read container: {0}")]
+	Io(std::io::Error),
+	/// The data does not start with the magic bytes of a container.
+	#[error("not a signature container")]
+	NotContainer,
+	/// The container is in a version of the format we can't read.
+	#[error("unsupported container version: {0}")]
+	UnsupportedVersion(u8),
+	/// The container ends before all of its contents.
+	#[error("the container is truncated")]
+	Truncated,
+	/// The contents of the container are not valid.
+	#[error("the container is corrupt")]
+	Corrupt,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The header of a container from a device with the layout of the
+	/// STU-530.
+	fn header() -> ContainerHeader {
+		ContainerHeader {
+			vendor: 0x056a,
+			product: 0x00a5,
+			device: 0x0100,
+			units: DeviceUnits { width: 10800, height: 6480, pressure: 1023 },
+		}
+	}
+
+	/// A path with positions and pressures falling between units of the
+	/// device, as they do once the path has been filtered.
+	fn path() -> EventPath {
+		let start = Instant::now();
+		(0..40u32)
+			.map(|i| {
+				let t = f64::from(i) / 40.0;
+				Event::new(
+					start + Duration::from_nanos(u64::from(i) * 5_000_321),
+					0.1 + 0.8 * t,
+					0.5 + 0.3 * (t * std::f64::consts::TAU).sin(),
+					if i % 10 < 7 { 0.2 + 0.6 * t } else { 0.0 },
+					i % 10 < 7,
+					i % 20 != 19)
+			})
+			.collect()
+	}
+
+	/// Writes the path into a container.
+	fn container() -> Vec<u8> {
+		let mut data = Vec::new();
+		write_container(&mut data, &header(), &path()).unwrap();
+		data
+	}
+
+	#[test]
+	fn containers_round_trip_to_the_resolution_of_the_device() {
+		let path = path();
+		let (header, decoded) = read_container(&container()[..]).unwrap();
+		assert_eq!(header, self::header());
+
+		let expected = path.events().collect::<Vec<_>>();
+		let found = decoded.events().collect::<Vec<_>>();
+		assert_eq!(expected.len(), found.len());
+
+		/* Values are rounded to the nearest unit, and times are cut down to
+		 * whole microseconds. */
+		let units = header.units;
+		let start = (expected[0].time(), found[0].time());
+		for (a, b) in expected.iter().zip(&found) {
+			assert!((a.x() - b.x()).abs() <= 0.5 / f64::from(units.width) + 1e-12);
+			assert!((a.y() - b.y()).abs() <= 0.5 / f64::from(units.height) + 1e-12);
+			assert!((a.pressure() - b.pressure()).abs() <= 0.5 / f64::from(units.pressure) + 1e-12);
+			assert_eq!((a.touching(), a.hovering()), (b.touching(), b.hovering()));
+
+			let elapsed = (a.time() - start.0, b.time() - start.1);
+			assert!(elapsed.0 >= elapsed.1 && elapsed.0 - elapsed.1 < Duration::from_micros(1));
+		}
+
+		/* The rounding is really there, rather than the values coming back
+		 * exactly as they went in. */
+		assert!(expected.iter().zip(&found).any(|(a, b)| a.x() != b.x()));
+	}
+
+	#[test]
+	fn truncated_containers_are_rejected() {
+		let data = container();
+		for length in 0..data.len() {
+			assert!(
+				read_container(&data[..length]).is_err(),
+				"container cut at {} of {} bytes was accepted",
+				length,
+				data.len());
+		}
+	}
+
+	#[test]
+	fn overlong_varints_are_corrupt() {
+		let read = |bytes: &[u8]| read_varint(&mut &bytes[..]);
+
+		assert_eq!(read(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]).unwrap(), u64::MAX);
+		assert!(matches!(
+			read(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]),
+			Err(ContainerError::Corrupt)));
+		assert!(matches!(
+			read(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]),
+			Err(ContainerError::Corrupt)));
+		assert!(matches!(read(&[0x80]), Err(ContainerError::Truncated)));
+
+		/* Units of the grid must fit in 32 bits. */
+		let mut data = MAGIC.to_vec();
+		data.push(VERSION);
+		data.extend_from_slice(&[0; 6]);
+		write_varint(&mut data, u64::from(u32::MAX) + 1);
+		assert!(matches!(ContainerReader::new(&data[..]), Err(ContainerError::Corrupt)));
+	}
+}
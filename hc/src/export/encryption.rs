@@ -0,0 +1,526 @@
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use aes_gcm::aead::{Aead, Payload};
+use argon2::{Argon2, Algorithm, Version, Params};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret, SharedSecret};
+use rsa::{RsaPublicKey, RsaPrivateKey, Oaep};
+use rsa::pkcs1::{DecodeRsaPublicKey, DecodeRsaPrivateKey};
+use rsa::pkcs8::{DecodePublicKey, DecodePrivateKey};
+use rsa::traits::PublicKeyParts;
+use rsa::rand_core::OsRng;
+use std::convert::TryFrom;
+use crate::export::{key_bytes, take};
+
+/// The bytes every encrypted file starts with.
+const MAGIC: [u8; 4] = *b"HCSE";
+
+/// The version of the format encrypted files are written in.
+const VERSION: u8 = 1;
+
+/// The length of the nonces files are encrypted with, in bytes.
+const NONCE_LENGTH: usize = 12;
+
+/// The length of the salts passphrases are stretched with, in bytes.
+const SALT_LENGTH: usize = 16;
+
+/// The length of X25519 public keys, in bytes.
+const PUBLIC_KEY_LENGTH: usize = 32;
+
+/// The memory cost of stretching passphrases, in kibibytes.
+const ARGON2_MEMORY: u32 = 19 * 1024;
+
+/// The number of passes made over the memory when stretching passphrases.
+const ARGON2_PASSES: u32 = 2;
+
+/// The number of lanes passphrases are stretched in.
+const ARGON2_LANES: u32 = 1;
+
+/// The largest memory cost of stretching a passphrase we accept from a file,
+/// in kibibytes, so that we don't get made to allocate absurd amounts of memory.
+const ARGON2_MAX_MEMORY: u32 = 1024 * 1024;
+
+/// The largest number of passes over the memory we accept from a file.
+const ARGON2_MAX_PASSES: u32 = 64;
+
+/// What gets mixed into the keys derived from X25519 key agreements, so that
+/// they are never confused with keys used for anything else.
+const RECIPIENT_INFO: &[u8] = b"hc signature encryption v1";
+
+/// The label keys are wrapped with RSA-OAEP under, so that they are never
+/// confused with keys wrapped for anything else.
+const RSA_LABEL: &str = "hc signature encryption v1";
+
+/// The smallest RSA keys we wrap keys with, in bits.
+const RSA_MIN_BITS: usize = 2048;
+
+/// The ways in which the key encrypting a file may be obtained.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum EncryptionMethod {
+	/// The file is encrypted with a raw 256-bit key.
+	Key,
+	/// The file is encrypted with a key stretched from a passphrase with
+	/// Argon2id.
+	Passphrase,
+	/// The file is encrypted with a key agreed upon with the X25519 public key
+	/// of its recipient, which only the holder of the private key can agree
+	/// upon again.
+	Recipient,
+	/// The file is encrypted with a random key, wrapped with RSA-OAEP, using
+	/// SHA-256, to the RSA public key of its recipient, which only the holder
+	/// of the private key can unwrap.
+	RsaRecipient,
+}
+impl EncryptionMethod {
+	/// The byte identifying this method in encrypted files.
+	fn tag(self) -> u8 {
+		match self {
+			Self::Key => 1,
+			Self::Passphrase => 2,
+			Self::Recipient => 3,
+			Self::RsaRecipient => 4,
+		}
+	}
+
+	/// The method identified by the given byte in encrypted files.
+	fn from_tag(tag: u8) -> Option<Self> {
+		match tag {
+			1 => Some(Self::Key),
+			2 => Some(Self::Passphrase),
+			3 => Some(Self::Recipient),
+			4 => Some(Self::RsaRecipient),
+			_ => None
+		}
+	}
+
+	/// The method the given data was encrypted with, if it is an encrypted
+	/// file at all.
+	pub fn of(data: &[u8]) -> Option<Self> {
+		if data.len() < MAGIC.len() + 2 || data[..MAGIC.len()] != MAGIC {
+			return None
+		}
+
+		Self::from_tag(data[MAGIC.len() + 1])
+	}
+}
+
+/// The key files get encrypted with.
+#[derive(Clone)]
+pub enum EncryptionKey {
+	/// A raw 256-bit key.
+	Key([u8; 32]),
+	/// A passphrase.
+	Passphrase(String),
+	/// The X25519 public key of the recipient of the file.
+	Recipient(PublicKey),
+	/// The RSA public key of the recipient of the file.
+	RsaRecipient(RsaPublicKey),
+}
+impl EncryptionKey {
+	/// The method files encrypted with this key are encrypted with.
+	fn method(&self) -> EncryptionMethod {
+		match self {
+			Self::Key(_) => EncryptionMethod::Key,
+			Self::Passphrase(_) => EncryptionMethod::Passphrase,
+			Self::Recipient(_) => EncryptionMethod::Recipient,
+			Self::RsaRecipient(_) => EncryptionMethod::RsaRecipient,
+		}
+	}
+}
+
+/// The key files get decrypted with.
+#[derive(Clone)]
+pub enum DecryptionKey {
+	/// A raw 256-bit key.
+	Key([u8; 32]),
+	/// A passphrase.
+	Passphrase(String),
+	/// The X25519 private key of the recipient of the file.
+	Recipient(StaticSecret),
+	/// The RSA private key of the recipient of the file.
+	RsaRecipient(Box<RsaPrivateKey>),
+}
+impl DecryptionKey {
+	/// The method of the files this key decrypts.
+	fn method(&self) -> EncryptionMethod {
+		match self {
+			Self::Key(_) => EncryptionMethod::Key,
+			Self::Passphrase(_) => EncryptionMethod::Passphrase,
+			Self::Recipient(_) => EncryptionMethod::Recipient,
+			Self::RsaRecipient(_) => EncryptionMethod::RsaRecipient,
+		}
+	}
+}
+
+/// Reads a key from a file, holding either the 32 bytes of the key or the same
+/// bytes as hexadecimal digits. Raw keys and both halves of X25519 key pairs
+/// are all read this way.
+pub fn load_key(data: &[u8]) -> Result<[u8; 32], EncryptionError> {
+	key_bytes(data).ok_or(EncryptionError::InvalidKey)
+}
+
+/// Reads an RSA public key from a file, in either the PEM or the DER encodings
+/// of either the SubjectPublicKeyInfo or the PKCS#1 structures. Keys shorter
+/// than 2048 bits are not accepted.
+pub fn load_rsa_public_key(data: &[u8]) -> Result<RsaPublicKey, EncryptionError> {
+	let key = match std::str::from_utf8(data) {
+		Ok(pem) => RsaPublicKey::from_public_key_pem(pem.trim())
+			.or_else(|_| RsaPublicKey::from_pkcs1_pem(pem.trim()))
+			.ok(),
+		Err(_) => None
+	};
+	let key = key
+		.or_else(|| RsaPublicKey::from_public_key_der(data).ok())
+		.or_else(|| RsaPublicKey::from_pkcs1_der(data).ok())
+		.ok_or(EncryptionError::InvalidKey)?;
+
+	if key.n().bits() < RSA_MIN_BITS {
+		return Err(EncryptionError::InvalidKey)
+	}
+	Ok(key)
+}
+
+/// Reads an RSA private key from a file, in either the PEM or the DER
+/// encodings of either the PKCS#8 or the PKCS#1 structures. Keys shorter than
+/// 2048 bits are not accepted.
+pub fn load_rsa_private_key(data: &[u8]) -> Result<RsaPrivateKey, EncryptionError> {
+	let key = match std::str::from_utf8(data) {
+		Ok(pem) => RsaPrivateKey::from_pkcs8_pem(pem.trim())
+			.or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem.trim()))
+			.ok(),
+		Err(_) => None
+	};
+	let key = key
+		.or_else(|| RsaPrivateKey::from_pkcs8_der(data).ok())
+		.or_else(|| RsaPrivateKey::from_pkcs1_der(data).ok())
+		.ok_or(EncryptionError::InvalidKey)?;
+
+	if key.n().bits() < RSA_MIN_BITS {
+		return Err(EncryptionError::InvalidKey)
+	}
+	Ok(key)
+}
+
+/// Encrypts the given data with AES-256-GCM.
+///
+/// An encrypted file starts with the `HCSE` magic bytes, the version of its
+/// format and the byte identifying the method its key is obtained with. Then
+/// come the parameters of that method, which, for passphrases, are the salt
+/// and the memory cost, number of passes and number of lanes of Argon2id, as
+/// little endian 32-bit integers, for X25519 recipients, the ephemeral public
+/// key the key was agreed upon with, and, for RSA recipients, the length of the
+/// wrapped key, as a little endian 16-bit integer, followed by the wrapped key.
+/// After the nonce comes the data, encrypted, with everything before it
+/// authenticated along with it.
+pub fn encrypt(data: &[u8], key: &EncryptionKey) -> Result<Vec<u8>, EncryptionError> {
+	let mut header = Vec::new();
+	header.extend_from_slice(&MAGIC);
+	header.push(VERSION);
+	header.push(key.method().tag());
+
+	let secret = match key {
+		EncryptionKey::Key(key) => *key,
+		EncryptionKey::Passphrase(passphrase) => {
+			let salt = random::<SALT_LENGTH>()?;
+			header.extend_from_slice(&salt);
+			for value in [ARGON2_MEMORY, ARGON2_PASSES, ARGON2_LANES] {
+				header.extend_from_slice(&value.to_le_bytes());
+			}
+
+			stretch(passphrase, &salt, ARGON2_MEMORY, ARGON2_PASSES, ARGON2_LANES)?
+		},
+		EncryptionKey::Recipient(recipient) => {
+			let ephemeral = StaticSecret::from(random::<32>()?);
+			let public = PublicKey::from(&ephemeral);
+			header.extend_from_slice(public.as_bytes());
+
+			derive(ephemeral.diffie_hellman(recipient), &public, recipient)?
+		},
+		EncryptionKey::RsaRecipient(recipient) => {
+			let secret = random::<32>()?;
+			let wrapped = recipient
+				.encrypt(&mut OsRng, Oaep::new_with_label::<Sha256, _>(RSA_LABEL), &secret)
+				.map_err(|_| EncryptionError::Encryption)?;
+			let length = u16::try_from(wrapped.len())
+				.map_err(|_| EncryptionError::InvalidKey)?;
+			header.extend_from_slice(&length.to_le_bytes());
+			header.extend_from_slice(&wrapped);
+
+			secret
+		}
+	};
+
+	let nonce = random::<NONCE_LENGTH>()?;
+	header.extend_from_slice(&nonce);
+
+	let cipher = Aes256Gcm::new(&secret.into());
+	let encrypted = cipher
+		.encrypt(Nonce::from_slice(&nonce), Payload { msg: data, aad: &header })
+		.map_err(|_| EncryptionError::Encryption)?;
+
+	header.extend_from_slice(&encrypted);
+	Ok(header)
+}
+
+/// Decrypts data encrypted by [`encrypt()`], failing if the data has been
+/// modified or the wrong key is given.
+///
+/// [`encrypt()`]: encrypt
+pub fn decrypt(data: &[u8], key: &DecryptionKey) -> Result<Vec<u8>, EncryptionError> {
+	let mut reader = data;
+	let mut read = |length: usize| take(&mut reader, length).ok_or(EncryptionError::Truncated);
+
+	if read(MAGIC.len())? != MAGIC {
+		return Err(EncryptionError::NotEncrypted)
+	}
+	let version = read(1)?[0];
+	if version != VERSION {
+		return Err(EncryptionError::UnsupportedVersion(version))
+	}
+	let method = EncryptionMethod::from_tag(read(1)?[0])
+		.ok_or(EncryptionError::NotEncrypted)?;
+	if method != key.method() {
+		return Err(EncryptionError::WrongMethod(method))
+	}
+
+	let secret = match key {
+		DecryptionKey::Key(key) => *key,
+		DecryptionKey::Passphrase(passphrase) => {
+			let salt = read(SALT_LENGTH)?;
+			let mut parameters = [0u32; 3];
+			for value in &mut parameters {
+				let bytes = <[u8; 4]>::try_from(read(4)?).expect("four bytes were taken");
+				*value = u32::from_le_bytes(bytes);
+			}
+
+			let [memory, passes, lanes] = parameters;
+			if memory > ARGON2_MAX_MEMORY || passes > ARGON2_MAX_PASSES {
+				return Err(EncryptionError::Corrupt)
+			}
+			stretch(passphrase, salt, memory, passes, lanes)?
+		},
+		DecryptionKey::Recipient(private) => {
+			let ephemeral = <[u8; PUBLIC_KEY_LENGTH]>::try_from(read(PUBLIC_KEY_LENGTH)?)
+				.expect("a whole key was taken");
+
+			let ephemeral = PublicKey::from(ephemeral);
+			derive(private.diffie_hellman(&ephemeral), &ephemeral, &PublicKey::from(private))?
+		},
+		DecryptionKey::RsaRecipient(private) => {
+			let length = <[u8; 2]>::try_from(read(2)?).expect("two bytes were taken");
+			let wrapped = read(usize::from(u16::from_le_bytes(length)))?;
+
+			let secret = private
+				.decrypt(Oaep::new_with_label::<Sha256, _>(RSA_LABEL), wrapped)
+				.map_err(|_| EncryptionError::Decryption)?;
+			<[u8; 32]>::try_from(&secret[..]).map_err(|_| EncryptionError::Decryption)?
+		}
+	};
+	let nonce = read(NONCE_LENGTH)?;
+	let header = &data[..data.len() - reader.len()];
+
+	let cipher = Aes256Gcm::new(&secret.into());
+	cipher
+		.decrypt(Nonce::from_slice(nonce), Payload { msg: reader, aad: header })
+		.map_err(|_| EncryptionError::Decryption)
+}
+
+/// Stretches a passphrase into a key with Argon2id.
+fn stretch(
+	passphrase: &str,
+	salt: &[u8],
+	memory: u32,
+	passes: u32,
+	lanes: u32) -> Result<[u8; 32], EncryptionError> {
+
+	let parameters = Params::new(memory, passes, lanes, Some(32))
+		.map_err(|_| EncryptionError::Corrupt)?;
+	let mut key = [0; 32];
+	Argon2::new(Algorithm::Argon2id, Version::V0x13, parameters)
+		.hash_password_into(passphrase.as_bytes(), salt, &mut key)
+		.map_err(|_| EncryptionError::Corrupt)?;
+
+	Ok(key)
+}
+
+/// Derives a key from the secret shared by an X25519 key agreement, binding it
+/// to the ephemeral public key and the public key of the recipient.
+fn derive(
+	shared: SharedSecret,
+	ephemeral: &PublicKey,
+	recipient: &PublicKey) -> Result<[u8; 32], EncryptionError> {
+
+	if !shared.was_contributory() {
+		return Err(EncryptionError::InvalidKey)
+	}
+
+	let mut salt = [0; 2 * PUBLIC_KEY_LENGTH];
+	salt[..PUBLIC_KEY_LENGTH].copy_from_slice(ephemeral.as_bytes());
+	salt[PUBLIC_KEY_LENGTH..].copy_from_slice(recipient.as_bytes());
+
+	let mut key = [0; 32];
+	Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+		.expand(RECIPIENT_INFO, &mut key)
+		.expect("32 bytes are a valid length for HKDF-SHA256");
+
+	Ok(key)
+}
+
+/// Random bytes from the random number generator of the operating system.
+fn random<const N: usize>() -> Result<[u8; N], EncryptionError> {
+	let mut bytes = [0; N];
+	getrandom::getrandom(&mut bytes).map_err(|_| EncryptionError::Random)?;
+
+	Ok(bytes)
+}
+
+/// Enumeration of the reasons for which data may fail to be encrypted or
+/// decrypted.
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+	/// The data does not start with the magic bytes of an encrypted file.
+	#[error("the file is not encrypted")]
+	NotEncrypted,
+	/// The file is in a version of the format we can't read.
+	#[error("unsupported encrypted file version: {0}")]
+	UnsupportedVersion(u8),
+	/// The file is encrypted with a different method than the key is for.
+	#[error("the file is encrypted with a different method: {0:?}")]
+	WrongMethod(EncryptionMethod),
+	/// The file ends before all of its contents.
+	#[error("the encrypted file is truncated")]
+	Truncated,
+	/// The parameters of the encryption are not valid.
+	#[error("the encrypted file is corrupt")]
+	Corrupt,
+	/// The key is not valid.
+	#[error("invalid encryption key")]
+	InvalidKey,
+	/// The operating system could not give us random numbers.
+	#[error("could not generate random numbers")]
+	Random,
+	/// The data could not be encrypted.
+	#[error("could not encrypt the data")]
+	Encryption,
+	/// The data could not be decrypted, either because the key is wrong or
+	/// because the data has been modified.
+	#[error("wrong key, or the file has been modified")]
+	Decryption,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The data encrypted by the tests.
+	const DATA: &[u8] = b"the events of a signature";
+
+	/// A pair of keys of each of the methods, made from the given byte, or
+	/// picked by it, for RSA keys.
+	fn keys(seed: u8) -> Vec<(EncryptionKey, DecryptionKey)> {
+		let (public, private) = match seed {
+			1 => (
+				include_bytes!("../../tests/fixtures/rsa-public-1.pem").as_ref(),
+				include_bytes!("../../tests/fixtures/rsa-private-1.pem").as_ref()),
+			_ => (
+				include_bytes!("../../tests/fixtures/rsa-public-2.pem").as_ref(),
+				include_bytes!("../../tests/fixtures/rsa-private-2.pem").as_ref()),
+		};
+		let secret = StaticSecret::from([seed; 32]);
+
+		vec![
+			(EncryptionKey::Key([seed; 32]), DecryptionKey::Key([seed; 32])),
+			(
+				EncryptionKey::Passphrase(format!("passphrase {}", seed)),
+				DecryptionKey::Passphrase(format!("passphrase {}", seed))
+			),
+			(EncryptionKey::Recipient(PublicKey::from(&secret)), DecryptionKey::Recipient(secret)),
+			(
+				EncryptionKey::RsaRecipient(load_rsa_public_key(public).unwrap()),
+				DecryptionKey::RsaRecipient(Box::new(load_rsa_private_key(private).unwrap()))
+			),
+		]
+	}
+
+	/// The length of the header of the given encrypted data, which is all of
+	/// it but the encrypted data and its authentication tag.
+	fn header_length(encrypted: &[u8]) -> usize {
+		encrypted.len() - DATA.len() - 16
+	}
+
+	#[test]
+	fn every_method_round_trips() {
+		for (encryption, decryption) in keys(1) {
+			let encrypted = encrypt(DATA, &encryption).unwrap();
+			assert_eq!(EncryptionMethod::of(&encrypted), Some(encryption.method()));
+			assert_eq!(decrypt(&encrypted, &decryption).unwrap(), DATA);
+
+			/* Every file gets its own nonce, and its own key, where it can. */
+			assert_ne!(encrypt(DATA, &encryption).unwrap(), encrypted);
+		}
+	}
+
+	#[test]
+	fn wrong_methods_are_told_apart() {
+		let keys = keys(1);
+		for (encryption, _) in &keys {
+			let encrypted = encrypt(DATA, encryption).unwrap();
+			for (_, decryption) in keys.iter().filter(|(other, _)| other.method() != encryption.method()) {
+				match decrypt(&encrypted, decryption) {
+					Err(EncryptionError::WrongMethod(method)) => assert_eq!(method, encryption.method()),
+					other => panic!("{:?} decrypted with {:?}: {:?}", encryption.method(), decryption.method(), other)
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn wrong_keys_are_rejected() {
+		for ((encryption, _), (_, decryption)) in keys(1).into_iter().zip(keys(2)) {
+			let encrypted = encrypt(DATA, &encryption).unwrap();
+			assert!(
+				matches!(decrypt(&encrypted, &decryption), Err(EncryptionError::Decryption)),
+				"{:?} decrypted with the wrong key",
+				encryption.method());
+		}
+	}
+
+	#[test]
+	fn tampering_is_caught() {
+		for (encryption, decryption) in keys(1) {
+			let encrypted = encrypt(DATA, &encryption).unwrap();
+			let header = header_length(&encrypted);
+
+			/* The header is authenticated along with the data, so changing any
+			 * of it, nonce included, fails decryption. Passphrases are only
+			 * tried on a few of the bytes, as stretching them is slow. */
+			let (positions, lengths) = match encryption.method() {
+				EncryptionMethod::Passphrase => (
+					vec![MAGIC.len() + 2, header - NONCE_LENGTH, header - 1],
+					vec![MAGIC.len() + 2, header - 1, header, encrypted.len() - 1]),
+				_ => ((MAGIC.len()..header).collect(), (0..encrypted.len()).collect()),
+			};
+			for position in positions.into_iter().chain([header, encrypted.len() - 1]) {
+				let mut modified = encrypted.clone();
+				modified[position] ^= 0x01;
+				assert!(
+					decrypt(&modified, &decryption).is_err(),
+					"{:?} with byte {} of {} flipped was decrypted",
+					encryption.method(),
+					position,
+					encrypted.len());
+			}
+
+			for length in lengths {
+				assert!(decrypt(&encrypted[..length], &decryption).is_err());
+			}
+		}
+	}
+
+	#[test]
+	fn short_rsa_keys_are_rejected() {
+		let key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+		let der = rsa::pkcs1::EncodeRsaPrivateKey::to_pkcs1_der(&key).unwrap();
+		assert!(matches!(load_rsa_private_key(der.as_bytes()), Err(EncryptionError::InvalidKey)));
+	}
+}
@@ -0,0 +1,628 @@
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use stu::{Event, Capability, Transform, StrokeKind};
+use crate::path::EventPath;
+use crate::export::number;
+
+/// The namespace of InkML documents.
+const NAMESPACE: &str = "http://www.w3.org/2003/InkML";
+
+/// The namespace of the attributes defined by XML itself, such as `xml:id`.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The time between events, in milliseconds, assumed for documents with no
+/// time channel. This is about the report rate of the STU devices.
+const DEFAULT_INTERVAL: f64 = 5.0;
+
+/// The pressure assumed for traces of the pen touching the screen, in
+/// documents with no force channel.
+const DEFAULT_PRESSURE: f64 = 0.5;
+
+/// Encodes all of the events in the given path as an InkML document.
+///
+/// The path is taken to have been captured by a device with the given
+/// capabilities, viewed in the given orientation. The positions of the pen are
+/// written in units of the input grid of the device, its pressure in units of
+/// the pressure levels of the device, and the time in milliseconds since the
+/// first event in the path. The strokes that left ink are written as pen down
+/// traces, and the movements of the pen between them as pen up traces.
+pub fn encode_inkml(path: &EventPath, caps: &Capability, transform: Transform) -> String {
+	let (width, height) = transform.dimensions(
+		caps.input_grid_width(),
+		caps.input_grid_height());
+	let depth = caps.input_grid_pressure();
+	let resolution = f64::from(caps.input_grid_width()) / caps.physical_width();
+
+	let mut ink = String::new();
+	let _ = writeln!(ink, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+	let _ = writeln!(ink, r#"<ink xmlns="{}">"#, NAMESPACE);
+	let _ = writeln!(ink, "<definitions>");
+	let _ = writeln!(ink, r#"<context xml:id="context">"#);
+	let _ = writeln!(ink, r#"<inkSource xml:id="source" manufacturer="Wacom" description="STU">"#);
+	let _ = writeln!(ink, "<traceFormat>");
+	let _ = writeln!(ink, r#"<channel name="X" type="decimal" min="0" max="{}"/>"#, width);
+	let _ = writeln!(ink, r#"<channel name="Y" type="decimal" min="0" max="{}"/>"#, height);
+	let _ = writeln!(ink, r#"<channel name="F" type="decimal" min="0" max="{}"/>"#, depth);
+	let _ = writeln!(ink, r#"<channel name="T" type="decimal" units="ms"/>"#);
+	let _ = writeln!(ink, "</traceFormat>");
+	let _ = writeln!(
+		ink,
+		r#"<activeArea width="{}" height="{}" units="mm"/>"#,
+		number(f64::from(width) / resolution),
+		number(f64::from(height) / resolution));
+	let _ = writeln!(ink, "<channelProperties>");
+	for channel in ["X", "Y"] {
+		let _ = writeln!(
+			ink,
+			r#"<channelProperty channel="{}" name="resolution" value="{}" units="1/mm"/>"#,
+			channel,
+			number(resolution));
+	}
+	let _ = writeln!(ink, "</channelProperties>");
+	let _ = writeln!(ink, "</inkSource>");
+	let _ = writeln!(ink, "</context>");
+	let _ = writeln!(ink, "</definitions>");
+
+	let start = path.events().next().map(Event::time);
+	for segment in path.segments() {
+		let points = segment.events()
+			.iter()
+			.map(|event| {
+				let time = start
+					.map(|start| event.time().saturating_duration_since(start))
+					.unwrap_or_default();
+
+				format!(
+					"{} {} {} {}",
+					number(event.x() * f64::from(width)),
+					number(event.y() * f64::from(height)),
+					number(event.pressure() * f64::from(depth)),
+					number(time.as_secs_f64() * 1000.0))
+			})
+			.collect::<Vec<_>>()
+			.join(", ");
+
+		let kind = match segment.kind() {
+			StrokeKind::Ink => "",
+			StrokeKind::Hover => r#" type="penUp""#,
+		};
+		let _ = writeln!(ink, r##"<trace contextRef="#context"{}>{}</trace>"##, kind, points);
+	}
+	let _ = writeln!(ink, "</ink>");
+
+	ink
+}
+
+/// Decodes the traces in an InkML document into a path.
+///
+/// Pen down traces become strokes that left ink, and pen up traces become
+/// movements of the pen hovering over the screen. The position and the force
+/// of the pen are normalized by the bounds of their channels, or by the largest
+/// value in the document for channels with no bounds. Times are taken from the
+/// time channel, when there is one, and counted from the moment the document
+/// was read.
+pub fn decode_inkml(text: &str) -> Result<EventPath, InkMlError> {
+	let document = roxmltree::Document::parse(text).map_err(InkMlError::Xml)?;
+	let root = document.root_element();
+	if root.tag_name().name() != "ink" {
+		return Err(InkMlError::NotInkMl)
+	}
+
+	let mut traces = Vec::new();
+	read_traces(&document, root, &Format::default(), &mut traces)?;
+
+	/* Channels with no bounds are normalized by the values in the document. */
+	let largest = |channel: fn(&Sample) -> Option<f64>| traces.iter()
+		.flat_map(|trace: &Trace| trace.samples.iter())
+		.filter_map(channel)
+		.fold(None, |largest: Option<f64>, value| Some(largest.map_or(value, |l| l.max(value))));
+	let fallback = Bounds {
+		x: largest(|sample| Some(sample.x)),
+		y: largest(|sample| Some(sample.y)),
+		force: largest(|sample| sample.force),
+	};
+
+	/* Times are counted from the earliest one in the document. */
+	let mut time = 0.0;
+	let mut times = Vec::new();
+	for trace in &traces {
+		for sample in &trace.samples {
+			time = sample.time.unwrap_or(time + DEFAULT_INTERVAL);
+			times.push(time);
+		}
+	}
+	let first = times.iter().copied().fold(f64::INFINITY, f64::min);
+	let base = Instant::now();
+
+	let mut events = Vec::new();
+	let mut times = times.into_iter();
+	for trace in &traces {
+		for sample in &trace.samples {
+			let time = times.next().unwrap_or(first);
+			let normalize = |value: f64, (min, max): (Option<f64>, Option<f64>), largest: Option<f64>| {
+				let min = min.unwrap_or(0.0);
+				let max = max.or(largest).unwrap_or(1.0);
+				if max > min { (value - min) / (max - min) } else { 0.0 }
+			};
+
+			let pressure = match sample.force {
+				Some(force) => normalize(force, trace.format.force, fallback.force),
+				None if trace.touching => DEFAULT_PRESSURE,
+				None => 0.0
+			};
+
+			/* Times too far apart for the clock to hold are not valid. */
+			let time = Some((time - first).max(0.0) / 1000.0)
+				.filter(|seconds| seconds.is_finite())
+				.and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+				.and_then(|offset| base.checked_add(offset))
+				.ok_or_else(|| InkMlError::InvalidValue(time.to_string()))?;
+
+			events.push(Event::new(
+				time,
+				normalize(sample.x, trace.format.x, fallback.x),
+				normalize(sample.y, trace.format.y, fallback.y),
+				pressure,
+				trace.touching,
+				true));
+		}
+	}
+
+	Ok(events.into_iter().collect())
+}
+
+/// The channels of a trace format we know how to read, given by their
+/// position in the trace format along with their bounds, where known.
+#[derive(Debug, Clone, PartialEq)]
+struct Format {
+	/// The names of all of the channels, in the order their values are given.
+	channels: Vec<String>,
+	/// The bounds of the horizontal position of the pen.
+	x: (Option<f64>, Option<f64>),
+	/// The bounds of the vertical position of the pen.
+	y: (Option<f64>, Option<f64>),
+	/// The bounds of the force applied to the pen.
+	force: (Option<f64>, Option<f64>),
+	/// The number of milliseconds in a unit of the time channel.
+	time_scale: f64,
+}
+impl Default for Format {
+	/// The default trace format, as defined by InkML, with the position of the
+	/// pen as its only channels.
+	fn default() -> Self {
+		Self {
+			channels: vec!["X".to_owned(), "Y".to_owned()],
+			x: (None, None),
+			y: (None, None),
+			force: (None, None),
+			time_scale: 1.0,
+		}
+	}
+}
+impl Format {
+	/// Reads a trace format element.
+	fn read(node: roxmltree::Node) -> Result<Self, InkMlError> {
+		let mut format = Self {
+			channels: Vec::new(),
+			..Self::default()
+		};
+
+		/* Intermittent channels come after the regular ones, and may be left
+		 * out of any of the points. */
+		let channels = node.children()
+			.filter(|child| child.has_tag_name("channel"))
+			.chain(node.children()
+				.filter(|child| child.has_tag_name("intermittentChannels"))
+				.flat_map(|group| group.children())
+				.filter(|child| child.has_tag_name("channel")));
+
+		for channel in channels {
+			let name = channel.attribute("name").unwrap_or_default();
+			let bound = |attribute: &str| channel.attribute(attribute)
+				.map(|value| value.trim()
+					.parse::<f64>()
+					.map_err(|_| InkMlError::InvalidValue(value.to_owned())))
+				.transpose();
+			let bounds = (bound("min")?, bound("max")?);
+
+			match name {
+				"X" => format.x = bounds,
+				"Y" => format.y = bounds,
+				"F" => format.force = bounds,
+				"T" => format.time_scale = match channel.attribute("units").unwrap_or("ms") {
+					"s" => 1000.0,
+					"us" | "µs" => 0.001,
+					_ => 1.0
+				},
+				_ => {}
+			}
+			format.channels.push(name.to_owned());
+		}
+
+		for required in ["X", "Y"] {
+			if !format.channels.iter().any(|channel| channel == required) {
+				return Err(InkMlError::MissingChannel(required))
+			}
+		}
+
+		Ok(format)
+	}
+
+	/// The position of the channel with the given name.
+	fn position(&self, name: &str) -> Option<usize> {
+		self.channels.iter().position(|channel| channel == name)
+	}
+}
+
+/// The bounds of the values of the channels we know how to read, where known.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Bounds {
+	/// The largest horizontal position of the pen.
+	x: Option<f64>,
+	/// The largest vertical position of the pen.
+	y: Option<f64>,
+	/// The largest force applied to the pen.
+	force: Option<f64>,
+}
+
+/// A point in a trace, with the values of the channels we know how to read.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Sample {
+	/// The horizontal position of the pen.
+	x: f64,
+	/// The vertical position of the pen.
+	y: f64,
+	/// The force applied to the pen, if given.
+	force: Option<f64>,
+	/// The time of the point, in milliseconds, if given.
+	time: Option<f64>,
+}
+
+/// A trace read from a document.
+#[derive(Debug, Clone, PartialEq)]
+struct Trace {
+	/// The format the values in this trace were given in.
+	format: Format,
+	/// Whether the pen was touching the screen during this trace.
+	touching: bool,
+	/// The points in this trace.
+	samples: Vec<Sample>,
+}
+
+/// Reads all of the traces under the given element, in document order, with
+/// traces that don't pick their own context being read in the given format.
+fn read_traces(
+	document: &roxmltree::Document,
+	node: roxmltree::Node,
+	format: &Format,
+	traces: &mut Vec<Trace>) -> Result<(), InkMlError> {
+
+	/* Contexts outside of definitions change the context of the traces that
+	 * come after them. */
+	let mut current = format.clone();
+	for child in node.children().filter(roxmltree::Node::is_element) {
+		match child.tag_name().name() {
+			"context" => current = read_context(document, child)?,
+			"traceGroup" => {
+				let format = match child.attribute("contextRef") {
+					Some(reference) => read_context(document, find(document, reference)?)?,
+					None => current.clone()
+				};
+				read_traces(document, child, &format, traces)?;
+			},
+			"trace" => {
+				let format = match child.attribute("contextRef") {
+					Some(reference) => read_context(document, find(document, reference)?)?,
+					None => current.clone()
+				};
+				let touching = child.attribute("type") != Some("penUp");
+				let samples = read_samples(&format, child.text().unwrap_or_default())?;
+
+				traces.push(Trace { format, touching, samples });
+			},
+			_ => {}
+		}
+	}
+
+	Ok(())
+}
+
+/// Reads the trace format of a context, or of an ink source.
+fn read_context(
+	document: &roxmltree::Document,
+	node: roxmltree::Node) -> Result<Format, InkMlError> {
+
+	let mut visited = vec![node.id()];
+	let mut node = node;
+	loop {
+		let child = |name: &str| node.children().find(|child| child.has_tag_name(name));
+
+		if node.has_tag_name("traceFormat") {
+			return Format::read(node)
+		} else if let Some(format) = child("traceFormat") {
+			return Format::read(format)
+		}
+
+		let next = if let Some(source) = child("inkSource") {
+			source
+		} else if let Some(reference) = node.attribute("traceFormatRef")
+			.or_else(|| node.attribute("inkSourceRef"))
+			.or_else(|| node.attribute("contextRef")) {
+
+			/* References that lead back to an element we have been through
+			 * would never get to a trace format. */
+			let target = find(document, reference)?;
+			if visited.contains(&target.id()) {
+				return Err(InkMlError::UnknownReference(reference.to_owned()))
+			}
+			target
+		} else {
+			return Ok(Format::default())
+		};
+
+		visited.push(next.id());
+		node = next;
+	}
+}
+
+/// Finds the element with the given reference to its identifier.
+fn find<'a, 'input>(
+	document: &'a roxmltree::Document<'input>,
+	reference: &str) -> Result<roxmltree::Node<'a, 'input>, InkMlError> {
+
+	let id = reference.trim().trim_start_matches('#');
+	document.descendants()
+		.find(|node| node.attribute((XML_NAMESPACE, "id")) == Some(id))
+		.ok_or_else(|| InkMlError::UnknownReference(reference.to_owned()))
+}
+
+/// How a value in a trace relates to the previous value in its channel.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Mode {
+	/// The value is given as is.
+	Explicit,
+	/// The value is given as the difference from the previous one.
+	FirstDifference,
+	/// The value is given as the difference between its first difference and
+	/// the first difference of the previous one.
+	SecondDifference,
+}
+
+/// The state of a channel while the points of a trace are being read.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Channel {
+	/// How the values in this channel are currently given.
+	mode: Mode,
+	/// The last value in this channel.
+	value: Option<f64>,
+	/// The last first difference in this channel.
+	velocity: f64,
+}
+
+/// Reads the points in the text of a trace.
+fn read_samples(format: &Format, text: &str) -> Result<Vec<Sample>, InkMlError> {
+	let x = format.position("X");
+	let y = format.position("Y");
+	let force = format.position("F");
+	let time = format.position("T");
+
+	let mut channels = vec![
+		Channel { mode: Mode::Explicit, value: None, velocity: 0.0 };
+		format.channels.len()];
+	let mut samples = Vec::new();
+
+	for point in text.split(',').filter(|point| !point.trim().is_empty()) {
+		let tokens = tokenize(point)?;
+		for (channel, (mode, value)) in channels.iter_mut().zip(tokens) {
+			let mode = mode.unwrap_or(channel.mode);
+			channel.mode = mode;
+
+			/* Missing values keep the previous value of the channel. */
+			let value = match value {
+				Some(value) => value,
+				None => continue
+			};
+			let previous = channel.value.unwrap_or(0.0);
+			let next = match mode {
+				Mode::Explicit => value,
+				Mode::FirstDifference => previous + value,
+				Mode::SecondDifference => previous + channel.velocity + value,
+			};
+
+			channel.velocity = channel.value.map_or(0.0, |previous| next - previous);
+			channel.value = Some(next);
+		}
+
+		let value = |position: Option<usize>| position
+			.and_then(|position| channels[position].value);
+		if let (Some(x), Some(y)) = (value(x), value(y)) {
+			samples.push(Sample {
+				x,
+				y,
+				force: value(force),
+				time: value(time).map(|time| time * format.time_scale),
+			});
+		}
+	}
+
+	Ok(samples)
+}
+
+/// A value in a point of a trace, along with the mode it switches its channel
+/// to, if any. Missing values are given as `None`.
+type Token = (Option<Mode>, Option<f64>);
+
+/// Splits the values of a point in a trace.
+fn tokenize(point: &str) -> Result<Vec<Token>, InkMlError> {
+	let invalid = || InkMlError::InvalidValue(point.trim().to_owned());
+
+	let bytes = point.as_bytes();
+	let mut tokens = Vec::new();
+	let mut i = 0;
+	while i < bytes.len() {
+		if bytes[i].is_ascii_whitespace() {
+			i += 1;
+			continue
+		}
+
+		let mode = match bytes[i] {
+			b'!' => Some(Mode::Explicit),
+			b'\'' => Some(Mode::FirstDifference),
+			b'"' => Some(Mode::SecondDifference),
+			_ => None
+		};
+		if mode.is_some() {
+			i += 1;
+		}
+
+		match bytes.get(i) {
+			Some(b'?') | Some(b'*') => {
+				tokens.push((mode, None));
+				i += 1;
+			},
+			Some(b'T') => {
+				tokens.push((mode, Some(1.0)));
+				i += 1;
+			},
+			Some(b'F') => {
+				tokens.push((mode, Some(0.0)));
+				i += 1;
+			},
+			Some(_) => {
+				/* Numbers end wherever something that can't continue them
+				 * shows up, so that values may be given with no whitespace
+				 * between them, as in "10-5". */
+				let start = i;
+				if matches!(bytes[i], b'-' | b'+') {
+					i += 1;
+				}
+				while i < bytes.len() && bytes[i].is_ascii_digit() { i += 1 }
+				if i < bytes.len() && bytes[i] == b'.' {
+					i += 1;
+					while i < bytes.len() && bytes[i].is_ascii_digit() { i += 1 }
+				}
+				if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+					i += 1;
+					if i < bytes.len() && matches!(bytes[i], b'-' | b'+') { i += 1 }
+					while i < bytes.len() && bytes[i].is_ascii_digit() { i += 1 }
+				}
+
+				let value = point[start..i].parse::<f64>().map_err(|_| invalid())?;
+				tokens.push((mode, Some(value)));
+			},
+			None => return Err(invalid())
+		}
+	}
+
+	Ok(tokens)
+}
+
+/// Enumeration of the reasons for which an InkML document may fail to be read.
+#[derive(Debug, thiserror::Error)]
+pub enum InkMlError {
+	/// The document is not valid XML.
+	#[error("invalid XML: {0}")]
+	Xml(roxmltree::Error),
+	/// The root of the document is not an `ink` element.
+	#[error("not an InkML document")]
+	NotInkMl,
+	/// The document refers to an element that is not in it.
+	#[error("reference to a missing element: {0}")]
+	UnknownReference(String),
+	/// A trace format is missing one of the channels we need.
+	#[error("trace format with no {0} channel")]
+	MissingChannel(&'static str),
+	/// A value in the document could not be read.
+	#[error("invalid value: {0}")]
+	InvalidValue(String),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use stu::Rotation;
+
+	/// The capabilities of a device with the layout of the STU-530.
+	fn caps() -> Capability {
+		Capability::new((800, 480), (10800, 6480, 1023), 2540, 0)
+	}
+
+	/// A path with a few strokes, each followed by the pen hovering away.
+	fn path() -> EventPath {
+		let start = Instant::now();
+		let mut events = Vec::new();
+		for stroke in 0..3 {
+			for i in 0..10 {
+				let touching = i < 7;
+				let k = stroke * 10 + i;
+				events.push(Event::new(
+					start + Duration::from_millis(k * 5),
+					0.1 + 0.05 * i as f64 + 0.2 * stroke as f64,
+					0.3 + 0.01 * (i * i) as f64,
+					if touching { 0.1 * i as f64 } else { 0.0 },
+					touching,
+					true));
+			}
+		}
+
+		events.into_iter().collect()
+	}
+
+	/// A document with a single trace whose points are given by the text, in
+	/// a format with a time channel.
+	fn document(points: &str) -> String {
+		format!(
+			r#"<ink xmlns="{}"><context><traceFormat><channel name="X" max="100"/><channel name="Y" max="100"/><channel name="T" units="ms"/></traceFormat></context><trace>{}</trace></ink>"#,
+			NAMESPACE,
+			points)
+	}
+
+	#[test]
+	fn encoding_round_trips() {
+		let path = path();
+		for transform in [Transform::identity(), Transform::rotated(Rotation::Quarter)] {
+			let decoded = decode_inkml(&encode_inkml(&path, &caps(), transform)).unwrap();
+
+			let expected = path.events().collect::<Vec<_>>();
+			let found = decoded.events().collect::<Vec<_>>();
+			assert_eq!(expected.len(), found.len());
+
+			let start = (expected[0].time(), found[0].time());
+			for (a, b) in expected.iter().zip(&found) {
+				assert!((a.x() - b.x()).abs() < 1e-6, "{:?} != {:?}", a, b);
+				assert!((a.y() - b.y()).abs() < 1e-6, "{:?} != {:?}", a, b);
+				assert!((a.pressure() - b.pressure()).abs() < 1e-5, "{:?} != {:?}", a, b);
+				assert_eq!(a.touching(), b.touching());
+				assert_eq!(
+					a.time().duration_since(start.0).as_millis(),
+					b.time().duration_since(start.1).as_millis());
+			}
+		}
+	}
+
+	#[test]
+	fn times_beyond_the_clock_are_invalid() {
+		for time in ["1e30", "1e400"] {
+			let text = document(&format!("10 10 0, 20 20 {}", time));
+			assert!(
+				matches!(decode_inkml(&text), Err(InkMlError::InvalidValue(_))),
+				"time {} was accepted",
+				time);
+		}
+
+		assert!(decode_inkml(&document("10 10 0, 20 20 5")).is_ok());
+	}
+
+	#[test]
+	fn context_cycles_are_unknown_references() {
+		let text = format!(
+			r##"<ink xmlns="{}"><definitions><context xml:id="a" contextRef="#b"/><context xml:id="b" contextRef="#a"/></definitions><trace contextRef="#a">10 10, 20 20</trace></ink>"##,
+			NAMESPACE);
+		assert!(matches!(decode_inkml(&text), Err(InkMlError::UnknownReference(_))));
+
+		let text = format!(
+			r##"<ink xmlns="{}"><definitions><context xml:id="a" contextRef="#a"/></definitions><trace contextRef="#a">10 10</trace></ink>"##,
+			NAMESPACE);
+		assert!(matches!(decode_inkml(&text), Err(InkMlError::UnknownReference(_))));
+	}
+}
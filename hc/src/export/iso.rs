@@ -0,0 +1,588 @@
+use serde::{Serialize, Deserialize};
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+use stu::{Event, Capability, Transform};
+use crate::path::EventPath;
+
+/// The format identifier every record starts with.
+const FORMAT_IDENTIFIER: [u8; 4] = *b"SDI\0";
+
+/// The version of the standard the records are written in.
+const VERSION: [u8; 4] = *b" 10\0";
+
+/// The channels a record may include, in the order they are given in the
+/// channel inclusion field, from its most significant bit down.
+const CHANNELS: [Channel; 16] = [
+	Channel::X, Channel::Y, Channel::Z, Channel::VelocityX, Channel::VelocityY,
+	Channel::AccelerationX, Channel::AccelerationY, Channel::Time,
+	Channel::TimeDifference, Channel::Pressure, Channel::TipSwitch,
+	Channel::TiltX, Channel::TiltY, Channel::Azimuth, Channel::Elevation,
+	Channel::Rotation,
+];
+
+/// The channels of the time series defined by the standard.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum Channel {
+	/// The horizontal position of the pen.
+	X,
+	/// The vertical position of the pen.
+	Y,
+	/// The height of the pen over the screen.
+	Z,
+	/// The horizontal velocity of the pen.
+	VelocityX,
+	/// The vertical velocity of the pen.
+	VelocityY,
+	/// The horizontal acceleration of the pen.
+	AccelerationX,
+	/// The vertical acceleration of the pen.
+	AccelerationY,
+	/// The time since the first sample.
+	Time,
+	/// The time since the previous sample.
+	TimeDifference,
+	/// The pressure of the pen on the screen.
+	Pressure,
+	/// Whether the tip of the pen is touching the screen.
+	TipSwitch,
+	/// The tilt of the pen along the horizontal axis.
+	TiltX,
+	/// The tilt of the pen along the vertical axis.
+	TiltY,
+	/// The azimuth of the pen.
+	Azimuth,
+	/// The elevation of the pen.
+	Elevation,
+	/// The rotation of the pen around its own axis.
+	Rotation,
+}
+impl Channel {
+	/// The bit of this channel in the channel inclusion field.
+	fn bit(self) -> u16 {
+		let index = CHANNELS.iter()
+			.position(|channel| *channel == self)
+			.expect("every channel is in the list of channels");
+
+		0x8000 >> index
+	}
+}
+
+/// The variants of the format records may be written in.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IsoVariant {
+	/// The full format, with every value stored in two bytes, and the time of
+	/// every sample given since the first one.
+	#[default]
+	Full,
+	/// The compact format, with every value stored in a single byte, and the
+	/// time of every sample given since the previous one. The scaling of the
+	/// positions is adjusted to make up for the coarser values.
+	Compact,
+}
+impl IsoVariant {
+	/// The largest value that fits in a value in this variant.
+	fn limit(self) -> u16 {
+		match self {
+			Self::Full => u16::MAX,
+			Self::Compact => u16::from(u8::MAX),
+		}
+	}
+}
+
+/// The description of a channel in the header of a record.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+struct Description {
+	/// The number of units per millimeter, or per second for times.
+	scaling: Option<f64>,
+	/// The smallest value of the channel.
+	min: Option<u16>,
+	/// The largest value of the channel.
+	max: Option<u16>,
+	/// The average value of the channel.
+	average: Option<u16>,
+	/// The standard deviation of the values of the channel.
+	deviation: Option<u16>,
+}
+impl Description {
+	/// The flags of the preamble of a description, in the order the fields
+	/// they stand for are given in.
+	const SCALING: u8 = 0x80;
+	const MIN: u8 = 0x40;
+	const MAX: u8 = 0x20;
+	const AVERAGE: u8 = 0x10;
+	const DEVIATION: u8 = 0x08;
+
+	/// Writes this description.
+	fn write(&self, record: &mut Vec<u8>) {
+		let fields = [
+			(Self::SCALING, self.scaling.map(encode_scaling)),
+			(Self::MIN, self.min),
+			(Self::MAX, self.max),
+			(Self::AVERAGE, self.average),
+			(Self::DEVIATION, self.deviation),
+		];
+
+		record.push(fields.iter()
+			.filter(|(_, value)| value.is_some())
+			.fold(0, |preamble, (flag, _)| preamble | flag));
+		for value in fields.iter().filter_map(|(_, value)| *value) {
+			record.extend_from_slice(&value.to_be_bytes());
+		}
+	}
+
+	/// Reads a description.
+	fn read(reader: &mut Reader) -> Result<Self, IsoError> {
+		let preamble = reader.u8()?;
+		let mut field = |flag: u8| if preamble & flag != 0 {
+			reader.u16().map(Some)
+		} else {
+			Ok(None)
+		};
+
+		Ok(Self {
+			scaling: field(Self::SCALING)?.map(decode_scaling),
+			min: field(Self::MIN)?,
+			max: field(Self::MAX)?,
+			average: field(Self::AVERAGE)?,
+			deviation: field(Self::DEVIATION)?,
+		})
+	}
+}
+
+/// Encodes all of the events in the given path as an ISO/IEC 19794-7 record
+/// of signature time series data, in the given variant.
+///
+/// The path is taken to have been captured by a device with the given
+/// capabilities, viewed in the given orientation. The record holds a single
+/// signature, with the position, pressure and tip switch channels, along with
+/// the time in milliseconds. Positions are given in units of the input grid of
+/// the device, with the scaling of the channels set to the resolution of the
+/// grid, and pressures in the pressure levels of the device.
+pub fn encode_iso19794(
+	path: &EventPath,
+	caps: &Capability,
+	transform: Transform,
+	variant: IsoVariant) -> Result<Vec<u8>, IsoError> {
+
+	let (width, height) = transform.dimensions(
+		caps.input_grid_width(),
+		caps.input_grid_height());
+	let resolution = f64::from(caps.input_grid_width()) / caps.physical_width();
+	let limit = variant.limit();
+
+	/* The compact variant squeezes every channel into the range of a byte. */
+	let range = |max: u32| match variant {
+		IsoVariant::Full => u16::try_from(max).map_err(|_| IsoError::OutOfRange("device")),
+		IsoVariant::Compact => Ok(limit),
+	};
+	let (x_max, y_max) = (range(width)?, range(height)?);
+	let pressure_max = range(caps.input_grid_pressure())?;
+
+	let time = match variant {
+		IsoVariant::Full => Channel::Time,
+		IsoVariant::Compact => Channel::TimeDifference,
+	};
+	let channels = [
+		(Channel::X, Description {
+			scaling: Some(resolution * f64::from(x_max) / f64::from(width.max(1))),
+			min: Some(0),
+			max: Some(x_max),
+			..Default::default()
+		}),
+		(Channel::Y, Description {
+			scaling: Some(resolution * f64::from(y_max) / f64::from(height.max(1))),
+			min: Some(0),
+			max: Some(y_max),
+			..Default::default()
+		}),
+		(time, Description {
+			scaling: Some(1000.0),
+			..Default::default()
+		}),
+		(Channel::Pressure, Description {
+			min: Some(0),
+			max: Some(pressure_max),
+			..Default::default()
+		}),
+		(Channel::TipSwitch, Description::default()),
+	];
+
+	let events = path.events().collect::<Vec<_>>();
+	let count = u16::try_from(events.len()).map_err(|_| IsoError::OutOfRange("samples"))?;
+
+	let mut record = Vec::new();
+	record.extend_from_slice(&FORMAT_IDENTIFIER);
+	record.extend_from_slice(&VERSION);
+	record.extend_from_slice(&[0; 4]);
+	record.push(1);
+
+	record.extend_from_slice(&channels.iter()
+		.fold(0u16, |inclusion, (channel, _)| inclusion | channel.bit())
+		.to_be_bytes());
+	for (_, description) in &channels {
+		description.write(&mut record);
+	}
+
+	record.extend_from_slice(&count.to_be_bytes());
+	let start = events.first().map(|event| event.time());
+	let mut previous = start;
+	for event in &events {
+		let milliseconds = |since: Option<Instant>| since
+			.map(|since| event.time().saturating_duration_since(since))
+			.unwrap_or_default()
+			.as_secs_f64() * 1000.0;
+		let time = match variant {
+			IsoVariant::Full => {
+				let time = milliseconds(start).round();
+				if time > f64::from(limit) {
+					return Err(IsoError::OutOfRange("time"))
+				}
+				time as u16
+			},
+			/* Long pauses are cut short, rather than making the record fail. */
+			IsoVariant::Compact => milliseconds(previous).round().min(f64::from(limit)) as u16,
+		};
+		previous = Some(event.time());
+
+		let values = [
+			(event.x() * f64::from(x_max)).round() as u16,
+			(event.y() * f64::from(y_max)).round() as u16,
+			time,
+			(event.pressure() * f64::from(pressure_max)).round() as u16,
+			u16::from(event.touching()),
+		];
+		for value in values.iter() {
+			match variant {
+				IsoVariant::Full => record.extend_from_slice(&value.to_be_bytes()),
+				IsoVariant::Compact => record.push(*value as u8),
+			}
+		}
+	}
+
+	/* No extended data. */
+	record.extend_from_slice(&0u16.to_be_bytes());
+
+	let length = u32::try_from(record.len()).map_err(|_| IsoError::OutOfRange("record"))?;
+	record[8..12].copy_from_slice(&length.to_be_bytes());
+
+	Ok(record)
+}
+
+/// Decodes the first signature in an ISO/IEC 19794-7 record of signature time
+/// series data into a path.
+///
+/// The variant the record is written in is told apart by the size of its
+/// samples, which is the one that makes the samples end where the extended
+/// data of the record starts.
+///
+/// Positions and pressures are normalized by the bounds given in the
+/// descriptions of their channels. The pen is taken to be touching the screen
+/// wherever the tip switch says so, or wherever there is pressure on it, for
+/// records with no tip switch channel. Times are counted from the moment the
+/// record was read.
+pub fn decode_iso19794(data: &[u8]) -> Result<EventPath, IsoError> {
+	let mut reader = Reader { data, position: 0 };
+	if reader.bytes(4)? != FORMAT_IDENTIFIER {
+		return Err(IsoError::NotIso19794)
+	}
+	let version = reader.bytes(4)?;
+	if version.iter().filter(|byte| byte.is_ascii_digit()).ne(b"10".iter()) {
+		return Err(IsoError::UnsupportedVersion)
+	}
+	if reader.u32()? as usize != data.len() {
+		return Err(IsoError::Truncated)
+	}
+	if reader.u8()? == 0 {
+		return Err(IsoError::NoSignatures)
+	}
+
+	let inclusion = reader.u16()?;
+	let mut channels = Vec::new();
+	for channel in CHANNELS.iter().copied() {
+		if inclusion & channel.bit() != 0 {
+			channels.push((channel, Description::read(&mut reader)?));
+		}
+	}
+
+	let description = |channel: Channel| channels.iter()
+		.find(|(included, _)| *included == channel)
+		.map(|(_, description)| *description);
+	let bounds = |channel: Channel, name: &'static str| {
+		let description = description(channel).ok_or(IsoError::MissingChannel(name))?;
+		let min = f64::from(description.min.unwrap_or(0));
+		let max = description.max
+			.map(f64::from)
+			.filter(|max| *max > min)
+			.ok_or(IsoError::MissingBounds(name))?;
+
+		Ok((min, max))
+	};
+	let x = bounds(Channel::X, "X")?;
+	let y = bounds(Channel::Y, "Y")?;
+	let pressure = bounds(Channel::Pressure, "pressure").ok();
+
+	/* Times are given in units per second, defaulting to milliseconds. */
+	let time_scale = |channel: Channel| description(channel)
+		.and_then(|description| description.scaling)
+		.filter(|scaling| *scaling > 0.0)
+		.unwrap_or(1000.0);
+
+	let count = reader.u16()?;
+	let variant = detect_variant(data, reader.position, count, channels.len())?;
+	let base = Instant::now();
+	let mut elapsed = 0.0;
+	let mut events = Vec::new();
+	for _ in 0..count {
+		let mut values = Vec::with_capacity(channels.len());
+		for (channel, _) in &channels {
+			let value = match variant {
+				IsoVariant::Full => reader.u16()?,
+				IsoVariant::Compact => u16::from(reader.u8()?),
+			};
+			values.push((*channel, f64::from(value)));
+		}
+		let value = |channel: Channel| values.iter()
+			.find(|(included, _)| *included == channel)
+			.map(|(_, value)| *value);
+		let normalize = |value: f64, (min, max): (f64, f64)| (value - min) / (max - min);
+
+		elapsed = match (value(Channel::Time), value(Channel::TimeDifference)) {
+			(Some(time), _) => time / time_scale(Channel::Time),
+			(None, Some(difference)) => elapsed + difference / time_scale(Channel::TimeDifference),
+			(None, None) => elapsed,
+		};
+		let force = match (value(Channel::Pressure), pressure) {
+			(Some(value), Some(bounds)) => normalize(value, bounds),
+			_ => 0.0
+		};
+		let touching = value(Channel::TipSwitch)
+			.map(|switch| switch != 0.0)
+			.unwrap_or(force > 0.0);
+
+		let time = Duration::try_from_secs_f64(elapsed.max(0.0))
+			.ok()
+			.and_then(|elapsed| base.checked_add(elapsed))
+			.ok_or(IsoError::OutOfRange("time"))?;
+
+		events.push(Event::new(
+			time,
+			normalize(value(Channel::X).unwrap_or(0.0), x),
+			normalize(value(Channel::Y).unwrap_or(0.0), y),
+			force,
+			touching,
+			true));
+	}
+
+	let extended = reader.u16()?;
+	reader.bytes(usize::from(extended))?;
+
+	Ok(events.into_iter().collect())
+}
+
+/// Tells the variant a record is written in, given the position its samples
+/// start at, their number and the number of channels in each of them.
+///
+/// Records with no samples read the same in either variant, and are taken to
+/// be in the full one.
+fn detect_variant(
+	data: &[u8],
+	start: usize,
+	count: u16,
+	channels: usize) -> Result<IsoVariant, IsoError> {
+
+	let fits = |size: usize| {
+		let end = usize::from(count)
+			.checked_mul(channels)
+			.and_then(|values| values.checked_mul(size))
+			.and_then(|length| start.checked_add(length));
+		let extended = end.and_then(|end| Some((end, data.get(end..end.checked_add(2)?)?)));
+
+		match extended {
+			Some((end, extended)) =>
+				end + 2 + usize::from(u16::from_be_bytes([extended[0], extended[1]])) == data.len(),
+			None => false
+		}
+	};
+
+	if fits(2) {
+		Ok(IsoVariant::Full)
+	} else if fits(1) {
+		Ok(IsoVariant::Compact)
+	} else {
+		Err(IsoError::Truncated)
+	}
+}
+
+/// A reader of big endian values off of a record.
+struct Reader<'a> {
+	/// The whole of the record.
+	data: &'a [u8],
+	/// The position of the next byte to be read.
+	position: usize,
+}
+impl<'a> Reader<'a> {
+	/// Reads the given number of bytes.
+	fn bytes(&mut self, count: usize) -> Result<&'a [u8], IsoError> {
+		let end = self.position.checked_add(count).ok_or(IsoError::Truncated)?;
+		let bytes = self.data.get(self.position..end).ok_or(IsoError::Truncated)?;
+		self.position = end;
+
+		Ok(bytes)
+	}
+
+	/// Reads a single byte.
+	fn u8(&mut self) -> Result<u8, IsoError> {
+		Ok(self.bytes(1)?[0])
+	}
+
+	/// Reads a two byte value.
+	fn u16(&mut self) -> Result<u16, IsoError> {
+		let bytes = self.bytes(2)?;
+		Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+	}
+
+	/// Reads a four byte value.
+	fn u32(&mut self) -> Result<u32, IsoError> {
+		let bytes = self.bytes(4)?;
+		Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+	}
+}
+
+/// Encodes a scaling value, as a five bit exponent of two, biased by sixteen,
+/// followed by the eleven bit fraction of the mantissa.
+fn encode_scaling(value: f64) -> u16 {
+	if value.is_nan() || value <= 0.0 {
+		return 0
+	}
+
+	let mut exponent = value.log2().floor() as i32;
+	let mut fraction = ((value / 2f64.powi(exponent) - 1.0) * 2048.0).round() as i32;
+	if fraction >= 2048 {
+		exponent += 1;
+		fraction = 0;
+	}
+
+	let exponent = (exponent + 16).clamp(0, 31) as u16;
+	(exponent << 11) | (fraction.clamp(0, 2047) as u16)
+}
+
+/// Decodes a scaling value written by [`encode_scaling()`].
+///
+/// [`encode_scaling()`]: encode_scaling
+fn decode_scaling(value: u16) -> f64 {
+	let exponent = i32::from(value >> 11) - 16;
+	let fraction = f64::from(value & 0x7ff) / 2048.0;
+
+	2f64.powi(exponent) * (1.0 + fraction)
+}
+
+/// Enumeration of the reasons for which a signature may fail to be written to
+/// or read from an ISO/IEC 19794-7 record.
+#[derive(Debug, thiserror::Error)]
+pub enum IsoError {
+	/// A value does not fit in the record.
+	#[error("the {0} does not fit in the record")]
+	OutOfRange(&'static str),
+	/// The data does not start with the format identifier of the standard.
+	#[error("not an ISO/IEC 19794-7 record")]
+	NotIso19794,
+	/// The record is of a version of the standard we can't read.
+	#[error("unsupported version of ISO/IEC 19794-7")]
+	UnsupportedVersion,
+	/// The record ends before all of its contents.
+	#[error("the record is truncated")]
+	Truncated,
+	/// The record holds no signatures.
+	#[error("the record holds no signatures")]
+	NoSignatures,
+	/// The record is missing a channel we need.
+	#[error("the record has no {0} channel")]
+	MissingChannel(&'static str),
+	/// The description of a channel is missing the bounds of its values.
+	#[error("the {0} channel has no bounds")]
+	MissingBounds(&'static str),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The capabilities of a device with the layout of the STU-530.
+	fn caps() -> Capability {
+		Capability::new((800, 480), (10800, 6480, 1023), 2540, 0)
+	}
+
+	/// A path with two strokes, each followed by the pen hovering away.
+	fn path() -> EventPath {
+		let start = Instant::now();
+		let mut events = Vec::new();
+		for stroke in 0..2 {
+			for i in 0..8 {
+				let touching = i < 6;
+				let k = stroke * 8 + i;
+				events.push(Event::new(
+					start + Duration::from_millis(k * 5),
+					0.1 + 0.05 * i as f64 + 0.4 * stroke as f64,
+					0.3 + 0.01 * (i * i) as f64,
+					if touching { 0.1 * (i + 1) as f64 } else { 0.0 },
+					touching,
+					true));
+			}
+		}
+
+		events.into_iter().collect()
+	}
+
+	/// The records the path is expected to be encoded as, in each variant.
+	const FIXTURES: [(IsoVariant, &[u8]); 2] = [
+		(IsoVariant::Full, include_bytes!("../../tests/fixtures/signature-full.sdi")),
+		(IsoVariant::Compact, include_bytes!("../../tests/fixtures/signature-compact.sdi")),
+	];
+
+	#[test]
+	fn records_match_fixtures() {
+		for (variant, fixture) in FIXTURES {
+			let record = encode_iso19794(&path(), &caps(), Transform::identity(), variant).unwrap();
+			assert!(record == fixture, "{:?} record does not match its fixture", variant);
+		}
+	}
+
+	#[test]
+	fn fixtures_round_trip() {
+		let path = path();
+		let expected = path.events().collect::<Vec<_>>();
+		for (variant, fixture) in FIXTURES {
+			/* Values are rounded to the pressure levels of the device, or to a
+			 * byte, in the compact variant. */
+			let tolerance = match variant {
+				IsoVariant::Full => 0.5 / 1023.0 + 1e-9,
+				IsoVariant::Compact => 0.5 / 255.0 + 1e-9,
+			};
+
+			let decoded = decode_iso19794(fixture).unwrap();
+			let found = decoded.events().collect::<Vec<_>>();
+			assert_eq!(expected.len(), found.len());
+
+			let start = (expected[0].time(), found[0].time());
+			for (a, b) in expected.iter().zip(&found) {
+				assert!((a.x() - b.x()).abs() <= tolerance, "{:?}: {:?} != {:?}", variant, a, b);
+				assert!((a.y() - b.y()).abs() <= tolerance, "{:?}: {:?} != {:?}", variant, a, b);
+				assert!((a.pressure() - b.pressure()).abs() <= tolerance, "{:?}: {:?} != {:?}", variant, a, b);
+				assert_eq!(a.touching(), b.touching());
+
+				let elapsed = |time: Instant, start: Instant| time.duration_since(start).as_secs_f64();
+				assert!((elapsed(a.time(), start.0) - elapsed(b.time(), start.1)).abs() < 1e-3);
+			}
+		}
+	}
+
+	#[test]
+	fn truncated_records_are_rejected() {
+		for (_, fixture) in FIXTURES {
+			for length in 0..fixture.len() {
+				assert!(decode_iso19794(&fixture[..length]).is_err(), "record cut at {} was accepted", length);
+			}
+		}
+	}
+}
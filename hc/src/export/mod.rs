@@ -0,0 +1,254 @@
+use std::convert::TryFrom;
+
+/// Encoding of signatures as raster images.
+mod raster;
+pub use raster::{ImageFormat, encode_image};
+
+/// Bilevel TIFF files, compressed with CCITT Group 4.
+mod tiff;
+
+/// Encoding of signatures as vector drawings.
+mod svg;
+pub use svg::{SvgOptions, encode_svg};
+
+/// Reading and writing of signatures as W3C InkML documents.
+mod inkml;
+pub use inkml::{InkMlError, encode_inkml, decode_inkml};
+
+/// Biometric signature records, as defined by ISO/IEC 19794-7.
+mod iso;
+pub use iso::{IsoVariant, IsoError, encode_iso19794, decode_iso19794};
+
+/// Reading and writing of the filtered samples of signatures as tables.
+mod samples;
+pub use samples::{
+	SampleOptions,
+	DeviceUnits,
+	SampleError,
+	encode_csv,
+	encode_json_lines,
+	decode_csv,
+	decode_json_lines
+};
+
+/// A compact binary container for the events in signatures.
+mod container;
+pub use container::{
+	ContainerHeader,
+	ContainerError,
+	ContainerReader,
+	write_container,
+	read_container
+};
+
+/// Tamper-evident packages binding signatures to the documents they sign.
+mod package;
+pub use package::{
+	DocumentHash,
+	CaptureMetadata,
+	SignaturePackage,
+	VerifiedPackage,
+	PackageError,
+	verify_package,
+	load_signing_key,
+	load_verifying_key,
+	hex
+};
+
+/// Encryption of signature files at rest.
+mod encryption;
+pub use encryption::{
+	EncryptionMethod,
+	EncryptionKey,
+	DecryptionKey,
+	EncryptionError,
+	load_key,
+	load_rsa_public_key,
+	load_rsa_private_key,
+	encrypt,
+	decrypt
+};
+
+/// The extension appended to the names of files that get encrypted.
+pub const ENCRYPTED_EXTENSION: &str = "hcenc";
+
+/// The formats signatures may be exported to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ExportFormat {
+	/// A raster image, in the given format.
+	Image(ImageFormat),
+	/// A Scalable Vector Graphics drawing.
+	Svg,
+	/// A W3C InkML document, which keeps all of the events in the signature.
+	InkMl,
+	/// An ISO/IEC 19794-7 record of signature time series data.
+	Iso19794,
+	/// The samples of the signature, as filtered while it was captured, as
+	/// comma separated values.
+	Csv,
+	/// The samples of the signature, as filtered while it was captured, as
+	/// JSON Lines.
+	JsonLines,
+	/// A compact binary container, which keeps all of the events in the
+	/// signature, rounded to the resolution of the device.
+	Container,
+	/// A sealed package binding the signature to the document it signs.
+	Package,
+	/// The descriptive features of the signature, as JSON.
+	Features,
+}
+impl ExportFormat {
+	/// All of the formats signatures may be exported to.
+	pub fn all() -> Vec<Self> {
+		ImageFormat::ALL
+			.iter()
+			.copied()
+			.map(Self::Image)
+			.chain([Self::Svg, Self::InkMl, Self::Iso19794, Self::Csv, Self::JsonLines, Self::Container, Self::Package, Self::Features].iter().copied())
+			.collect()
+	}
+
+	/// The name of this format, as shown to the user.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Image(format) => format.name(),
+			Self::Svg => "SVG",
+			Self::InkMl => "InkML",
+			Self::Iso19794 => "ISO/IEC 19794-7",
+			Self::Csv => "CSV",
+			Self::JsonLines => "JSON Lines",
+			Self::Container => "Signature Container",
+			Self::Package => "Signature Package",
+			Self::Features => "Signature Features",
+		}
+	}
+
+	/// The usual extension of files in this format.
+	pub fn extension(self) -> &'static str {
+		match self {
+			Self::Image(format) => format.extension(),
+			Self::Svg => "svg",
+			Self::InkMl => "inkml",
+			Self::Iso19794 => "sdi",
+			Self::Csv => "csv",
+			Self::JsonLines => "jsonl",
+			Self::Container => "hcsig",
+			Self::Package => "hcpkg",
+			Self::Features => "json",
+		}
+	}
+
+	/// The format of files with the given extension, if any.
+	pub fn from_extension(extension: &str) -> Option<Self> {
+		match extension.to_ascii_lowercase().as_str() {
+			"svg" => Some(Self::Svg),
+			"inkml" | "ink" => Some(Self::InkMl),
+			"sdi" => Some(Self::Iso19794),
+			"csv" => Some(Self::Csv),
+			"jsonl" | "ndjson" => Some(Self::JsonLines),
+			"hcsig" => Some(Self::Container),
+			"hcpkg" => Some(Self::Package),
+			"json" => Some(Self::Features),
+			extension => ImageFormat::from_extension(extension).map(Self::Image)
+		}
+	}
+}
+
+/// Enumeration of the reasons for which a signature may fail to be exported.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+	/// The image could not be encoded.
+	#[error("could not encode image: {0}")]
+	Image(image::ImageError),
+	/// The signature could not be written as an ISO/IEC 19794-7 record.
+	#[error("could not encode ISO/IEC 19794-7 record: {0}")]
+	Iso(IsoError),
+	/// The signature could not be packaged.
+	#[error("could not package signature: {0}")]
+	Package(PackageError),
+	/// The signature could not be encrypted.
+	#[error("could not encrypt signature: {0}")]
+	Encryption(EncryptionError),
+	/// The user has cancelled the operation.
+	#[error("the operation was cancelled")]
+	Cancelled,
+	/// The exported file could not be written.
+	#[error("could not write file: {0}")]
+	Io(std::io::Error),
+}
+
+/// Enumeration of the reasons for which a signature may fail to be imported.
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+	/// The file could not be read.
+	#[error("could not read file: {0}")]
+	Io(std::io::Error),
+	/// The file is not a valid InkML document.
+	#[error("could not read InkML document: {0}")]
+	InkMl(InkMlError),
+	/// The file is not a valid ISO/IEC 19794-7 record.
+	#[error("could not read ISO/IEC 19794-7 record: {0}")]
+	Iso(IsoError),
+	/// The file does not hold valid samples.
+	#[error("could not read samples: {0}")]
+	Samples(SampleError),
+	/// The file is not a valid signature container.
+	#[error("could not read signature container: {0}")]
+	Container(ContainerError),
+	/// The file is not a valid signature package.
+	#[error("could not verify signature package: {0}")]
+	Package(PackageError),
+	/// The file could not be decrypted.
+	#[error("could not decrypt file: {0}")]
+	Encryption(EncryptionError),
+	/// No key for decrypting the file has been set up.
+	#[error("no key has been set up for decrypting files encrypted with the {0:?} method")]
+	MissingKey(EncryptionMethod),
+	/// The user has cancelled the operation.
+	#[error("the operation was cancelled")]
+	Cancelled,
+}
+
+/// Formats a number with up to three decimal places, with no trailing zeroes,
+/// so that the same number is always written the same way.
+fn number(value: f64) -> String {
+	let formatted = format!("{:.3}", value);
+	let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+
+	match formatted {
+		"-0" | "" => "0".to_owned(),
+		_ => formatted.to_owned()
+	}
+}
+
+/// Reads a 256-bit key from a file, holding either the 32 bytes of the key or
+/// the same bytes as hexadecimal digits.
+fn key_bytes(data: &[u8]) -> Option<[u8; 32]> {
+	if let Ok(bytes) = <[u8; 32]>::try_from(data) {
+		return Some(bytes)
+	}
+
+	let text = std::str::from_utf8(data).ok()?.trim();
+	if text.len() != 64 || !text.is_ascii() {
+		return None
+	}
+
+	let mut bytes = [0; 32];
+	for (byte, digits) in bytes.iter_mut().zip(text.as_bytes().chunks(2)) {
+		*byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+	}
+
+	Some(bytes)
+}
+
+/// Takes the given number of bytes from the front of the given data, if it
+/// has that many.
+fn take<'a>(data: &mut &'a [u8], length: usize) -> Option<&'a [u8]> {
+	if data.len() < length {
+		return None
+	}
+	let (taken, rest) = data.split_at(length);
+	*data = rest;
+
+	Some(taken)
+}
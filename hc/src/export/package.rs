@@ -0,0 +1,438 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer};
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::time::{Instant, SystemTime};
+use stu::Information;
+use crate::path::EventPath;
+use crate::export::{ContainerHeader, ContainerError, write_container, read_container};
+use crate::export::{key_bytes, take};
+
+/// The bytes every package starts with.
+const MAGIC: [u8; 4] = *b"HCSP";
+
+/// The version of the format packages are written in.
+const VERSION: u8 = 1;
+
+/// The length of the integrity hash of a package, in bytes.
+const HASH_LENGTH: usize = 32;
+
+/// The length of the public key of the signer of a package, in bytes.
+const KEY_LENGTH: usize = 32;
+
+/// The length of the signature of a package, in bytes.
+const SIGNATURE_LENGTH: usize = 64;
+
+/// The hash of the document a signature was made over, as computed by
+/// whoever asked for the signature.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DocumentHash {
+	/// The name of the algorithm the hash was computed with.
+	pub algorithm: String,
+	/// The hash, as hexadecimal digits.
+	pub digest: String,
+}
+impl DocumentHash {
+	/// The SHA-256 hash of the given document.
+	pub fn sha256(document: &[u8]) -> Self {
+		Self {
+			algorithm: "SHA-256".to_owned(),
+			digest: hex(&Sha256::digest(document)),
+		}
+	}
+}
+
+/// The circumstances under which a signature was captured.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct CaptureMetadata {
+	/// The vendor identification number of the device.
+	pub vendor: u16,
+	/// The product identification number of the device.
+	pub product: u16,
+	/// The device identification number of the device.
+	pub device: u16,
+	/// The moment the first event of the signature happened at, if it has
+	/// any events.
+	pub started: Option<SystemTime>,
+	/// The moment the last event of the signature happened at, if it has any
+	/// events.
+	pub finished: Option<SystemTime>,
+	/// The moment the signature was packaged.
+	pub packaged: SystemTime,
+	/// The hash of the document that was signed.
+	pub document: DocumentHash,
+}
+impl CaptureMetadata {
+	/// The metadata of the given signature, captured by the device with the
+	/// given information, over the document with the given hash, packaged now.
+	pub fn new(info: &Information, path: &EventPath, document: DocumentHash) -> Self {
+		/* Events are timed by the monotonic clock, which we translate to the
+		 * wall clock by way of the current time in both. */
+		let now = (Instant::now(), SystemTime::now());
+		let wall = |instant: Instant| now.1
+			.checked_sub(now.0.saturating_duration_since(instant))
+			.unwrap_or(now.1);
+
+		Self {
+			vendor: info.vendor(),
+			product: info.product(),
+			device: info.device(),
+			started: path.events().next().map(|event| wall(event.time())),
+			finished: path.events().last().map(|event| wall(event.time())),
+			packaged: now.1,
+			document,
+		}
+	}
+}
+
+/// A signature bundled with everything needed to tell what it was captured
+/// for, which can be sealed against modification.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SignaturePackage {
+	/// The circumstances under which the signature was captured.
+	pub metadata: CaptureMetadata,
+	/// The events of the signature, as a signature container.
+	pub events: Vec<u8>,
+	/// The rendering of the signature, as a PNG image.
+	pub image: Vec<u8>,
+}
+impl SignaturePackage {
+	/// Bundles the given signature with its rendering, in PNG format, and the
+	/// metadata of its capture.
+	pub fn new(
+		metadata: CaptureMetadata,
+		header: &ContainerHeader,
+		path: &EventPath,
+		image: Vec<u8>) -> Self {
+
+		let mut events = Vec::new();
+		write_container(&mut events, header, path)
+			.expect("writing to memory never fails");
+
+		Self { metadata, events, image }
+	}
+
+	/// The events of the signature.
+	pub fn path(&self) -> Result<EventPath, ContainerError> {
+		read_container(&self.events[..]).map(|(_, path)| path)
+	}
+
+	/// Seals the package, optionally signing it with the given key.
+	///
+	/// A sealed package starts with the `HCSP` magic bytes and the version of
+	/// its format, followed by the metadata, as JSON, the events and the image,
+	/// each of them preceded by its length as a little endian 32-bit integer.
+	/// Then comes the SHA-256 hash of everything before it, which seals the
+	/// package, and a single byte telling whether the package is signed. When
+	/// it is, the byte is followed by the Ed25519 public key of the signer and
+	/// its signature over the hash.
+	///
+	/// The hash only shows the package was not modified by accident, as anyone
+	/// may compute it anew after modifying the package. Only the signature ties
+	/// the package to whoever holds the key.
+	pub fn seal(&self, key: Option<&SigningKey>) -> Vec<u8> {
+		let metadata = serde_json::to_vec(&self.metadata)
+			.expect("metadata is always valid JSON");
+
+		let mut data = Vec::new();
+		data.extend_from_slice(&MAGIC);
+		data.push(VERSION);
+		for section in [&metadata[..], &self.events[..], &self.image[..]] {
+			let length = u32::try_from(section.len())
+				.expect("sections are never over 4 GiB long");
+			data.extend_from_slice(&length.to_le_bytes());
+			data.extend_from_slice(section);
+		}
+
+		let hash = Sha256::digest(&data);
+		data.extend_from_slice(&hash);
+		match key {
+			Some(key) => {
+				data.push(1);
+				data.extend_from_slice(key.verifying_key().as_bytes());
+				data.extend_from_slice(&key.sign(&hash).to_bytes());
+			},
+			None => data.push(0)
+		}
+
+		data
+	}
+}
+
+/// A package whose seal has been verified.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerifiedPackage {
+	/// The contents of the package.
+	pub package: SignaturePackage,
+	/// The public key of whoever signed the package, if it is signed.
+	pub signer: Option<VerifyingKey>,
+	/// Whether the package was signed with the trusted key. Packages that are
+	/// not only show they were not modified by accident, as anyone may have
+	/// sealed or signed them.
+	pub trusted: bool,
+}
+
+/// Verifies a sealed package and reads its contents, failing if any part of
+/// it has been modified since it was sealed.
+///
+/// When a trusted key is given, the package must have been signed with it.
+/// Otherwise, packages signed with any key are accepted, as long as their
+/// signature is valid, and so are packages that are not signed at all, in
+/// which case the seal only guards against accidental modification.
+pub fn verify_package(
+	data: &[u8],
+	trusted: Option<&VerifyingKey>) -> Result<VerifiedPackage, PackageError> {
+
+	let mut reader = data;
+	if take(&mut reader, MAGIC.len()).ok_or(PackageError::Truncated)? != MAGIC {
+		return Err(PackageError::NotPackage)
+	}
+	let version = take(&mut reader, 1).ok_or(PackageError::Truncated)?[0];
+	if version != VERSION {
+		return Err(PackageError::UnsupportedVersion(version))
+	}
+	let mut sections = [&[][..]; 3];
+	for section in &mut sections {
+		let length = take(&mut reader, 4).ok_or(PackageError::Truncated)?;
+		let length = u32::from_le_bytes(<[u8; 4]>::try_from(length).expect("four bytes were taken"));
+		*section = take(&mut reader, length as usize).ok_or(PackageError::Truncated)?;
+	}
+	let body = &data[..data.len() - reader.len()];
+
+	let hash = take(&mut reader, HASH_LENGTH).ok_or(PackageError::Truncated)?;
+	if hash != &Sha256::digest(body)[..] {
+		return Err(PackageError::Modified)
+	}
+
+	let signer = match take(&mut reader, 1).ok_or(PackageError::Truncated)?[0] {
+		0 => None,
+		1 => {
+			let key = take(&mut reader, KEY_LENGTH).ok_or(PackageError::Truncated)?;
+			let key = <[u8; KEY_LENGTH]>::try_from(key).expect("a whole key was taken");
+			let signature = take(&mut reader, SIGNATURE_LENGTH).ok_or(PackageError::Truncated)?;
+			let signature = <[u8; SIGNATURE_LENGTH]>::try_from(signature)
+				.expect("a whole signature was taken");
+
+			let key = VerifyingKey::from_bytes(&key)
+				.map_err(|_| PackageError::InvalidSignature)?;
+			key.verify_strict(hash, &Signature::from_bytes(&signature))
+				.map_err(|_| PackageError::InvalidSignature)?;
+
+			Some(key)
+		},
+		_ => return Err(PackageError::Modified)
+	};
+	if !reader.is_empty() {
+		return Err(PackageError::Modified)
+	}
+
+	if let Some(trusted) = trusted {
+		match signer {
+			Some(signer) if signer == *trusted => {},
+			Some(_) => return Err(PackageError::UntrustedSigner),
+			None => return Err(PackageError::Unsigned),
+		}
+	}
+
+	let [metadata, events, image] = sections;
+	Ok(VerifiedPackage {
+		package: SignaturePackage {
+			metadata: serde_json::from_slice(metadata).map_err(PackageError::Metadata)?,
+			events: events.to_vec(),
+			image: image.to_vec(),
+		},
+		signer,
+		trusted: trusted.is_some(),
+	})
+}
+
+/// Reads an Ed25519 signing key from a file, holding either the 32 bytes of the
+/// key or the same bytes as hexadecimal digits.
+pub fn load_signing_key(data: &[u8]) -> Result<SigningKey, PackageError> {
+	key_bytes(data)
+		.map(|bytes| SigningKey::from_bytes(&bytes))
+		.ok_or(PackageError::InvalidKey)
+}
+
+/// Reads an Ed25519 public key from a file, holding either the 32 bytes of the
+/// key or the same bytes as hexadecimal digits.
+pub fn load_verifying_key(data: &[u8]) -> Result<VerifyingKey, PackageError> {
+	key_bytes(data)
+		.and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+		.ok_or(PackageError::InvalidKey)
+}
+
+/// Formats the given bytes as lowercase hexadecimal digits.
+pub fn hex(bytes: &[u8]) -> String {
+	let mut hex = String::with_capacity(bytes.len() * 2);
+	for byte in bytes {
+		let _ = write!(hex, "{:02x}", byte);
+	}
+
+	hex
+}
+
+/// Enumeration of the reasons for which a signature package may fail to be
+/// verified.
+#[derive(Debug, thiserror::Error)]
+pub enum PackageError {
+	/// The data does not start with the magic bytes of a package.
+	#[error("not a signature package")]
+	NotPackage,
+	/// The package is in a version of the format we can't read.
+	#[error("unsupported package version: {0}")]
+	UnsupportedVersion(u8),
+	/// The package ends before all of its contents.
+	#[error("the package is truncated")]
+	Truncated,
+	/// The contents of the package do not match its seal.
+	#[error("the package has been modified since it was sealed")]
+	Modified,
+	/// The signature of the package is not valid.
+	#[error("the signature of the package is not valid")]
+	InvalidSignature,
+	/// The package was signed, but not with the trusted key.
+	#[error("the package was not signed with the trusted key")]
+	UntrustedSigner,
+	/// The package was required to be signed, but it is not.
+	#[error("the package is not signed")]
+	Unsigned,
+	/// The metadata of the package could not be read.
+	#[error("invalid package metadata: {0}")]
+	Metadata(serde_json::Error),
+	/// The signing or the trusted key is not a valid Ed25519 key.
+	#[error("invalid Ed25519 key")]
+	InvalidKey,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A package with a short section of each kind.
+	fn package() -> SignaturePackage {
+		SignaturePackage {
+			metadata: CaptureMetadata {
+				vendor: 0x056a,
+				product: 0x00a5,
+				device: 0x0100,
+				started: Some(SystemTime::UNIX_EPOCH),
+				finished: Some(SystemTime::UNIX_EPOCH),
+				packaged: SystemTime::UNIX_EPOCH,
+				document: DocumentHash::sha256(b"document"),
+			},
+			events: b"events".to_vec(),
+			image: b"image".to_vec(),
+		}
+	}
+
+	/// A signing key made from the given byte.
+	fn key(seed: u8) -> SigningKey {
+		SigningKey::from_bytes(&[seed; 32])
+	}
+
+	/// The ranges of each of the parts of a signed package, by their names.
+	fn parts(data: &[u8]) -> Vec<(&'static str, std::ops::Range<usize>)> {
+		let mut parts = Vec::new();
+		let mut start = MAGIC.len() + 1;
+		for name in ["metadata", "events", "image"] {
+			let length = u32::from_le_bytes(<[u8; 4]>::try_from(&data[start..start + 4]).unwrap());
+			parts.push((name, start + 4..start + 4 + length as usize));
+			start += 4 + length as usize;
+		}
+		parts.push(("hash", start..start + HASH_LENGTH));
+		start += HASH_LENGTH + 1;
+		parts.push(("key", start..start + KEY_LENGTH));
+		start += KEY_LENGTH;
+		parts.push(("signature", start..start + SIGNATURE_LENGTH));
+		assert_eq!(start + SIGNATURE_LENGTH, data.len());
+
+		parts
+	}
+
+	#[test]
+	fn packages_signed_with_the_trusted_key_are_trusted() {
+		let data = package().seal(Some(&key(1)));
+
+		let verified = verify_package(&data, Some(&key(1).verifying_key())).unwrap();
+		assert_eq!(verified.package, package());
+		assert_eq!(verified.signer, Some(key(1).verifying_key()));
+		assert!(verified.trusted);
+
+		let verified = verify_package(&data, None).unwrap();
+		assert_eq!(verified.signer, Some(key(1).verifying_key()));
+		assert!(!verified.trusted);
+	}
+
+	#[test]
+	fn flipping_a_byte_in_any_part_is_caught() {
+		let data = package().seal(Some(&key(1)));
+		for (name, range) in parts(&data) {
+			for position in [range.start, range.end - 1] {
+				let mut modified = data.clone();
+				modified[position] ^= 0x01;
+
+				for trusted in [None, Some(key(1).verifying_key())] {
+					match verify_package(&modified, trusted.as_ref()) {
+						Err(PackageError::Modified) | Err(PackageError::InvalidSignature) => {},
+						other => panic!("flip in {} at {} gave {:?}", name, position, other)
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn stripped_signatures_are_not_trusted() {
+		let data = package().seal(Some(&key(1)));
+		let hash = parts(&data).into_iter()
+			.find(|(name, _)| *name == "hash")
+			.map(|(_, range)| range)
+			.unwrap();
+
+		let mut stripped = data[..hash.end].to_vec();
+		stripped.push(0);
+
+		assert!(matches!(
+			verify_package(&stripped, Some(&key(1).verifying_key())),
+			Err(PackageError::Unsigned)));
+
+		let verified = verify_package(&stripped, None).unwrap();
+		assert_eq!(verified.signer, None);
+		assert!(!verified.trusted);
+	}
+
+	#[test]
+	fn packages_signed_with_other_keys_are_not_trusted() {
+		let data = package().seal(Some(&key(1)));
+		let hash = parts(&data).into_iter()
+			.find(|(name, _)| *name == "hash")
+			.map(|(_, range)| range)
+			.unwrap();
+
+		/* Sign the same package anew, with a key of our own. */
+		let other = key(2);
+		let mut resigned = data[..hash.end].to_vec();
+		resigned.push(1);
+		resigned.extend_from_slice(other.verifying_key().as_bytes());
+		resigned.extend_from_slice(&other.sign(&data[hash]).to_bytes());
+		assert_eq!(resigned, package().seal(Some(&other)));
+
+		assert!(matches!(
+			verify_package(&resigned, Some(&key(1).verifying_key())),
+			Err(PackageError::UntrustedSigner)));
+
+		let verified = verify_package(&resigned, None).unwrap();
+		assert_eq!(verified.signer, Some(other.verifying_key()));
+		assert!(!verified.trusted);
+	}
+
+	#[test]
+	fn verifying_keys_load_from_hex() {
+		let key = key(1).verifying_key();
+		assert_eq!(load_verifying_key(hex(key.as_bytes()).as_bytes()).unwrap(), key);
+		assert_eq!(load_verifying_key(key.as_bytes()).unwrap(), key);
+		assert!(matches!(load_verifying_key(b"not a key"), Err(PackageError::InvalidKey)));
+	}
+}
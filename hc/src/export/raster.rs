@@ -0,0 +1,249 @@
+use serde::{Serialize, Deserialize};
+use crate::path::EventCanvas;
+use crate::export::ExportError;
+
+/// The formats signatures may be exported to as raster images.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFormat {
+	/// Uncompressed, 24-bit Windows bitmap.
+	Bmp,
+	/// 32-bit PNG, which keeps the transparency of the background.
+	#[default]
+	Png,
+	/// Bilevel TIFF, compressed with CCITT Group 4, as used for archiving.
+	Tiff,
+	/// 24-bit JPEG.
+	Jpeg,
+}
+impl ImageFormat {
+	/// All of the image formats.
+	pub const ALL: [Self; 4] = [Self::Png, Self::Tiff, Self::Bmp, Self::Jpeg];
+
+	/// The name of this format, as shown to the user.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Bmp => "BMP",
+			Self::Png => "PNG",
+			Self::Tiff => "TIFF",
+			Self::Jpeg => "JPEG",
+		}
+	}
+
+	/// The usual extension of files in this format.
+	pub fn extension(self) -> &'static str {
+		match self {
+			Self::Bmp => "bmp",
+			Self::Png => "png",
+			Self::Tiff => "tif",
+			Self::Jpeg => "jpg",
+		}
+	}
+
+	/// The format of files with the given extension, if any.
+	pub fn from_extension(extension: &str) -> Option<Self> {
+		match extension.to_ascii_lowercase().as_str() {
+			"bmp" | "dib" => Some(Self::Bmp),
+			"png" => Some(Self::Png),
+			"tif" | "tiff" => Some(Self::Tiff),
+			"jpg" | "jpeg" => Some(Self::Jpeg),
+			_ => None
+		}
+	}
+
+	/// Whether images in this format may have a transparent background.
+	pub fn supports_transparency(self) -> bool {
+		self == Self::Png
+	}
+}
+
+/// The quality JPEG images are encoded with, from 1 to 100.
+const JPEG_QUALITY: u8 = 90;
+
+/// Encodes the picture in the given canvas as an image file in the given
+/// format, tagged with the given resolution, in dots per inch.
+///
+/// When a transparent background is requested, the background color of the
+/// canvas is left out of the image, and the ink is kept with the opacity given
+/// by its coverage. This only applies to formats that support transparency,
+/// with all other formats having the picture blended over white instead.
+/// Bilevel formats keep only the pixels that are mostly covered by ink.
+pub fn encode_image(
+	canvas: &EventCanvas,
+	format: ImageFormat,
+	dpi: f64,
+	transparent: bool) -> Result<Vec<u8>, ExportError> {
+
+	let transparent = transparent && format.supports_transparency();
+	let mut buffer = Vec::new();
+
+	match format {
+		ImageFormat::Bmp => {
+			let image = canvas.to_rgb();
+			image::codecs::bmp::BmpEncoder::new(&mut buffer)
+				.encode(image.as_raw(), image.width(), image.height(), image::ColorType::Rgb8)
+				.map_err(ExportError::Image)?;
+
+			/* Fill in the pixels per meter in the info header. */
+			if buffer.len() >= 46 && buffer.starts_with(b"BM") {
+				let ppm = pixels_per_meter(dpi).to_le_bytes();
+				buffer[38..42].copy_from_slice(&ppm);
+				buffer[42..46].copy_from_slice(&ppm);
+			}
+		},
+		ImageFormat::Png => {
+			let image = canvas.to_rgba(transparent);
+			image::codecs::png::PngEncoder::new(&mut buffer)
+				.encode(image.as_raw(), image.width(), image.height(), image::ColorType::Rgba8)
+				.map_err(ExportError::Image)?;
+
+			/* Add a physical pixel dimensions chunk, right after the header
+			 * chunk, which always comes first and has a fixed length. */
+			let ppm = pixels_per_meter(dpi).to_be_bytes();
+			let mut chunk = b"pHYs".to_vec();
+			chunk.extend_from_slice(&ppm);
+			chunk.extend_from_slice(&ppm);
+			chunk.push(1);
+
+			let mut phys = 9u32.to_be_bytes().to_vec();
+			phys.extend_from_slice(&chunk);
+			phys.extend_from_slice(&crc32(&chunk).to_be_bytes());
+
+			buffer.splice(33..33, phys);
+		},
+		ImageFormat::Tiff => {
+			buffer = crate::export::tiff::encode_g4(
+				canvas.width(),
+				canvas.height(),
+				dpi,
+				|x, y| canvas.get(x, y).unwrap_or(false));
+		},
+		ImageFormat::Jpeg => {
+			let image = canvas.to_rgb();
+			image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY)
+				.encode(image.as_raw(), image.width(), image.height(), image::ColorType::Rgb8)
+				.map_err(ExportError::Image)?;
+
+			/* Fill in the density in the JFIF header. */
+			if buffer.len() >= 18 && buffer[2..4] == [0xff, 0xe0] && &buffer[6..11] == b"JFIF\0" {
+				let dpi = (dpi.round().max(1.0).min(f64::from(u16::MAX)) as u16).to_be_bytes();
+				buffer[13] = 1;
+				buffer[14..16].copy_from_slice(&dpi);
+				buffer[16..18].copy_from_slice(&dpi);
+			}
+		}
+	}
+
+	Ok(buffer)
+}
+
+/// Converts a resolution in dots per inch to pixels per meter.
+fn pixels_per_meter(dpi: f64) -> u32 {
+	(dpi / 0.0254).round().max(0.0).min(f64::from(i32::MAX)) as u32
+}
+
+/// The CRC-32 checksum of the given data, as used by PNG.
+fn crc32(data: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for byte in data {
+		crc ^= u32::from(*byte);
+		for _ in 0..8 {
+			crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+		}
+	}
+
+	!crc
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::path::CanvasStyle;
+	use stu::Event;
+	use std::time::Instant;
+
+	/// A small canvas with a loop of ink going through it, in the default
+	/// style.
+	fn canvas() -> EventCanvas {
+		let time = Instant::now();
+		let mut canvas = EventCanvas::with_style(48, 24, CanvasStyle::default());
+		for i in 0..=32 {
+			let t = f64::from(i) / 32.0 * std::f64::consts::TAU;
+			canvas.process(Event::new(
+				time,
+				0.5 + 0.4 * t.cos(),
+				0.5 + 0.3 * (2.0 * t).sin(),
+				0.2 + 0.8 * f64::from(i) / 32.0,
+				true,
+				true));
+		}
+
+		canvas
+	}
+
+	/// The image files the canvas is expected to be encoded as, in each of
+	/// the formats, at each of the resolutions.
+	const FIXTURES: [(ImageFormat, f64, &[u8]); 8] = [
+		(ImageFormat::Png, 96.0, include_bytes!("../../tests/fixtures/canvas-96dpi.png")),
+		(ImageFormat::Png, 300.0, include_bytes!("../../tests/fixtures/canvas-300dpi.png")),
+		(ImageFormat::Bmp, 96.0, include_bytes!("../../tests/fixtures/canvas-96dpi.bmp")),
+		(ImageFormat::Bmp, 300.0, include_bytes!("../../tests/fixtures/canvas-300dpi.bmp")),
+		(ImageFormat::Jpeg, 96.0, include_bytes!("../../tests/fixtures/canvas-96dpi.jpg")),
+		(ImageFormat::Jpeg, 300.0, include_bytes!("../../tests/fixtures/canvas-300dpi.jpg")),
+		(ImageFormat::Tiff, 96.0, include_bytes!("../../tests/fixtures/canvas-96dpi.tif")),
+		(ImageFormat::Tiff, 300.0, include_bytes!("../../tests/fixtures/canvas-300dpi.tif")),
+	];
+
+	#[test]
+	fn images_match_golden_fixtures() {
+		let canvas = canvas();
+		for (format, dpi, fixture) in FIXTURES.iter() {
+			let image = encode_image(&canvas, *format, *dpi, false).unwrap();
+			assert!(image == *fixture, "the {} image at {} dpi changed", format.name(), dpi);
+		}
+	}
+
+	#[test]
+	fn images_decode_to_the_canvas() {
+		let canvas = canvas();
+		for format in [ImageFormat::Png, ImageFormat::Bmp].iter().copied() {
+			let image = encode_image(&canvas, format, 300.0, false).unwrap();
+			let decoded = image::load_from_memory(&image).unwrap().to_rgb8();
+
+			assert!(decoded == canvas.to_rgb(), "the {} image has other pixels", format.name());
+		}
+
+		let image = encode_image(&canvas, ImageFormat::Png, 300.0, true).unwrap();
+		let decoded = image::load_from_memory(&image).unwrap().to_rgba8();
+		assert!(decoded == canvas.to_rgba(true));
+	}
+
+	#[test]
+	fn images_carry_their_resolution() {
+		let canvas = canvas();
+		for dpi in [96.0, 300.0].iter().copied() {
+			let ppm = pixels_per_meter(dpi);
+
+			let png = encode_image(&canvas, ImageFormat::Png, dpi, false).unwrap();
+			let phys = png.windows(4).position(|window| window == b"pHYs").unwrap();
+			assert_eq!(&png[phys + 4..phys + 8], &ppm.to_be_bytes());
+			assert_eq!(&png[phys + 8..phys + 12], &ppm.to_be_bytes());
+			assert_eq!(png[phys + 12], 1);
+
+			let bmp = encode_image(&canvas, ImageFormat::Bmp, dpi, false).unwrap();
+			assert_eq!(&bmp[38..42], &ppm.to_le_bytes());
+			assert_eq!(&bmp[42..46], &ppm.to_le_bytes());
+
+			let jpeg = encode_image(&canvas, ImageFormat::Jpeg, dpi, false).unwrap();
+			assert_eq!(jpeg[13], 1);
+			assert_eq!(&jpeg[14..16], &(dpi as u16).to_be_bytes());
+			assert_eq!(&jpeg[16..18], &(dpi as u16).to_be_bytes());
+		}
+	}
+
+	#[test]
+	fn png_checksums_are_valid() {
+		/* The check value from the PNG specification. */
+		assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+	}
+}
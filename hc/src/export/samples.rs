@@ -0,0 +1,412 @@
+use serde::{Serialize, Deserialize};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use stu::{Event, Capability, Transform};
+use crate::path::EventPath;
+use crate::export::number;
+
+/// The parameters of the exporting of the samples of signatures.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SampleOptions {
+	/// Whether every sample is tagged with the index of the ink stroke it is
+	/// part of, counting from zero. Samples of the pen moving between strokes
+	/// are left untagged.
+	pub stroke_index: bool,
+}
+
+/// The units of the input grid of a device, which the grid values of the
+/// samples are scaled to. The grid values are the filtered, normalized values
+/// scaled back up, not the values the device reported.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct DeviceUnits {
+	/// The width of the input grid, as seen in the view.
+	pub(super) width: u32,
+	/// The height of the input grid, as seen in the view.
+	pub(super) height: u32,
+	/// The number of pressure levels of the device.
+	pub(super) pressure: u32,
+}
+impl DeviceUnits {
+	/// The units of a device with the given capabilities, viewed in the given
+	/// orientation.
+	pub fn new(caps: &Capability, transform: Transform) -> Self {
+		let (width, height) = transform.dimensions(
+			caps.input_grid_width(),
+			caps.input_grid_height());
+
+		Self { width, height, pressure: caps.input_grid_pressure() }
+	}
+}
+
+/// A single sample, as written to and read from files.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+struct Sample {
+	/// The time of the sample, in milliseconds since the first sample.
+	time: f64,
+	/// The horizontal position of the pen, in normalized coordinates.
+	x: f64,
+	/// The vertical position of the pen, in normalized coordinates.
+	y: f64,
+	/// The pressure of the pen on the screen, in normalized coordinates.
+	#[serde(default)]
+	pressure: f64,
+	/// Whether the pen was touching the screen.
+	#[serde(default)]
+	touching: bool,
+	/// Whether the pen was in proximity of the screen.
+	#[serde(default = "hovering_default")]
+	hovering: bool,
+	/// The horizontal position of the pen, in units of the input grid, as seen
+	/// in the view.
+	///
+	/// Like the other grid values, this is the normalized value scaled back up
+	/// to the grid, rather than the value reported by the device, so it holds
+	/// the position after the filters of the path were applied to it.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	grid_x: Option<u32>,
+	/// The vertical position of the pen, in units of the input grid, as seen
+	/// in the view, scaled from the filtered position.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	grid_y: Option<u32>,
+	/// The pressure of the pen, in pressure levels of the device, scaled from
+	/// the filtered pressure.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	grid_pressure: Option<u32>,
+	/// The index of the ink stroke this sample is part of.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	stroke: Option<usize>,
+}
+
+/// Samples read from files are taken to be in proximity of the screen, unless
+/// they say otherwise.
+fn hovering_default() -> bool {
+	true
+}
+
+/// Turns all of the events in the given path into samples.
+fn samples(
+	path: &EventPath,
+	units: Option<DeviceUnits>,
+	options: &SampleOptions) -> Vec<Sample> {
+
+	let start = path.events().next().map(Event::time);
+	let grid = |value: f64, range: fn(&DeviceUnits) -> u32| units
+		.map(|units| (value * f64::from(range(&units))).round() as u32);
+	let mut stroke = 0;
+
+	path.segments()
+		.into_iter()
+		.flat_map(|segment| {
+			let index = if segment.is_ink() {
+				stroke += 1;
+				Some(stroke - 1)
+			} else {
+				None
+			};
+
+			segment.events()
+				.iter()
+				.map(|event| Sample {
+					time: start
+						.map(|start| event.time().saturating_duration_since(start))
+						.unwrap_or_default()
+						.as_secs_f64() * 1000.0,
+					x: event.x(),
+					y: event.y(),
+					pressure: event.pressure(),
+					touching: event.touching(),
+					hovering: event.hovering(),
+					grid_x: grid(event.x(), |units| units.width),
+					grid_y: grid(event.y(), |units| units.height),
+					grid_pressure: grid(event.pressure(), |units| units.pressure),
+					stroke: index.filter(|_| options.stroke_index),
+				})
+				.collect::<Vec<_>>()
+		})
+		.collect()
+}
+
+/// Rebuilds a path from samples, given along with the lines they were read
+/// from, with their times counted from the moment they were read.
+///
+/// Samples whose times are too far apart for the clock to hold are not valid.
+fn path(samples: &[(usize, Sample)]) -> Result<EventPath, SampleError> {
+	let first = samples.iter()
+		.map(|(_, sample)| sample.time)
+		.fold(f64::INFINITY, f64::min);
+	let base = Instant::now();
+
+	samples.iter()
+		.map(|(line, sample)| {
+			let time = Some((sample.time - first).max(0.0) / 1000.0)
+				.filter(|seconds| seconds.is_finite())
+				.and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+				.and_then(|offset| base.checked_add(offset))
+				.ok_or_else(|| SampleError::InvalidValue {
+					line: *line,
+					value: sample.time.to_string()
+				})?;
+
+			Ok(Event::new(
+				time,
+				sample.x,
+				sample.y,
+				sample.pressure,
+				sample.touching,
+				sample.hovering))
+		})
+		.collect()
+}
+
+/// Encodes all of the events in the given path as comma separated values, one
+/// event per line, after a line with the names of the columns.
+///
+/// Every event has its time in milliseconds since the first event, its
+/// position and pressure in normalized coordinates, and whether the pen was
+/// touching and hovering over the screen. When the units of the device are
+/// given, the position and pressure are also given in those units, in the
+/// `grid_x`, `grid_y` and `grid_pressure` columns. These are scaled up from
+/// the normalized values, so they hold the events after they went through the
+/// orientation, debouncing and smoothing of the capture, rather than the
+/// values the device reported.
+pub fn encode_csv(
+	path: &EventPath,
+	units: Option<DeviceUnits>,
+	options: &SampleOptions) -> String {
+
+	let mut columns = vec!["time", "x", "y", "pressure", "touching", "hovering"];
+	if units.is_some() {
+		columns.extend_from_slice(&["grid_x", "grid_y", "grid_pressure"]);
+	}
+	if options.stroke_index {
+		columns.push("stroke");
+	}
+
+	let mut csv = String::new();
+	let _ = writeln!(csv, "{}", columns.join(","));
+	for sample in samples(path, units, options) {
+		let _ = write!(
+			csv,
+			"{},{},{},{},{},{}",
+			number(sample.time),
+			sample.x,
+			sample.y,
+			sample.pressure,
+			sample.touching,
+			sample.hovering);
+		if let (Some(x), Some(y), Some(pressure)) = (sample.grid_x, sample.grid_y, sample.grid_pressure) {
+			let _ = write!(csv, ",{},{},{}", x, y, pressure);
+		}
+		if options.stroke_index {
+			let _ = write!(
+				csv,
+				",{}",
+				sample.stroke.map(|stroke| stroke.to_string()).unwrap_or_default());
+		}
+		let _ = writeln!(csv);
+	}
+
+	csv
+}
+
+/// Encodes all of the events in the given path as JSON Lines, with one object
+/// per event, holding the same fields as the columns of [`encode_csv()`].
+///
+/// [`encode_csv()`]: encode_csv
+pub fn encode_json_lines(
+	path: &EventPath,
+	units: Option<DeviceUnits>,
+	options: &SampleOptions) -> String {
+
+	let mut lines = String::new();
+	for sample in samples(path, units, options) {
+		let _ = writeln!(
+			lines,
+			"{}",
+			serde_json::to_string(&sample).expect("samples are always valid JSON"));
+	}
+
+	lines
+}
+
+/// Decodes a path from comma separated values, as written by [`encode_csv()`].
+///
+/// Columns are picked by their names in the first line, and may come in any
+/// order. Only the time and position columns are required, and columns that
+/// are not known are ignored. Values that are not finite numbers are not
+/// valid.
+///
+/// [`encode_csv()`]: encode_csv
+pub fn decode_csv(text: &str) -> Result<EventPath, SampleError> {
+	let mut lines = text.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty());
+	let header = lines.next()
+		.map(|(_, line)| line.split(',').map(str::trim).collect::<Vec<_>>())
+		.unwrap_or_default();
+	let column = |name: &'static str| header.iter().position(|column| *column == name);
+	let required = |name: &'static str| column(name).ok_or(SampleError::MissingColumn(name));
+
+	let (time, x, y) = (required("time")?, required("x")?, required("y")?);
+	let (pressure, touching, hovering) = (column("pressure"), column("touching"), column("hovering"));
+
+	let mut samples = Vec::new();
+	for (index, line) in lines {
+		let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+		let invalid = |value: &str| SampleError::InvalidValue {
+			line: index + 1,
+			value: value.to_owned()
+		};
+		let field = |column: usize| fields.get(column).copied().unwrap_or_default();
+		let float = |column: usize| field(column)
+			.parse::<f64>()
+			.ok()
+			.filter(|value| value.is_finite())
+			.ok_or_else(|| invalid(field(column)));
+		let boolean = |column: usize| match field(column) {
+			"true" | "1" => Ok(true),
+			"false" | "0" => Ok(false),
+			value => Err(invalid(value))
+		};
+
+		let pressure = pressure.map(float).transpose()?.unwrap_or(0.0);
+		samples.push((index + 1, Sample {
+			time: float(time)?,
+			x: float(x)?,
+			y: float(y)?,
+			pressure,
+			touching: touching.map(boolean).transpose()?.unwrap_or(pressure > 0.0),
+			hovering: hovering.map(boolean).transpose()?.unwrap_or_else(hovering_default),
+			grid_x: None,
+			grid_y: None,
+			grid_pressure: None,
+			stroke: None,
+		}));
+	}
+
+	path(&samples[..])
+}
+
+/// Decodes a path from JSON Lines, as written by [`encode_json_lines()`].
+///
+/// [`encode_json_lines()`]: encode_json_lines
+pub fn decode_json_lines(text: &str) -> Result<EventPath, SampleError> {
+	let samples = text.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.map(|(index, line)| serde_json::from_str::<Sample>(line)
+			.map(|sample| (index + 1, sample))
+			.map_err(|error| SampleError::Json { line: index + 1, error }))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	path(&samples[..])
+}
+
+/// Enumeration of the reasons for which samples may fail to be read.
+#[derive(Debug, thiserror::Error)]
+pub enum SampleError {
+	/// The values are missing a column we need.
+	#[error("missing the {0} column")]
+	MissingColumn(&'static str),
+	/// A value could not be read.
+	#[error("invalid value on line {line}: {value:?}")]
+	InvalidValue {
+		/// The line the value is on, counting from one.
+		line: usize,
+		/// The value that could not be read.
+		value: String,
+	},
+	/// A line is not a valid sample.
+	#[error("invalid sample on line {line}: {error}")]
+	Json {
+		/// The line the sample is on, counting from one.
+		line: usize,
+		/// The reason the sample could not be read.
+		error: serde_json::Error,
+	},
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A path with a stroke followed by the pen hovering away.
+	fn path() -> EventPath {
+		let start = Instant::now();
+		(0..10u32)
+			.map(|i| Event::new(
+				start + Duration::from_millis(u64::from(i) * 5),
+				0.1 + 0.05 * f64::from(i),
+				0.5 - 0.02 * f64::from(i),
+				if i < 7 { 0.1 * f64::from(i + 1) } else { 0.0 },
+				i < 7,
+				true))
+			.collect()
+	}
+
+	/// Checks that two paths hold the same events, with times kept to the
+	/// millisecond.
+	fn assert_same(expected: &EventPath, found: &EventPath) {
+		let expected = expected.events().collect::<Vec<_>>();
+		let found = found.events().collect::<Vec<_>>();
+		assert_eq!(expected.len(), found.len());
+
+		let start = (expected[0].time(), found[0].time());
+		for (a, b) in expected.iter().zip(&found) {
+			assert_eq!((a.x(), a.y(), a.pressure()), (b.x(), b.y(), b.pressure()));
+			assert_eq!((a.touching(), a.hovering()), (b.touching(), b.hovering()));
+			assert_eq!(
+				a.time().duration_since(start.0).as_millis(),
+				b.time().duration_since(start.1).as_millis());
+		}
+	}
+
+	#[test]
+	fn samples_round_trip() {
+		let path = path();
+		let units = Some(DeviceUnits { width: 10800, height: 6480, pressure: 1023 });
+		let options = SampleOptions { stroke_index: true };
+
+		assert_same(&path, &decode_csv(&encode_csv(&path, units, &options)).unwrap());
+		assert_same(&path, &decode_json_lines(&encode_json_lines(&path, units, &options)).unwrap());
+	}
+
+	#[test]
+	fn grid_values_are_scaled_from_the_path() {
+		let units = Some(DeviceUnits { width: 1000, height: 100, pressure: 10 });
+		let csv = encode_csv(&path(), units, &SampleOptions::default());
+
+		let mut lines = csv.lines();
+		assert_eq!(
+			lines.next(),
+			Some("time,x,y,pressure,touching,hovering,grid_x,grid_y,grid_pressure"));
+		assert!(lines.next().unwrap().ends_with(",100,50,1"));
+	}
+
+	#[test]
+	fn values_that_are_not_finite_are_invalid() {
+		for value in ["inf", "-inf", "NaN", "1e400"] {
+			let csv = format!("time,x,y\n0,0.5,0.5\n{},0.5,0.5\n", value);
+			match decode_csv(&csv) {
+				Err(SampleError::InvalidValue { line, .. }) => assert_eq!(line, 3),
+				other => panic!("{} gave {:?}", value, other.map(|path| path.events().count()))
+			}
+		}
+	}
+
+	#[test]
+	fn times_beyond_the_clock_are_invalid() {
+		let csv = "time,x,y\n0,0.5,0.5\n1e300,0.5,0.5\n";
+		match decode_csv(csv) {
+			Err(SampleError::InvalidValue { line, .. }) => assert_eq!(line, 3),
+			other => panic!("gave {:?}", other.map(|path| path.events().count()))
+		}
+
+		let lines = "{\"time\":0,\"x\":0.5,\"y\":0.5}\n{\"time\":1e300,\"x\":0.5,\"y\":0.5}\n";
+		match decode_json_lines(lines) {
+			Err(SampleError::InvalidValue { line, .. }) => assert_eq!(line, 2),
+			other => panic!("gave {:?}", other.map(|path| path.events().count()))
+		}
+	}
+}
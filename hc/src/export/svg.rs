@@ -0,0 +1,324 @@
+use serde::{Serialize, Deserialize};
+use std::fmt::Write;
+use stu::PressureCurve;
+use crate::path::{EventPath, CanvasStyle, Interpolation};
+use crate::export::number;
+
+/// The parameters of the exporting of signatures as SVG documents.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SvgOptions {
+	/// The method used to smooth out the strokes between events. Linear
+	/// interpolation leaves the strokes as straight lines between events.
+	pub smoothing: Interpolation,
+	/// The number of straight segments each stretch of a stroke between two
+	/// events is split into when smoothing it out.
+	pub samples: u32,
+	/// Whether the pressure of the pen is encoded in the width of the strokes,
+	/// in which case every stroke is written as a filled outline, rather than
+	/// as a line of constant width.
+	pub pressure: bool,
+}
+impl Default for SvgOptions {
+	fn default() -> Self {
+		Self {
+			smoothing: Interpolation::default(),
+			samples: 8,
+			pressure: true,
+		}
+	}
+}
+
+/// A point along a stroke, in millimeters, along with the radius of the pen at
+/// that point, also in millimeters.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Sample {
+	/// The position of the point along the horizontal axis.
+	x: f64,
+	/// The position of the point along the vertical axis.
+	y: f64,
+	/// The radius of the pen at the point.
+	radius: f64,
+}
+
+/// Encodes the ink strokes in the given path as an SVG document.
+///
+/// The path is taken to have been drawn on a screen whose physical dimensions,
+/// in millimeters, are given by `size`, and whose dimensions in pixels are
+/// given by `screen`. The document covers the whole of the screen, with its
+/// view box given in millimeters, and the widths in the given style, which are
+/// in pixels of the screen, get scaled to match. The pressure recorded in the
+/// path is shaped by the given curve before it sets the widths. Every stroke is written as a
+/// single path element, in the order the strokes were written in.
+///
+/// The background of the style is only drawn when the background is not
+/// requested to be transparent. The output only depends on the arguments, so
+/// that exporting the same signature twice gives the exact same document.
+pub fn encode_svg(
+	path: &EventPath,
+	size: (f64, f64),
+	screen: (u32, u32),
+	style: &CanvasStyle,
+	curve: &PressureCurve,
+	options: &SvgOptions,
+	transparent: bool) -> String {
+
+	let (width, height) = size;
+	let millimeters_per_pixel = width / f64::from(screen.0.max(1));
+
+	let mut svg = String::new();
+	let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+	let _ = writeln!(
+		svg,
+		r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}">"#,
+		w = number(width),
+		h = number(height));
+
+	if !transparent && style.background[3] > 0 {
+		let _ = writeln!(
+			svg,
+			r#"<rect width="{}" height="{}" {}/>"#,
+			number(width),
+			number(height),
+			paint("fill", style.background));
+	}
+
+	let _ = writeln!(svg, r#"<g {} stroke="none">"#, paint("fill", style.ink));
+	for stroke in path.strokes() {
+		let samples = {
+			let points = stroke.events()
+				.iter()
+				.map(|event| (
+					event.x() * width,
+					event.y() * height,
+					if options.pressure { curve.apply(event.pressure()) } else { 0.5 }))
+				.collect::<Vec<_>>();
+
+			smooth(&points[..], options.smoothing, options.samples)
+				.into_iter()
+				.map(|(x, y, pressure)| Sample {
+					x,
+					y,
+					radius: style.width(pressure) * millimeters_per_pixel / 2.0
+				})
+				.collect::<Vec<_>>()
+		};
+
+		let _ = writeln!(svg, "{}", if samples.len() == 1 {
+			let sample = samples[0];
+			format!(
+				r#"<circle cx="{}" cy="{}" r="{}"/>"#,
+				number(sample.x),
+				number(sample.y),
+				number(sample.radius))
+		} else if options.pressure {
+			format!(r#"<path d="{}"/>"#, outline(&samples[..]))
+		} else {
+			let d = samples.iter()
+				.enumerate()
+				.map(|(i, sample)| format!(
+					"{}{} {}",
+					if i == 0 { "M" } else { "L" },
+					number(sample.x),
+					number(sample.y)))
+				.collect::<Vec<_>>()
+				.join(" ");
+
+			format!(
+				r#"<path d="{}" fill="none" {} stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+				d,
+				paint("stroke", style.ink),
+				number(2.0 * samples[0].radius))
+		});
+	}
+	let _ = writeln!(svg, "</g>");
+	let _ = writeln!(svg, "</svg>");
+
+	svg
+}
+
+/// Smooths out a stroke, given as a list of positions along with the pressure
+/// of the pen at each of them, into a list of positions and pressures.
+///
+/// Consecutive points at the same position are merged, so that every pair of
+/// consecutive points has a direction.
+fn smooth(
+	points: &[(f64, f64, f64)],
+	interpolation: Interpolation,
+	samples: u32) -> Vec<(f64, f64, f64)> {
+
+	let samples = if interpolation == Interpolation::Linear { 1 } else { samples.max(1) };
+	let position = |i: usize| (points[i].0, points[i].1);
+
+	let mut smoothed = Vec::new();
+	let mut push = |point: (f64, f64, f64)| {
+		let repeated = smoothed.last()
+			.map(|last: &(f64, f64, f64)| last.0 == point.0 && last.1 == point.1)
+			.unwrap_or(false);
+		if !repeated {
+			smoothed.push(point)
+		}
+	};
+
+	for i in 0..points.len().saturating_sub(1) {
+		let (p1, p2) = (position(i), position(i + 1));
+
+		/* At the ends of the stroke, mirror the neighbouring point. */
+		let p0 = i.checked_sub(1)
+			.map(position)
+			.unwrap_or((2.0 * p1.0 - p2.0, 2.0 * p1.1 - p2.1));
+		let p3 = if i + 2 < points.len() {
+			position(i + 2)
+		} else {
+			(2.0 * p2.0 - p1.0, 2.0 * p2.1 - p1.1)
+		};
+
+		for step in 0..samples {
+			let t = f64::from(step) / f64::from(samples);
+			let (x, y) = interpolation.interpolate([p0, p1, p2, p3], t);
+
+			push((x, y, t.lerp(points[i].2, points[i + 1].2)));
+		}
+	}
+	if let Some(last) = points.last() {
+		push(*last);
+	}
+
+	smoothed
+}
+
+/// The outline of a stroke of varying width, as the path data of a closed
+/// shape, with round caps at both ends.
+fn outline(samples: &[Sample]) -> String {
+	/* Offset every sample along the normal of the stroke at that sample,
+	 * taken from the direction between its neighbours. */
+	let offsets = (0..samples.len())
+		.map(|i| {
+			let before = samples[i.saturating_sub(1)];
+			let after = samples[(i + 1).min(samples.len() - 1)];
+			let (dx, dy) = (after.x - before.x, after.y - before.y);
+			let length = dx.hypot(dy);
+			let (nx, ny) = if length > 0.0 {
+				(-dy / length, dx / length)
+			} else {
+				(0.0, 0.0)
+			};
+
+			let sample = samples[i];
+			(
+				(sample.x + nx * sample.radius, sample.y + ny * sample.radius),
+				(sample.x - nx * sample.radius, sample.y - ny * sample.radius),
+			)
+		})
+		.collect::<Vec<_>>();
+
+	let point = |(x, y): (f64, f64)| format!("{} {}", number(x), number(y));
+	let cap = |radius: f64, to: (f64, f64)| format!(
+		"A{r} {r} 0 0 0 {}",
+		point(to),
+		r = number(radius));
+
+	let first = samples[0];
+	let last = samples[samples.len() - 1];
+
+	/* Go forward along one side, around the end, back along the other side
+	 * and around the start. */
+	let mut d = format!("M{}", point(offsets[0].0));
+	for (left, _) in &offsets[1..] {
+		let _ = write!(d, " L{}", point(*left));
+	}
+	let _ = write!(d, " {}", cap(last.radius, offsets[offsets.len() - 1].1));
+	for (_, right) in offsets[..offsets.len() - 1].iter().rev() {
+		let _ = write!(d, " L{}", point(*right));
+	}
+	let _ = write!(d, " {} Z", cap(first.radius, offsets[0].0));
+
+	d
+}
+
+/// The attributes painting the given property with the given RGBA color.
+fn paint(property: &str, [r, g, b, a]: [u8; 4]) -> String {
+	if a == 255 {
+		format!(r##"{}="#{:02x}{:02x}{:02x}""##, property, r, g, b)
+	} else {
+		format!(
+			r##"{p}="#{:02x}{:02x}{:02x}" {p}-opacity="{}""##,
+			r, g, b,
+			number(f64::from(a) / 255.0),
+			p = property)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use stu::Event;
+	use std::time::{Duration, Instant};
+
+	/// A path with a wavy stroke of growing pressure, followed by a dot.
+	fn path() -> EventPath {
+		let start = Instant::now();
+		let event = |i: u32, x: f64, y: f64, pressure: f64, touching: bool| Event::new(
+			start + Duration::from_millis(u64::from(i) * 5),
+			x,
+			y,
+			pressure,
+			touching,
+			true);
+
+		let mut events = (0..=8)
+			.map(|i| {
+				let t = f64::from(i) / 8.0;
+				event(i, 0.1 + 0.6 * t, 0.5 + 0.2 * (t * std::f64::consts::TAU).sin(), 0.2 + 0.6 * t, true)
+			})
+			.collect::<Vec<_>>();
+		events.push(event(9, 0.7, 0.5, 0.0, false));
+		events.push(event(10, 0.85, 0.7, 0.5, true));
+		events.push(event(11, 0.85, 0.7, 0.0, false));
+
+		events.into_iter().collect()
+	}
+
+	/// Encodes the test path with the given options, on a screen the size of
+	/// that of the STU-430.
+	fn encode(options: SvgOptions) -> String {
+		encode_svg(
+			&path(),
+			(96.0, 60.0),
+			(320, 200),
+			&CanvasStyle::default(),
+			&PressureCurve::gamma(2.0),
+			&options,
+			false)
+	}
+
+	#[test]
+	fn plain_strokes_match_snapshot() {
+		let svg = encode(SvgOptions {
+			smoothing: Interpolation::Linear,
+			samples: 8,
+			pressure: false,
+		});
+		assert_eq!(svg, include_str!("../../tests/fixtures/signature-plain.svg"));
+	}
+
+	#[test]
+	fn smoothed_strokes_match_snapshot() {
+		let svg = encode(SvgOptions {
+			smoothing: Interpolation::CentripetalCatmullRom,
+			samples: 4,
+			pressure: false,
+		});
+		assert_eq!(svg, include_str!("../../tests/fixtures/signature-smoothed.svg"));
+	}
+
+	#[test]
+	fn pressure_widths_match_snapshot() {
+		let svg = encode(SvgOptions {
+			smoothing: Interpolation::CentripetalCatmullRom,
+			samples: 4,
+			pressure: true,
+		});
+		assert_eq!(svg, include_str!("../../tests/fixtures/signature-pressure.svg"));
+	}
+}
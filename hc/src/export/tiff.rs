@@ -0,0 +1,365 @@
+/// Encodes a bilevel image as a TIFF file, compressed with CCITT Group 4.
+///
+/// The pixels are given by a function returning whether the pixel at the given
+/// position is black. The resolution is given in dots per inch.
+pub fn encode_g4<F>(width: u32, height: u32, dpi: f64, black: F) -> Vec<u8>
+	where F: Fn(u32, u32) -> bool {
+
+	let strip = g4(width, height, black);
+
+	/* Lay the file out as the header, the image strip, the resolution values
+	 * and then the directory, with everything aligned to a word boundary. */
+	let strip_offset = 8u32;
+	let resolution_offset = strip_offset + strip.len() as u32 + (strip.len() as u32 & 1);
+	let directory_offset = resolution_offset + 8;
+
+	let mut file = Vec::new();
+	file.extend_from_slice(b"II");
+	file.extend_from_slice(&42u16.to_le_bytes());
+	file.extend_from_slice(&directory_offset.to_le_bytes());
+	file.extend_from_slice(&strip[..]);
+	if strip.len() & 1 != 0 {
+		file.push(0);
+	}
+
+	let dpi = dpi.max(1.0).round().min(f64::from(u32::MAX)) as u32;
+	file.extend_from_slice(&dpi.to_le_bytes());
+	file.extend_from_slice(&1u32.to_le_bytes());
+
+	/* The entries in the directory must be sorted by their tags. */
+	let entries: [(u16, u16, u32); 13] = [
+		(IMAGE_WIDTH, LONG, width),
+		(IMAGE_LENGTH, LONG, height),
+		(BITS_PER_SAMPLE, SHORT, 1),
+		(COMPRESSION, SHORT, COMPRESSION_CCITT_T6),
+		(PHOTOMETRIC_INTERPRETATION, SHORT, PHOTOMETRIC_WHITE_IS_ZERO),
+		(FILL_ORDER, SHORT, 1),
+		(STRIP_OFFSETS, LONG, strip_offset),
+		(SAMPLES_PER_PIXEL, SHORT, 1),
+		(ROWS_PER_STRIP, LONG, height),
+		(STRIP_BYTE_COUNTS, LONG, strip.len() as u32),
+		(X_RESOLUTION, RATIONAL, resolution_offset),
+		(Y_RESOLUTION, RATIONAL, resolution_offset),
+		(RESOLUTION_UNIT, SHORT, RESOLUTION_UNIT_INCH),
+	];
+
+	file.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+	for (tag, kind, value) in entries.iter().copied() {
+		file.extend_from_slice(&tag.to_le_bytes());
+		file.extend_from_slice(&kind.to_le_bytes());
+		file.extend_from_slice(&1u32.to_le_bytes());
+
+		/* Values that fit in the entry are stored in its lowest bytes. */
+		if kind == SHORT {
+			file.extend_from_slice(&(value as u16).to_le_bytes());
+			file.extend_from_slice(&[0, 0]);
+		} else {
+			file.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+	file.extend_from_slice(&0u32.to_le_bytes());
+
+	file
+}
+
+/* The TIFF tags we use. */
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const PHOTOMETRIC_INTERPRETATION: u16 = 262;
+const FILL_ORDER: u16 = 266;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const X_RESOLUTION: u16 = 282;
+const Y_RESOLUTION: u16 = 283;
+const RESOLUTION_UNIT: u16 = 296;
+
+/* The TIFF field types we use. */
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+/* The values of the TIFF fields we use. */
+const COMPRESSION_CCITT_T6: u32 = 4;
+const PHOTOMETRIC_WHITE_IS_ZERO: u32 = 0;
+const RESOLUTION_UNIT_INCH: u32 = 2;
+
+/// A writer of codes into a stream of bits, most significant bit first.
+#[derive(Debug, Default)]
+struct BitWriter {
+	/// The bytes written so far.
+	bytes: Vec<u8>,
+	/// The number of bits used in the last byte, from zero to seven.
+	used: u8,
+}
+impl BitWriter {
+	/// Writes the given code, given as a string of ones and zeroes.
+	fn code(&mut self, code: &str) {
+		for bit in code.bytes() {
+			if self.used == 0 {
+				self.bytes.push(0);
+			}
+			if bit == b'1' {
+				*self.bytes.last_mut().unwrap() |= 0x80 >> self.used;
+			}
+			self.used = (self.used + 1) % 8;
+		}
+	}
+
+	/// Writes the codes for a run of pixels of the given color.
+	fn run(&mut self, length: usize, black: bool) {
+		let (terminating, makeup) = if black {
+			(&BLACK_TERMINATING, &BLACK_MAKEUP)
+		} else {
+			(&WHITE_TERMINATING, &WHITE_MAKEUP)
+		};
+
+		let mut length = length;
+		while length >= 2560 {
+			self.code(EXTENDED_MAKEUP[EXTENDED_MAKEUP.len() - 1]);
+			length -= 2560;
+		}
+		if length >= 1792 {
+			self.code(EXTENDED_MAKEUP[length / 64 - 28]);
+			length %= 64;
+		} else if length >= 64 {
+			self.code(makeup[length / 64 - 1]);
+			length %= 64;
+		}
+		self.code(terminating[length]);
+	}
+}
+
+/// Compresses a bilevel image with the CCITT Group 4 (T.6) scheme.
+fn g4<F>(width: u32, height: u32, black: F) -> Vec<u8>
+	where F: Fn(u32, u32) -> bool {
+
+	let width = width as usize;
+
+	/* The first line is coded against an imaginary, all white line. */
+	let mut reference = vec![false; width];
+	let mut current = vec![false; width];
+	let mut out = BitWriter::default();
+
+	for y in 0..height {
+		for (x, pixel) in current.iter_mut().enumerate() {
+			*pixel = black(x as u32, y);
+		}
+
+		/* The position of the changing element a0, with -1 standing for the
+		 * imaginary white pixel before the start of the line. */
+		let mut a0: isize = -1;
+		let mut color = false;
+		while a0 < width as isize {
+			let start = (a0 + 1) as usize;
+			let a1 = changing(&current, start);
+			let b1 = {
+				let mut b1 = changing(&reference, start);
+				while b1 < width && reference[b1] == color {
+					b1 = changing(&reference, b1 + 1);
+				}
+				b1
+			};
+			let b2 = changing(&reference, b1 + 1);
+
+			if b2 < a1 {
+				/* Pass mode. */
+				out.code("0001");
+				a0 = b2 as isize;
+			} else if (a1 as isize - b1 as isize).abs() <= 3 {
+				/* Vertical mode. */
+				out.code(VERTICAL[(a1 as isize - b1 as isize + 3) as usize]);
+				a0 = a1 as isize;
+				color = !color;
+			} else {
+				/* Horizontal mode. */
+				let a2 = changing(&current, a1 + 1);
+				out.code("001");
+				out.run(a1 - a0.max(0) as usize, color);
+				out.run(a2 - a1, !color);
+				a0 = a2 as isize;
+			}
+		}
+
+		std::mem::swap(&mut reference, &mut current);
+	}
+
+	/* End of facsimile block. */
+	out.code("000000000001");
+	out.code("000000000001");
+
+	out.bytes
+}
+
+/// The position of the first changing element at or after the given position
+/// in the given line, or the width of the line if there are none.
+fn changing(line: &[bool], start: usize) -> usize {
+	if start >= line.len() {
+		return line.len()
+	}
+
+	let mut previous = if start == 0 { false } else { line[start - 1] };
+	for (i, pixel) in line.iter().enumerate().skip(start) {
+		if *pixel != previous {
+			return i
+		}
+		previous = *pixel;
+	}
+
+	line.len()
+}
+
+/// The codes of the vertical mode, for offsets from -3 to 3.
+const VERTICAL: [&str; 7] = [
+	"0000010", "000010", "010", "1", "011", "000011", "0000011",
+];
+
+/// The terminating codes for runs of white pixels, from 0 to 63 pixels long.
+const WHITE_TERMINATING: [&str; 64] = [
+	"00110101", "000111", "0111", "1000", "1011", "1100", "1110", "1111",
+	"10011", "10100", "00111", "01000", "001000", "000011", "110100", "110101",
+	"101010", "101011", "0100111", "0001100", "0001000", "0010111", "0000011", "0000100",
+	"0101000", "0101011", "0010011", "0100100", "0011000", "00000010", "00000011", "00011010",
+	"00011011", "00010010", "00010011", "00010100", "00010101", "00010110", "00010111", "00101000",
+	"00101001", "00101010", "00101011", "00101100", "00101101", "00000100", "00000101", "00001010",
+	"00001011", "01010010", "01010011", "01010100", "01010101", "00100100", "00100101", "01011000",
+	"01011001", "01011010", "01011011", "01001010", "01001011", "00110010", "00110011", "00110100",
+];
+
+/// The makeup codes for runs of white pixels, from 64 to 1728 pixels long.
+const WHITE_MAKEUP: [&str; 27] = [
+	"11011", "10010", "010111", "0110111", "00110110", "00110111", "01100100", "01100101",
+	"01101000", "01100111", "011001100", "011001101", "011010010", "011010011", "011010100", "011010101",
+	"011010110", "011010111", "011011000", "011011001", "011011010", "011011011", "010011000", "010011001",
+	"010011010", "011000", "010011011",
+];
+
+/// The terminating codes for runs of black pixels, from 0 to 63 pixels long.
+const BLACK_TERMINATING: [&str; 64] = [
+	"0000110111", "010", "11", "10", "011", "0011", "0010", "00011",
+	"000101", "000100", "0000100", "0000101", "0000111", "00000100", "00000111", "000011000",
+	"0000010111", "0000011000", "0000001000", "00001100111", "00001101000", "00001101100", "00000110111", "00000101000",
+	"00000010111", "00000011000", "000011001010", "000011001011", "000011001100", "000011001101", "000001101000", "000001101001",
+	"000001101010", "000001101011", "000011010010", "000011010011", "000011010100", "000011010101", "000011010110", "000011010111",
+	"000001101100", "000001101101", "000011011010", "000011011011", "000001010100", "000001010101", "000001010110", "000001010111",
+	"000001100100", "000001100101", "000001010010", "000001010011", "000000100100", "000000110111", "000000111000", "000000100111",
+	"000000101000", "000001011000", "000001011001", "000000101011", "000000101100", "000001011010", "000001100110", "000001100111",
+];
+
+/// The makeup codes for runs of black pixels, from 64 to 1728 pixels long.
+const BLACK_MAKEUP: [&str; 27] = [
+	"0000001111", "000011001000", "000011001001", "000001011011", "000000110011", "000000110100", "000000110101", "0000001101100",
+	"0000001101101", "0000001001010", "0000001001011", "0000001001100", "0000001001101", "0000001110010", "0000001110011", "0000001110100",
+	"0000001110101", "0000001110110", "0000001110111", "0000001010010", "0000001010011", "0000001010100", "0000001010101", "0000001011010",
+	"0000001011011", "0000001100100", "0000001100101",
+];
+
+/// The makeup codes shared by both colors, from 1792 to 2560 pixels long.
+const EXTENDED_MAKEUP: [&str; 13] = [
+	"00000001000", "00000001100", "00000001101", "000000010010", "000000010011", "000000010100", "000000010101", "000000010110",
+	"000000010111", "000000011100", "000000011101", "000000011110", "000000011111",
+];
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Patterns exercising every coding mode, given by the pixels they paint
+	/// black.
+	fn pattern(kind: u32, x: u32, y: u32) -> bool {
+		match kind {
+			/* Noise, mostly coded in horizontal mode. */
+			0 => ((x * 7 + y * 13) ^ (x * y)) % 5 == 0,
+			/* A disc, mostly coded in vertical mode. */
+			1 => (i64::from(x) - 1000).pow(2) + (i64::from(y) - 40).pow(2) < 1500,
+			/* Long runs, needing makeup codes of every kind. */
+			2 => x % 3000 > 5 && y % 2 == 0,
+			/* Checkers, which need pass mode. */
+			3 => (x / 4 + y / 3) % 2 == 1,
+			4 => true,
+			_ => false,
+		}
+	}
+
+	/// Reads the value of a field of the first directory of a TIFF file.
+	fn field(file: &[u8], tag: u16) -> Option<u32> {
+		let u16_at = |offset: usize| u16::from_le_bytes([file[offset], file[offset + 1]]);
+		let u32_at = |offset: usize| u32::from_le_bytes([
+			file[offset],
+			file[offset + 1],
+			file[offset + 2],
+			file[offset + 3]]);
+
+		let directory = u32_at(4) as usize;
+		(0..usize::from(u16_at(directory)))
+			.map(|i| directory + 2 + 12 * i)
+			.find(|entry| u16_at(*entry) == tag)
+			.map(|entry| if u16_at(entry + 2) == SHORT {
+				u32::from(u16_at(entry + 8))
+			} else {
+				u32_at(entry + 8)
+			})
+	}
+
+	/// Decodes the strip of a TIFF file with a reference decoder.
+	fn decode(file: &[u8]) -> Vec<Vec<bool>> {
+		let width = field(file, IMAGE_WIDTH).unwrap() as u16;
+		let offset = field(file, STRIP_OFFSETS).unwrap() as usize;
+		let length = field(file, STRIP_BYTE_COUNTS).unwrap() as usize;
+
+		let mut rows = Vec::new();
+		fax::decoder::decode_g4(file[offset..offset + length].iter().copied(), width, None, |transitions| {
+			rows.push(fax::decoder::pels(transitions, width)
+				.map(|color| color == fax::Color::Black)
+				.collect());
+		}).expect("the strip should decode");
+
+		rows
+	}
+
+	#[test]
+	fn strips_decode_to_the_same_pixels() {
+		let sizes = [(0, (97, 31)), (1, (3000, 80)), (2, (5200, 4)), (3, (1, 7)), (3, (64, 9)), (4, (2600, 3)), (5, (64, 5))];
+		for (kind, (width, height)) in sizes.iter().copied() {
+			let file = encode_g4(width, height, 300.0, |x, y| pattern(kind, x, y));
+			let rows = decode(&file);
+
+			assert_eq!(rows.len(), height as usize, "pattern {} at {}x{}", kind, width, height);
+			for (y, row) in rows.iter().enumerate() {
+				let expected = (0..width).map(|x| pattern(kind, x, y as u32)).collect::<Vec<_>>();
+				assert!(*row == expected, "pattern {} at {}x{} differs on row {}", kind, width, height, y);
+			}
+		}
+	}
+
+	#[test]
+	fn directories_describe_the_image() {
+		let file = encode_g4(97, 31, 300.0, |x, y| pattern(0, x, y));
+		assert_eq!(&file[..4], b"II*\0");
+
+		assert_eq!(field(&file, IMAGE_WIDTH), Some(97));
+		assert_eq!(field(&file, IMAGE_LENGTH), Some(31));
+		assert_eq!(field(&file, COMPRESSION), Some(COMPRESSION_CCITT_T6));
+		assert_eq!(field(&file, PHOTOMETRIC_INTERPRETATION), Some(PHOTOMETRIC_WHITE_IS_ZERO));
+		assert_eq!(field(&file, RESOLUTION_UNIT), Some(RESOLUTION_UNIT_INCH));
+
+		let resolution = field(&file, X_RESOLUTION).unwrap() as usize;
+		assert_eq!(field(&file, Y_RESOLUTION), Some(resolution as u32));
+		assert_eq!(&file[resolution..resolution + 8], &[44, 1, 0, 0, 1, 0, 0, 0]);
+	}
+
+	#[test]
+	fn files_match_golden_fixtures() {
+		let fixtures: [(f64, &[u8]); 2] = [
+			(96.0, include_bytes!("../../tests/fixtures/pattern-96dpi.tif")),
+			(300.0, include_bytes!("../../tests/fixtures/pattern-300dpi.tif")),
+		];
+		for (dpi, fixture) in fixtures.iter() {
+			let file = encode_g4(97, 31, *dpi, |x, y| pattern(0, x, y) || pattern(3, x, y));
+			assert!(file == *fixture, "the file at {} dpi changed", dpi);
+		}
+	}
+}
@@ -21,8 +21,29 @@ mod robot;
 /// Strings used in the UI.
 mod strings;
 
+/// User-configurable settings of the application.
+mod settings;
+
+/// Structures handling the images displayed on the screen of the device.
+mod screen;
+
+/// Exporting of signatures into files for other applications.
+mod export;
+
 fn main() {
 	window::init();
+	let settings = match settings::Settings::load() {
+		Ok(settings) => settings,
+		Err(what) => {
+			/* Not being able to load the settings should not keep the user
+			 * from using the application, so just fall back to the defaults. */
+			nwg::error_message(
+				crate::strings::errors::title(),
+				&crate::strings::errors::settings_load_failed(what));
+			Default::default()
+		}
+	};
+
 	let information = match window::pick_tablet() {
 		Ok(information) => information,
 		Err(what) => {
@@ -69,7 +90,7 @@ fn main() {
 		}
 	};
 
-	if let Err(what) = window::manage(device) {
+	if let Err(what) = window::manage(device, settings) {
 		nwg::error_message(
 			&crate::strings::errors::title(),
 			&crate::strings::errors::management_failed(what));
@@ -0,0 +1,411 @@
+use serde::{Serialize, Deserialize};
+use stu::{Event, PressureCurve};
+
+/// The height of the bar showing how much of a countdown is left, in pixels.
+const COUNTDOWN_HEIGHT: u32 = 4;
+
+/// The style the strokes on a canvas are rendered with.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CanvasStyle {
+	/// The color of the ink, as an RGBA value.
+	pub ink: [u8; 4],
+	/// The color of the background, as an RGBA value.
+	pub background: [u8; 4],
+	/// The width of the strokes at the lightest pressure, in pixels.
+	pub min_width: f64,
+	/// The width of the strokes at full pressure, in pixels.
+	pub max_width: f64,
+	/// Whether the edges of the strokes are smoothed out. When this is off,
+	/// every pixel is either fully inked or not inked at all, which is what
+	/// devices with monochrome screens can display.
+	pub antialiased: bool,
+}
+impl CanvasStyle {
+	/// The style of monochrome, one pixel wide strokes, in black over white.
+	pub fn monochrome() -> Self {
+		Self {
+			ink: [0, 0, 0, 255],
+			background: [255, 255, 255, 255],
+			min_width: 1.0,
+			max_width: 1.0,
+			antialiased: false,
+		}
+	}
+
+	/// The width of the strokes at the given pressure, in pixels.
+	pub fn width(&self, pressure: f64) -> f64 {
+		let min = self.min_width.max(0.0);
+		let max = self.max_width.max(min);
+
+		pressure.lerp(min, max)
+	}
+}
+impl Default for CanvasStyle {
+	fn default() -> Self {
+		Self {
+			ink: [0, 0, 0, 255],
+			background: [255, 255, 255, 255],
+			min_width: 1.0,
+			max_width: 3.0,
+			antialiased: true,
+		}
+	}
+}
+
+/// A structure for generating pictures from events.
+///
+/// The canvas keeps track of how much of each pixel is covered by ink, and
+/// only applies the colors of its style when the picture is read out of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventCanvas {
+	/// The ink coverage of each pixel, row by row, from fully uncovered at `0`
+	/// to fully covered at `255`.
+	buffer: Box<[u8]>,
+	/// The width of the canvas, in pixels.
+	width: u32,
+	/// The height of the canvas, in pixels.
+	height: u32,
+	/// The style strokes are rendered with.
+	style: CanvasStyle,
+	/// The curve the pressure of the pen is shaped by before it sets the width
+	/// of the strokes.
+	curve: PressureCurve,
+	/// The position and radius of the pen at the last point of the stroke
+	/// being drawn, if any, in pixels.
+	last: Option<(f64, f64, f64)>,
+}
+impl EventCanvas {
+	/// Creates a new, blank monochrome canvas with the given dimensions.
+	pub fn new(width: u32, height: u32) -> Self {
+		Self::with_style(width, height, CanvasStyle::monochrome())
+	}
+
+	/// Creates a new, blank canvas with the given dimensions and style.
+	pub fn with_style(width: u32, height: u32, style: CanvasStyle) -> Self {
+		if width == 0 {
+			panic!("Tried to create a canvas with no width.")
+		}
+		if height == 0 {
+			panic!("Tried to create a canvas with no height.")
+		}
+
+		let pixels = u64::from(width) * u64::from(height);
+		let pixels = std::convert::TryFrom::try_from(pixels)
+			.expect("Canvas size does not fit in a usize");
+
+		let buffer = vec![0u8; pixels].into_boxed_slice();
+		Self { buffer, width, height, style, curve: PressureCurve::linear(), last: None }
+	}
+
+	/// Shapes the pressure of the pen by the given curve before it sets the
+	/// width of the strokes drawn from now on.
+	pub fn with_pressure_curve(mut self, curve: PressureCurve) -> Self {
+		self.curve = curve;
+		self
+	}
+
+	/// Changes the curve the pressure of the pen is shaped by, for the strokes
+	/// drawn from now on.
+	pub fn set_pressure_curve(&mut self, curve: PressureCurve) {
+		self.curve = curve;
+	}
+
+	/// The width of this canvas, in pixels.
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	/// The height of this canvas, in pixels.
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// Copies the picture in this canvas into an RGB image, with the colors of
+	/// the canvas being blended over a white background wherever they are not
+	/// fully opaque.
+	pub fn to_rgb(&self) -> image::RgbImage {
+		image::ImageBuffer::from_fn(
+			self.width,
+			self.height,
+			|x, y| {
+				let [r, g, b, a] = self.pixel(x, y).unwrap();
+				let blend = |c: u8| {
+					let f = f64::from(a) / 255.0;
+					f.lerp(255.0, f64::from(c)).round() as u8
+				};
+
+				image::Rgb([blend(r), blend(g), blend(b)])
+			})
+	}
+
+	/// Copies the picture in this canvas into an RGBA image.
+	///
+	/// If a transparent background is requested, the background color of this
+	/// canvas is left out, and the ink is given an opacity proportional to how
+	/// much of each pixel it covers.
+	pub fn to_rgba(&self, transparent: bool) -> image::RgbaImage {
+		image::ImageBuffer::from_fn(
+			self.width,
+			self.height,
+			|x, y| {
+				if transparent {
+					let [r, g, b, a] = self.style.ink;
+					let coverage = u32::from(self.coverage(x, y).unwrap());
+
+					image::Rgba([r, g, b, (u32::from(a) * coverage / 255) as u8])
+				} else {
+					image::Rgba(self.pixel(x, y).unwrap())
+				}
+			})
+	}
+
+	/// Copies the image data in this canvas into a memory blob encoded as a
+	/// bitmap.
+	///
+	/// The format the bitmap will be in is full color 24-bpp RGB, with the
+	/// colors of the canvas being blended over a white background wherever
+	/// they are not fully opaque.
+	pub fn to_bitmap(&self) -> Box<[u8]> {
+		encode_bitmap(&self.to_rgb())
+	}
+
+	/// Copies the image data in this canvas into a memory blob encoded as a
+	/// bitmap, in the same format as [`to_bitmap()`], with a bar along its
+	/// bottom edge, in the color of the ink, showing the given fraction of a
+	/// countdown that is left.
+	///
+	/// [`to_bitmap()`]: Self::to_bitmap
+	pub fn to_bitmap_with_countdown(&self, remaining: f64) -> Box<[u8]> {
+		let mut image = self.to_rgb();
+
+		let [r, g, b, _] = self.style.ink;
+		let width = (f64::from(self.width) * remaining.clamp(0.0, 1.0)).round() as u32;
+		let height = COUNTDOWN_HEIGHT.min(self.height);
+		for y in self.height - height..self.height {
+			for x in 0..width {
+				image.put_pixel(x, y, image::Rgb([r, g, b]));
+			}
+		}
+
+		encode_bitmap(&image)
+	}
+
+	/// Clears this canvas back into an unset state.
+	pub fn clear(&mut self) {
+		for byte in &mut self.buffer[..] { *byte = 0; }
+		self.last = None;
+	}
+
+	/// Process the given event altering the canvas if needed.
+	pub fn process(&mut self, event: Event) {
+		/* A pen resting on the screen with no pressure behind it, as shaped by
+		 * the pressure curve, leaves no ink. */
+		let pressure = self.curve.apply(event.pressure());
+		if event.touching() && pressure > 0.0 {
+			let mut x = f64::from(self.width - 1) * event.x();
+			let mut y = f64::from(self.height - 1) * event.y();
+			let r = self.style.width(pressure) / 2.0;
+
+			/* Without antialiasing, the pen is snapped to the closest pixel,
+			 * so that the pixel under it is always inked, and so that strokes
+			 * along the edges between pixels don't ink both sides of them. */
+			if !self.style.antialiased {
+				x = x.round();
+				y = y.round();
+			}
+
+			let from = self.last.unwrap_or((x, y, r));
+			self.segment(from, (x, y, r));
+
+			self.last = Some((x, y, r));
+		} else {
+			self.last = None
+		}
+	}
+
+	/// Inks a segment between two points, given along with the radius of the
+	/// pen at each of them, with the radius varying linearly between them.
+	fn segment(&mut self, a: (f64, f64, f64), b: (f64, f64, f64)) {
+		let (ax, ay, ar) = a;
+		let (bx, by, br) = b;
+
+		/* Only go over the pixels the segment may possibly cover. */
+		let reach = ar.max(br) + 1.0;
+		let clamp = |value: f64, limit: u32| value.max(0.0).min(f64::from(limit - 1)) as u32;
+		let left = clamp((ax.min(bx) - reach).floor(), self.width);
+		let right = clamp((ax.max(bx) + reach).ceil(), self.width);
+		let top = clamp((ay.min(by) - reach).floor(), self.height);
+		let bottom = clamp((ay.max(by) + reach).ceil(), self.height);
+
+		let (dx, dy) = (bx - ax, by - ay);
+		let length = dx * dx + dy * dy;
+
+		for py in top..=bottom {
+			for px in left..=right {
+				let (cx, cy) = (f64::from(px), f64::from(py));
+
+				/* Find the closest point along the segment, and the radius of
+				 * the pen at it. */
+				let t = if length > 0.0 {
+					(((cx - ax) * dx + (cy - ay) * dy) / length).clamp(0.0, 1.0)
+				} else {
+					0.0
+				};
+				let r = t.lerp(ar, br);
+				let d = (cx - (ax + t * dx)).hypot(cy - (ay + t * dy));
+
+				/* Approximate the area of the pixel covered by the pen with the
+				 * distance from its center to the edge of the pen. */
+				self.plot(px, py, (r - d + 0.5).clamp(0.0, 1.0));
+			}
+		}
+	}
+
+	/// Adds the given coverage to the pixel at the given position.
+	fn plot(&mut self, x: u32, y: u32, coverage: f64) {
+		let index = y as usize * self.width as usize + x as usize;
+		let pixel = &mut self.buffer[index];
+
+		if self.style.antialiased {
+			/* Overlapping parts of the same stroke should not get darker, so
+			 * keep the largest coverage rather than accumulating it. */
+			*pixel = (*pixel).max((coverage * 255.0).round() as u8);
+		} else if coverage >= 0.5 {
+			*pixel = 255;
+		}
+	}
+
+	/// The ink coverage of the pixel at the given position, from fully
+	/// uncovered at `0` to fully covered at `255`.
+	pub fn coverage(&self, x: u32, y: u32) -> Option<u8> {
+		if x >= self.width || y >= self.height {
+			return None
+		}
+
+		Some(self.buffer[y as usize * self.width as usize + x as usize])
+	}
+
+	/// Gets whether the pixel at the given position is mostly covered by ink.
+	pub fn get(&self, x: u32, y: u32) -> Option<bool> {
+		self.coverage(x, y).map(|coverage| coverage >= 128)
+	}
+
+	/// The color of the pixel at the given position, as an RGBA value, with
+	/// the ink blended over the background by its coverage.
+	pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+		let f = f64::from(self.coverage(x, y)?) / 255.0;
+		let ink = self.style.ink;
+		let background = self.style.background;
+
+		let mut pixel = [0; 4];
+		for i in 0..4 {
+			pixel[i] = f.lerp(f64::from(background[i]), f64::from(ink[i])).round() as u8;
+		}
+
+		Some(pixel)
+	}
+}
+
+/// Encodes the given image into a memory blob as a 24-bpp RGB bitmap.
+fn encode_bitmap(image: &image::RgbImage) -> Box<[u8]> {
+	let mut buffer = Vec::new();
+	let mut encoder = image::codecs::bmp::BmpEncoder::new(&mut buffer);
+
+	encoder.encode(
+		image.as_raw(),
+		image.width(),
+		image.height(),
+		image::ColorType::Rgb8)
+		.unwrap();
+
+	buffer.into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+
+	/// Draws a stroke through the given positions, in pixels, at the given
+	/// pressure, on a canvas of the given size and style.
+	fn draw(size: u32, style: CanvasStyle, pressure: f64, points: &[(f64, f64)]) -> EventCanvas {
+		let time = Instant::now();
+		let scale = f64::from(size - 1);
+		let mut canvas = EventCanvas::with_style(size, size, style);
+		for (x, y) in points {
+			canvas.process(Event::new(time, x / scale, y / scale, pressure, true, true));
+		}
+		canvas
+	}
+
+	/// The positions of the pixels of the given canvas that are inked.
+	fn inked(canvas: &EventCanvas) -> Vec<(u32, u32)> {
+		(0..canvas.height())
+			.flat_map(|y| (0..canvas.width()).map(move |x| (x, y)))
+			.filter(|(x, y)| canvas.get(*x, *y).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn monochrome_dots_ink_one_pixel() {
+		for (x, y) in [(10.0, 10.0), (10.5, 10.5), (10.49, 9.51), (0.0, 20.0)] {
+			let canvas = draw(21, CanvasStyle::monochrome(), 0.5, &[(x, y)]);
+			assert_eq!(inked(&canvas), [(x.round() as u32, y.round() as u32)], "{} {}", x, y);
+		}
+	}
+
+	#[test]
+	fn monochrome_strokes_between_pixels_ink_one_row() {
+		let canvas = draw(21, CanvasStyle::monochrome(), 0.5, &[(2.0, 10.5), (18.0, 10.5)]);
+		assert_eq!(inked(&canvas), (2..=18).map(|x| (x, 11)).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn monochrome_diagonals_are_one_pixel_wide() {
+		let canvas = draw(21, CanvasStyle::monochrome(), 0.5, &[(2.0, 2.0), (18.0, 18.0)]);
+		assert_eq!(inked(&canvas), (2..=18).map(|i| (i, i)).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn pressure_sets_the_width() {
+		let style = CanvasStyle { min_width: 1.0, max_width: 5.0, ..CanvasStyle::monochrome() };
+
+		let canvas = draw(21, style, 1.0, &[(5.0, 10.0), (15.0, 10.0)]);
+		let column = (0..21).filter(|y| canvas.get(10, *y).unwrap()).collect::<Vec<_>>();
+		assert_eq!(column, [8, 9, 10, 11, 12]);
+
+		let canvas = draw(21, style, 0.01, &[(5.0, 10.0), (15.0, 10.0)]);
+		let column = (0..21).filter(|y| canvas.get(10, *y).unwrap()).collect::<Vec<_>>();
+		assert_eq!(column, [10]);
+
+		/* The curve shapes the pressure before it sets the width. */
+		let mut canvas = EventCanvas::with_style(21, 21, style)
+			.with_pressure_curve(PressureCurve::gamma(8.0));
+		for x in [5.0, 15.0] {
+			canvas.process(Event::new(Instant::now(), x / 20.0, 0.5, 0.5, true, true));
+		}
+		let column = (0..21).filter(|y| canvas.get(10, *y).unwrap()).collect::<Vec<_>>();
+		assert_eq!(column, [10]);
+	}
+
+	#[test]
+	fn antialiased_edges_are_partly_covered() {
+		let style = CanvasStyle { min_width: 3.0, max_width: 3.0, ..CanvasStyle::default() };
+		let canvas = draw(21, style, 0.5, &[(5.0, 10.5), (15.0, 10.5)]);
+
+		assert_eq!(canvas.coverage(10, 10), Some(255));
+		assert_eq!(canvas.coverage(10, 11), Some(255));
+		assert_eq!(canvas.coverage(10, 9), Some(128));
+		assert_eq!(canvas.coverage(10, 12), Some(128));
+		assert_eq!(canvas.coverage(10, 8), Some(0));
+	}
+
+	#[test]
+	fn lifted_pens_leave_no_ink() {
+		let mut canvas = EventCanvas::new(21, 21);
+		canvas.process(Event::new(Instant::now(), 0.5, 0.5, 0.5, false, true));
+		canvas.process(Event::new(Instant::now(), 0.5, 0.5, 0.0, true, true));
+		assert!(inked(&canvas).is_empty());
+	}
+}
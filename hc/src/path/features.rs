@@ -0,0 +1,292 @@
+use serde::{Serialize, Deserialize};
+use std::time::{Duration, Instant};
+use crate::path::EventPath;
+
+/// The version of the set of features, which is bumped whenever a feature is
+/// added, so that consumers can tell which of them to expect.
+const FEATURES_VERSION: u32 = 1;
+
+/// The smallest distance the pen has to travel along an axis, in millimeters,
+/// before a reversal of its direction along that axis is counted, so that the
+/// jitter of the pen is not taken for changes of direction.
+const DIRECTION_CHANGE_DISTANCE: f64 = 0.5;
+
+/// The shortest time the speed of the pen is measured over. Devices report
+/// events in batches, with the events in a batch only microseconds apart, so
+/// the motion between them is gathered until this much time has passed.
+const VELOCITY_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Descriptive features of a signature.
+///
+/// Features are stable across versions: their names, units and meanings never
+/// change, and new features only ever get added, along with a bump of the
+/// version. Distances are in millimeters, and times are in milliseconds. All
+/// of the motion features only take the strokes that left ink into account.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignatureFeatures {
+	/// The version of the set of features.
+	pub version: u32,
+	/// The time between the first and the last event of the signature.
+	pub duration: f64,
+	/// The time the pen spent touching the screen.
+	pub pen_down: f64,
+	/// The number of strokes that left ink.
+	pub strokes: usize,
+	/// The number of events in the strokes that left ink.
+	pub points: usize,
+	/// The length of the ink.
+	pub length: f64,
+	/// The mean speed of the pen, in millimeters per second.
+	pub mean_velocity: f64,
+	/// The highest speed of the pen, in millimeters per second.
+	pub max_velocity: f64,
+	/// The mean magnitude of the change in speed of the pen, in millimeters
+	/// per second squared.
+	pub mean_acceleration: f64,
+	/// The highest magnitude of the change in speed of the pen, in millimeters
+	/// per second squared.
+	pub max_acceleration: f64,
+	/// The mean pressure of the pen, from zero to one. The pressure is taken
+	/// as reported by the device, before the pressure curve of the device
+	/// shapes it for drawing, as are all of the other pressure features.
+	pub mean_pressure: f64,
+	/// The standard deviation of the pressure of the pen.
+	pub pressure_deviation: f64,
+	/// The lowest pressure of the pen.
+	pub min_pressure: f64,
+	/// The highest pressure of the pen.
+	pub max_pressure: f64,
+	/// The width of the bounding box of the ink.
+	pub width: f64,
+	/// The height of the bounding box of the ink.
+	pub height: f64,
+	/// The ratio between the width and the height of the bounding box of the
+	/// ink, or zero when the ink has no height.
+	pub aspect_ratio: f64,
+	/// The number of times the pen reversed its direction of movement along
+	/// either axis.
+	pub direction_changes: usize,
+}
+impl SignatureFeatures {
+	/// Computes the features of the given signature, taken to have been drawn
+	/// on a screen whose physical dimensions, in millimeters, are given by
+	/// `size`.
+	pub fn new(path: &EventPath, size: (f64, f64)) -> Self {
+		let (width, height) = size;
+		let mut features = Self {
+			version: FEATURES_VERSION,
+			..Default::default()
+		};
+
+		let first = path.events().next().map(|event| event.time());
+		let last = path.events().last().map(|event| event.time());
+		if let (Some(first), Some(last)) = (first, last) {
+			features.duration = millis(last.saturating_duration_since(first));
+		}
+
+		let mut velocities = Vec::new();
+		let mut accelerations = Vec::new();
+		let mut pressures = Vec::new();
+		let mut bounds = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+		for stroke in path.strokes() {
+			let events = stroke.events();
+			features.strokes += 1;
+			features.points += events.len();
+			if let (Some(first), Some(last)) = (events.first(), events.last()) {
+				features.pen_down += millis(last.time().saturating_duration_since(first.time()));
+			}
+
+			let points = events.iter()
+				.map(|event| (event.time(), event.x() * width, event.y() * height))
+				.collect::<Vec<_>>();
+			for (_, x, y) in &points {
+				bounds = (bounds.0.min(*x), bounds.1.min(*y), bounds.2.max(*x), bounds.3.max(*y));
+			}
+			pressures.extend(events.iter().map(|event| event.pressure()));
+
+			/* Velocities are taken over the motion of the pen in at least the
+			 * interval for velocities, and accelerations between consecutive
+			 * velocities, so that events reported in the same batch, whose
+			 * motion can't be told apart in time, are measured together. */
+			let mut previous: Option<(f64, f64)> = None;
+			let mut anchor: Option<(Instant, f64)> = None;
+			for pair in points.windows(2) {
+				let ((t0, x0, y0), (t1, x1, y1)) = (pair[0], pair[1]);
+				let distance = (x1 - x0).hypot(y1 - y0);
+				features.length += distance;
+
+				let (start, travelled) = anchor.unwrap_or((t0, 0.0));
+				let travelled = travelled + distance;
+				let dt = t1.saturating_duration_since(start);
+				if dt < VELOCITY_INTERVAL {
+					anchor = Some((start, travelled));
+					continue
+				}
+				anchor = None;
+
+				let dt = dt.as_secs_f64();
+				let velocity = travelled / dt;
+				let midpoint = start.saturating_duration_since(points[0].0).as_secs_f64() + dt / 2.0;
+				velocities.push(velocity);
+
+				if let Some((time, last)) = previous {
+					if midpoint > time {
+						accelerations.push((velocity - last).abs() / (midpoint - time));
+					}
+				}
+				previous = Some((midpoint, velocity));
+			}
+
+			features.direction_changes += reversals(points.iter().map(|(_, x, _)| *x))
+				+ reversals(points.iter().map(|(_, _, y)| *y));
+		}
+
+		let mean = |values: &[f64]| if values.is_empty() {
+			0.0
+		} else {
+			values.iter().sum::<f64>() / values.len() as f64
+		};
+		let max = |values: &[f64]| values.iter().copied().fold(0.0, f64::max);
+
+		features.mean_velocity = mean(&velocities);
+		features.max_velocity = max(&velocities);
+		features.mean_acceleration = mean(&accelerations);
+		features.max_acceleration = max(&accelerations);
+
+		if !pressures.is_empty() {
+			features.mean_pressure = mean(&pressures);
+			features.pressure_deviation = (pressures.iter()
+				.map(|pressure| (pressure - features.mean_pressure).powi(2))
+				.sum::<f64>() / pressures.len() as f64)
+				.sqrt();
+			features.min_pressure = pressures.iter().copied().fold(f64::INFINITY, f64::min);
+			features.max_pressure = max(&pressures);
+
+			features.width = bounds.2 - bounds.0;
+			features.height = bounds.3 - bounds.1;
+			features.aspect_ratio = if features.height > 0.0 {
+				features.width / features.height
+			} else {
+				0.0
+			};
+		}
+
+		features
+	}
+}
+
+/// The given duration, in milliseconds.
+fn millis(duration: std::time::Duration) -> f64 {
+	duration.as_secs_f64() * 1000.0
+}
+
+/// The number of times the given sequence of positions along an axis reverses
+/// its direction, after moving far enough in each direction.
+fn reversals<I>(positions: I) -> usize
+	where I: IntoIterator<Item = f64> {
+
+	let mut positions = positions.into_iter();
+	let mut anchor = match positions.next() {
+		Some(position) => position,
+		None => return 0
+	};
+
+	/* Follow the extreme reached in the current direction, and count a reversal
+	 * whenever the pen moves back far enough from it. */
+	let mut direction = 0.0;
+	let mut count = 0;
+	for position in positions {
+		let delta = position - anchor;
+		if direction == 0.0 {
+			if delta.abs() >= DIRECTION_CHANGE_DISTANCE {
+				direction = delta.signum();
+				anchor = position;
+			}
+		} else if delta * direction > 0.0 {
+			anchor = position;
+		} else if -delta * direction >= DIRECTION_CHANGE_DISTANCE {
+			direction = -direction;
+			anchor = position;
+			count += 1;
+		}
+	}
+
+	count
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use stu::Event;
+
+	/// The size of the screen the signatures are taken to be drawn on.
+	const SIZE: (f64, f64) = (100.0, 100.0);
+
+	/// A horizontal stroke across the screen at ten millimeters per second,
+	/// with events every five milliseconds, reported in batches of the given
+	/// number of events. Batches come in every five milliseconds times their
+	/// size, with the events in them stamped a microsecond apart.
+	fn stroke(batch: u32, pressure: impl Fn(u32) -> f64) -> EventPath {
+		let start = Instant::now();
+		(0..=200)
+			.map(|i| {
+				let x = 0.1 + f64::from(i) * 0.005 * 10.0 / SIZE.0;
+				let time = Duration::from_millis(u64::from(i / batch * batch) * 5)
+					+ Duration::from_micros(u64::from(i % batch));
+				Event::new(start + time, x, 0.5, pressure(i), true, true)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn evenly_spaced_events_have_steady_motion() {
+		let features = SignatureFeatures::new(&stroke(1, |_| 0.5), SIZE);
+
+		assert_eq!(features.strokes, 1);
+		assert_eq!(features.points, 201);
+		assert!((features.duration - 1000.0).abs() < 1e-6);
+		assert!((features.length - 10.0).abs() < 1e-6);
+		assert!((features.mean_velocity - 10.0).abs() < 1e-3);
+		assert!((features.max_velocity - 10.0).abs() < 1e-3);
+		assert!(features.max_acceleration < 1.0);
+		assert_eq!(features.direction_changes, 0);
+	}
+
+	#[test]
+	fn batched_events_have_steady_motion() {
+		/* Measured alone, the motion between the events of a batch would be
+		 * thousands of times faster than the pen. */
+		let features = SignatureFeatures::new(&stroke(4, |_| 0.5), SIZE);
+
+		assert_eq!(features.points, 201);
+		assert!((features.length - 10.0).abs() < 1e-6);
+		assert!((features.mean_velocity - 10.0).abs() < 0.1, "{:?}", features);
+		assert!(features.max_velocity < 11.0, "{:?}", features);
+		assert!(features.max_acceleration < 100.0, "{:?}", features);
+	}
+
+	#[test]
+	fn pressure_is_taken_as_reported() {
+		/* Any curve other than the linear one would move the mean away from
+		 * one half. */
+		let features = SignatureFeatures::new(
+			&stroke(1, |i| if i % 2 == 0 { 0.25 } else { 0.75 }),
+			SIZE);
+
+		assert!((features.mean_pressure - 0.5).abs() < 1e-2);
+		assert!((features.min_pressure - 0.25).abs() < 1e-9);
+		assert!((features.max_pressure - 0.75).abs() < 1e-9);
+		assert!((features.pressure_deviation - 0.25).abs() < 1e-2);
+	}
+
+	#[test]
+	fn empty_paths_have_no_features() {
+		let features = SignatureFeatures::new(&EventPath::new(), SIZE);
+		assert_eq!(features, SignatureFeatures {
+			version: FEATURES_VERSION,
+			..Default::default()
+		});
+	}
+}
@@ -0,0 +1,239 @@
+use serde::{Serialize, Deserialize};
+
+/// The methods by which the position of the pen may be interpolated between
+/// two consecutive events in a stroke.
+///
+/// All of these methods pass through every one of the events in the stroke,
+/// and differ only in the shape of the curve they take between them.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+	/// Straight lines between the events.
+	Linear,
+	/// A uniform Catmull-Rom spline, which is smooth, but may overshoot and
+	/// form small loops around sharp turns in the stroke.
+	CatmullRom,
+	/// A centripetal Catmull-Rom spline, which is smooth and never forms loops
+	/// or cusps within a segment, even around sharp turns.
+	#[default]
+	CentripetalCatmullRom,
+	/// A cubic Bézier fit, with the control points placed along the tangent of
+	/// the stroke, at distances proportional to the lengths of the neighbouring
+	/// segments. This keeps short segments next to long ones from bulging.
+	Bezier,
+}
+impl Interpolation {
+	/// Interpolates the position between the second and third of the given
+	/// points, with the first and the last ones being the points right before
+	/// and right after them, respectively.
+	///
+	/// The curve is at the second point when `t` is `0.0` and at the third one
+	/// when `t` is `1.0`.
+	pub fn interpolate(self, points: [(f64, f64); 4], t: f64) -> (f64, f64) {
+		let [p0, p1, p2, p3] = points;
+		match self {
+			Self::Linear => lerp(p1, p2, t),
+			Self::CatmullRom => {
+				/* Uniform Catmull-Rom is the Bézier curve whose control points
+				 * lie a sixth of the chord between the neighbours away. */
+				let c1 = add(p1, scale(sub(p2, p0), 1.0 / 6.0));
+				let c2 = sub(p2, scale(sub(p3, p1), 1.0 / 6.0));
+
+				bezier(p1, c1, c2, p2, t)
+			},
+			Self::CentripetalCatmullRom => barry_goldman(points, 0.5, t),
+			Self::Bezier => {
+				let d01 = distance(p0, p1);
+				let d12 = distance(p1, p2);
+				let d23 = distance(p2, p3);
+
+				/* The smoothing factor is picked so that evenly spaced points
+				 * give the same curve as the uniform Catmull-Rom spline. */
+				const SMOOTHING: f64 = 1.0 / 3.0;
+				let k1 = if d01 + d12 > 0.0 { SMOOTHING * d12 / (d01 + d12) } else { 0.0 };
+				let k2 = if d12 + d23 > 0.0 { SMOOTHING * d12 / (d12 + d23) } else { 0.0 };
+
+				let c1 = add(p1, scale(sub(p2, p0), k1));
+				let c2 = sub(p2, scale(sub(p3, p1), k2));
+
+				bezier(p1, c1, c2, p2, t)
+			}
+		}
+	}
+}
+
+/// Evaluates a Catmull-Rom spline with knots spaced by the distance between
+/// the points raised to the given power, using the Barry-Goldman pyramid.
+fn barry_goldman(points: [(f64, f64); 4], alpha: f64, t: f64) -> (f64, f64) {
+	let [p0, p1, p2, p3] = points;
+
+	let t0 = 0.0;
+	let t1 = t0 + distance(p0, p1).powf(alpha);
+	let t2 = t1 + distance(p1, p2).powf(alpha);
+	let t3 = t2 + distance(p2, p3).powf(alpha);
+
+	/* Coincident points leave the knots undefined. In that case, the segment
+	 * is either degenerate or has no neighbour to curve towards, so a straight
+	 * line is as good as anything. */
+	if t1 <= t0 || t2 <= t1 || t3 <= t2 {
+		return lerp(p1, p2, t)
+	}
+
+	let t = t.lerp(t1, t2);
+	let blend = |a: (f64, f64), b: (f64, f64), ta: f64, tb: f64|
+		lerp(a, b, (t - ta) / (tb - ta));
+
+	let a1 = blend(p0, p1, t0, t1);
+	let a2 = blend(p1, p2, t1, t2);
+	let a3 = blend(p2, p3, t2, t3);
+
+	let b1 = blend(a1, a2, t0, t2);
+	let b2 = blend(a2, a3, t1, t3);
+
+	blend(b1, b2, t1, t2)
+}
+
+/// Evaluates the cubic Bézier curve with the given control points.
+fn bezier(
+	p0: (f64, f64),
+	p1: (f64, f64),
+	p2: (f64, f64),
+	p3: (f64, f64),
+	t: f64) -> (f64, f64) {
+
+	let a = lerp(p0, p1, t);
+	let b = lerp(p1, p2, t);
+	let c = lerp(p2, p3, t);
+
+	lerp(lerp(a, b, t), lerp(b, c, t), t)
+}
+
+/// Linearly interpolates between two points.
+fn lerp(a: (f64, f64), b: (f64, f64), t: f64) -> (f64, f64) {
+	(t.lerp(a.0, b.0), t.lerp(a.1, b.1))
+}
+
+/// The sum of two vectors.
+fn add(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+	(a.0 + b.0, a.1 + b.1)
+}
+
+/// The difference between two vectors.
+fn sub(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+	(a.0 - b.0, a.1 - b.1)
+}
+
+/// A vector scaled by the given factor.
+fn scale(a: (f64, f64), k: f64) -> (f64, f64) {
+	(a.0 * k, a.1 * k)
+}
+
+/// The distance between two points.
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+	(b.0 - a.0).hypot(b.1 - a.1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// An uneven, winding stroke, with segments of very different lengths.
+	const STROKE: [(f64, f64); 6] = [
+		(0.0, 0.0),
+		(0.1, 0.05),
+		(0.5, 0.1),
+		(0.55, 0.4),
+		(0.2, 0.6),
+		(0.25, 0.62),
+	];
+
+	const ALL: [Interpolation; 4] = [
+		Interpolation::Linear,
+		Interpolation::CatmullRom,
+		Interpolation::CentripetalCatmullRom,
+		Interpolation::Bezier,
+	];
+
+	/// The points around the segment starting at the given event of the stroke.
+	fn window(i: usize) -> [(f64, f64); 4] {
+		[STROKE[i - 1], STROKE[i], STROKE[i + 1], STROKE[i + 2]]
+	}
+
+	/// The derivative of the curve of the segment starting at the given event
+	/// of the stroke, estimated at the given end of the segment.
+	fn tangent(interpolation: Interpolation, i: usize, t: f64) -> (f64, f64) {
+		const H: f64 = 1e-6;
+		let (a, b) = if t < 0.5 { (t, t + H) } else { (t - H, t) };
+		scale(
+			sub(
+				interpolation.interpolate(window(i), b),
+				interpolation.interpolate(window(i), a)),
+			1.0 / H)
+	}
+
+	fn close(a: (f64, f64), b: (f64, f64), tolerance: f64) -> bool {
+		distance(a, b) <= tolerance
+	}
+
+	#[test]
+	fn curves_pass_through_knots() {
+		for interpolation in ALL.iter().copied() {
+			for i in 1..STROKE.len() - 2 {
+				let points = window(i);
+				assert!(close(interpolation.interpolate(points, 0.0), points[1], 1e-12),
+					"{:?} misses the start of segment {}", interpolation, i);
+				assert!(close(interpolation.interpolate(points, 1.0), points[2], 1e-12),
+					"{:?} misses the end of segment {}", interpolation, i);
+			}
+		}
+	}
+
+	#[test]
+	fn coincident_points_stay_finite() {
+		let points = [(0.5, 0.5); 4];
+		for interpolation in ALL.iter().copied() {
+			for t in [0.0, 0.5, 1.0].iter().copied() {
+				assert_eq!(interpolation.interpolate(points, t), (0.5, 0.5), "{:?}", interpolation);
+			}
+		}
+	}
+
+	#[test]
+	fn catmull_rom_tangents_match_at_joins() {
+		for i in 1..STROKE.len() - 3 {
+			let before = tangent(Interpolation::CatmullRom, i, 1.0);
+			let after = tangent(Interpolation::CatmullRom, i + 1, 0.0);
+
+			assert!(close(before, after, 1e-4),
+				"joint {} has tangents {:?} and {:?}", i + 1, before, after);
+		}
+	}
+
+	#[test]
+	fn smooth_tangents_point_the_same_way_at_joins() {
+		/* The Bézier fit scales the tangent by the length of each segment, so
+		 * only the direction of the curve is continuous across joins. */
+		for interpolation in [Interpolation::Bezier, Interpolation::CentripetalCatmullRom].iter().copied() {
+			for i in 1..STROKE.len() - 3 {
+				let before = tangent(interpolation, i, 1.0);
+				let after = tangent(interpolation, i + 1, 0.0);
+				let before = scale(before, 1.0 / before.0.hypot(before.1));
+				let after = scale(after, 1.0 / after.0.hypot(after.1));
+
+				assert!(close(before, after, 1e-4),
+					"{:?} turns at joint {}: {:?} and {:?}", interpolation, i + 1, before, after);
+			}
+		}
+	}
+
+	#[test]
+	fn even_spacing_matches_catmull_rom() {
+		let points = [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0)];
+		for t in [0.25, 0.5, 0.75].iter().copied() {
+			assert!(close(
+				Interpolation::Bezier.interpolate(points, t),
+				Interpolation::CatmullRom.interpolate(points, t),
+				1e-12));
+		}
+	}
+}
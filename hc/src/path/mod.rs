@@ -0,0 +1,500 @@
+use std::ops::Range;
+use stu::{Event, Stroke, Simplifier, Tolerance};
+
+/// Methods for interpolating the position of the pen between events.
+mod interpolation;
+pub use interpolation::Interpolation;
+
+/// Rendering of events into pictures.
+mod canvas;
+pub use canvas::{EventCanvas, CanvasStyle};
+
+/// Rendering of paths at arbitrary sizes and resolutions.
+mod render;
+pub use render::{Length, RenderOptions};
+
+/// Mappings between the progress along a trace and the events in it.
+mod parameterization;
+pub use parameterization::{Parameterization, Gaps};
+
+/// Comparison of signatures against enrolled references.
+mod verification;
+pub use verification::{VerificationOptions, Verification, Enrollment, VerificationError};
+
+/// Descriptive features of signatures.
+mod features;
+pub use features::SignatureFeatures;
+
+/// Checks of the quality of captured signatures.
+mod validation;
+pub use validation::{ValidationRules, Rejection};
+
+/// A structure for generating paths from events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventPath {
+	/// List of events in this path, in the order they were processed in.
+	///
+	/// Events are keyed by their position in this list rather than by their
+	/// timestamps, as the timestamps of events reported in the same batch may
+	/// be equal, and we don't want to lose any of them.
+	events: Vec<Event>,
+}
+impl EventPath {
+	/// Creates a new, empty path.
+	pub fn new() -> Self {
+		Self {
+			events: Default::default()
+		}
+	}
+
+	/// Appends a new event to this path.
+	///
+	/// Events are never dropped nor replaced by this function. Instead, if the
+	/// given event looks suspicious when compared to the last event in this
+	/// path, it is added anyway, and the problem with it is returned.
+	#[must_use = "anomalies should be reported rather than silently ignored"]
+	pub fn process(&mut self, event: Event) -> Option<Anomaly> {
+		let anomaly = self.events.last().and_then(|last| Anomaly::between(last, &event));
+
+		self.events.push(event);
+		anomaly
+	}
+
+	/// The problems found with the events in this path, along with the index
+	/// of the event each of them was found with.
+	pub fn anomalies(&self) -> Vec<(usize, Anomaly)> {
+		self.events.windows(2)
+			.enumerate()
+			.filter_map(|(i, pair)| Anomaly::between(&pair[0], &pair[1]).map(|anomaly| (i + 1, anomaly)))
+			.collect()
+	}
+
+	/// Clears all of the events in this path.
+	pub fn clear(&mut self) {
+		self.events.clear()
+	}
+
+	/// The events in this path, in the order they were processed in.
+	pub fn events(&self) -> impl Iterator<Item = &Event> + '_ {
+		self.events.iter()
+	}
+
+	/// Splits the events in this path into strokes, including both the ones
+	/// that left ink and the movements of the pen between them.
+	pub fn segments(&self) -> Vec<Stroke> {
+		Stroke::split(self.events.iter().copied())
+	}
+
+	/// The strokes in this path that left ink, in the order they were written.
+	pub fn strokes(&self) -> Vec<Stroke> {
+		self.segments()
+			.into_iter()
+			.filter(Stroke::is_ink)
+			.collect()
+	}
+
+	/// The ranges of indices of the events in each of the ink strokes in this
+	/// path, in the order the strokes were written.
+	fn stroke_ranges(&self) -> Vec<Range<usize>> {
+		let mut ranges = Vec::new();
+		let mut start = None;
+
+		for (i, event) in self.events.iter().enumerate() {
+			match (start, event.touching()) {
+				(None, true) => start = Some(i),
+				(Some(first), false) => {
+					ranges.push(first..i);
+					start = None;
+				},
+				_ => {}
+			}
+		}
+		if let Some(first) = start {
+			ranges.push(first..self.events.len());
+		}
+
+		ranges
+	}
+
+	/// Replaces the ink stroke with the given index, as given by [`strokes()`],
+	/// with the given events, returning the stroke that got replaced.
+	///
+	/// The new events take the place of the old ones in the order of the path.
+	/// If there is no stroke with the given index, the path is left unchanged
+	/// and this function returns `None`.
+	///
+	/// [`strokes()`]: Self::strokes
+	pub fn replace_stroke<I>(&mut self, index: usize, events: I) -> Option<Stroke>
+		where I: IntoIterator<Item = Event> {
+
+		let range = self.stroke_ranges().into_iter().nth(index)?;
+		let stroke = Stroke::split(self.events.splice(range, events))
+			.pop()
+			.expect("stroke ranges always cover exactly one stroke");
+
+		Some(stroke)
+	}
+
+	/// Removes the ink stroke with the given index, as given by [`strokes()`],
+	/// returning it. If there is no stroke with the given index, the path is
+	/// left unchanged and this function returns `None`.
+	///
+	/// [`strokes()`]: Self::strokes
+	pub fn remove_stroke(&mut self, index: usize) -> Option<Stroke> {
+		self.replace_stroke(index, std::iter::empty())
+	}
+
+	/// Simplifies every ink stroke in this path with the given algorithm and
+	/// tolerance, leaving the movements of the pen between strokes untouched.
+	pub fn simplify(&mut self, simplifier: Simplifier, tolerance: Tolerance) {
+		/* Go from the last stroke to the first, so that the ranges of the
+		 * strokes we have yet to get to are not shifted by the changes. */
+		for range in self.stroke_ranges().into_iter().rev() {
+			let events = simplifier.simplify(&self.events[range.clone()], tolerance);
+			self.events.splice(range, events);
+		}
+	}
+
+	/// Generate a tracing along the curve in this structure, interpolating
+	/// between the events in it with the given method.
+	///
+	/// The trace starts out evenly spaced over the events, which can be changed
+	/// with [`Trace::with_parameterization()`].
+	///
+	/// [`Trace::with_parameterization()`]: Trace::with_parameterization
+	pub fn trace(&self, interpolation: Interpolation) -> Trace {
+		let events = self.events
+			.iter()
+			.collect::<Vec<_>>()
+			.into_boxed_slice();
+		let knots = parameterization::knots(
+			&events[..],
+			Parameterization::Index,
+			Gaps::Keep,
+			(1.0, 1.0));
+
+		Trace {
+			events,
+			knots,
+			interpolation
+		}
+	}
+}
+impl Default for EventPath {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+impl std::iter::FromIterator<Event> for EventPath {
+	/// Collects the given events into a path, keeping all of them, just like
+	/// [`process()`] does. The problems found with them may be looked up with
+	/// [`anomalies()`] afterwards.
+	///
+	/// [`process()`]: EventPath::process
+	/// [`anomalies()`]: EventPath::anomalies
+	fn from_iter<I: IntoIterator<Item = Event>>(events: I) -> Self {
+		Self {
+			events: events.into_iter().collect()
+		}
+	}
+}
+
+/// The problems that may be found with an event as it gets added to a path.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[must_use]
+pub enum Anomaly {
+	/// The event is identical to the one before it, timestamp included.
+	Duplicate,
+	/// The event happened before the one that was added before it.
+	OutOfOrder,
+}
+impl Anomaly {
+	/// The problem with the given event, if any, when it comes right after the
+	/// given last one. Events reported at the same time are not a problem, as
+	/// devices report events in batches that may share a timestamp.
+	fn between(last: &Event, event: &Event) -> Option<Self> {
+		if last == event {
+			Some(Self::Duplicate)
+		} else if event.time() < last.time() {
+			Some(Self::OutOfOrder)
+		} else {
+			None
+		}
+	}
+}
+
+/// A tracing along a path generated by [`EventPath`].
+///
+/// [`EventPath`]: EventPath
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace<'a> {
+	/// A list of events, in the order they were processed in. This is a list
+	/// rather than other kinds of containers because it allows for us to
+	/// uniformly access its elements, which avoids the clustering of events.
+	events: Box<[&'a Event]>,
+	/// The position of each of the events along the trace, in increasing order,
+	/// starting at zero.
+	knots: Box<[f64]>,
+	/// The method used to interpolate between events within a stroke.
+	interpolation: Interpolation,
+}
+impl Trace<'_> {
+	/// Changes how the progress along this trace maps to the events in it, and
+	/// how the movement of the pen between strokes is handled.
+	///
+	/// Distances along the trace are measured in a space with the given
+	/// dimensions, such as the area of the screen the trace is drawn over.
+	pub fn with_parameterization(
+		mut self,
+		parameterization: Parameterization,
+		gaps: Gaps,
+		size: (f64, f64)) -> Self {
+
+		self.knots = parameterization::knots(&self.events[..], parameterization, gaps, size);
+		self
+	}
+
+	/// Get the point along this path at the given time.
+	///
+	/// The minimum value for the time is `0.0`, at the start of the path, and
+	/// the maximum value is `1.0`, at the end of the path. Values greater than
+	/// the maximum or smaller than the minimum will be clamped.
+	///
+	/// Between two events that are both part of the same stroke, the position
+	/// is interpolated using the method this trace was created with, while the
+	/// movement of the pen while lifted is always interpolated linearly, so
+	/// that strokes never get bent towards each other.
+	pub fn get(&self, t: f64) -> Option<Point> {
+		if self.events.len() == 0 { return None }
+		if self.events.len() == 1 {
+			return Some(Point {
+				x: self.events[0].x(),
+				y: self.events[0].y(),
+				pressure: self.events[0].pressure(),
+				touch: self.events[0].touching()
+			})
+		}
+
+		/* Find the segment the given time falls in, along with how far into
+		 * that segment it is. */
+		let total = self.knots[self.knots.len() - 1];
+		let t = t.clamp(0.0, 1.0) * total;
+
+		let i = self.knots
+			.partition_point(|knot| *knot <= t)
+			.saturating_sub(1)
+			.min(self.events.len() - 2);
+		let j = i + 1;
+
+		let span = self.knots[j] - self.knots[i];
+		let f = if span > 0.0 {
+			((t - self.knots[i]) / span).clamp(0.0, 1.0)
+		} else {
+			0.0
+		};
+
+		let a = self.events[i];
+		let b = self.events[j];
+
+		let (x, y) = if a.touching() && b.touching() {
+			let p1 = (a.x(), a.y());
+			let p2 = (b.x(), b.y());
+
+			/* Only events in the same stroke may shape the curve. At the ends
+			 * of the stroke, mirror the neighbouring point instead. */
+			let p0 = i.checked_sub(1)
+				.map(|k| self.events[k])
+				.filter(|event| event.touching())
+				.map(|event| (event.x(), event.y()))
+				.unwrap_or((2.0 * p1.0 - p2.0, 2.0 * p1.1 - p2.1));
+			let p3 = self.events.get(j + 1)
+				.filter(|event| event.touching())
+				.map(|event| (event.x(), event.y()))
+				.unwrap_or((2.0 * p2.0 - p1.0, 2.0 * p2.1 - p1.1));
+
+			let (x, y) = self.interpolation.interpolate([p0, p1, p2, p3], f);
+			(x.clamp(0.0, 1.0), y.clamp(0.0, 1.0))
+		} else {
+			(f.lerp(a.x(), b.x()), f.lerp(a.y(), b.y()))
+		};
+
+		Some(Point {
+			x,
+			y,
+			pressure: f.lerp(a.pressure(), b.pressure()),
+			touch: a.touching()
+		})
+	}
+}
+
+/// A point on the screen along a curve.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point {
+	/// The position of the cursor in the horizontal axis.
+	pub x: f64,
+	/// The position of the cursor in the vertical axis.
+	pub y: f64,
+	/// The pressure of the pen on the screen at this point.
+	pub pressure: f64,
+	/// Whether the pen is touching the screen at this point.
+	pub touch: bool,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn bursts_of_equal_timestamps_are_kept() {
+		/* Events reported in the same batch share their timestamp. */
+		let time = Instant::now();
+		let mut path = EventPath::new();
+		for i in 0..8 {
+			let anomaly = path.process(Event::new(time, f64::from(i) / 10.0, 0.5, 0.5, true, true));
+			assert_eq!(anomaly, None);
+		}
+
+		assert_eq!(path.events().count(), 8);
+		assert!(path.events().all(|event| event.time() == time));
+		assert!(path.anomalies().is_empty());
+		assert_eq!(path.strokes().len(), 1);
+	}
+
+	#[test]
+	fn anomalous_events_are_kept() {
+		let time = Instant::now() + Duration::from_secs(1);
+		let event = Event::new(time, 0.5, 0.5, 0.5, true, true);
+		let early = Event::new(time - Duration::from_millis(5), 0.6, 0.5, 0.5, true, true);
+
+		let mut path = EventPath::new();
+		assert_eq!(path.process(event), None);
+		assert_eq!(path.process(event), Some(Anomaly::Duplicate));
+		assert_eq!(path.process(early), Some(Anomaly::OutOfOrder));
+
+		assert_eq!(path.events().copied().collect::<Vec<_>>(), [event, event, early]);
+		assert_eq!(path.anomalies(), [(1, Anomaly::Duplicate), (2, Anomaly::OutOfOrder)]);
+	}
+
+	#[test]
+	fn collected_paths_keep_every_event() {
+		let time = Instant::now() + Duration::from_secs(1);
+		let events = [
+			Event::new(time, 0.1, 0.1, 0.5, true, true),
+			Event::new(time, 0.1, 0.1, 0.5, true, true),
+			Event::new(time, 0.2, 0.1, 0.5, true, true),
+			Event::new(time - Duration::from_millis(1), 0.3, 0.1, 0.5, true, true),
+		];
+		let path = events.iter().copied().collect::<EventPath>();
+
+		assert_eq!(path.events().copied().collect::<Vec<_>>(), events);
+		assert_eq!(path.anomalies(), [(1, Anomaly::Duplicate), (3, Anomaly::OutOfOrder)]);
+	}
+
+	/// A path of three ink strokes, of two, three and two events, with the pen
+	/// hovering for two events between each of them.
+	fn three_strokes() -> EventPath {
+		let time = Instant::now();
+		[
+			(0.1, true), (0.2, true),
+			(0.3, false), (0.4, false),
+			(0.5, true), (0.6, true), (0.7, true),
+			(0.8, false), (0.9, false),
+			(1.0, true), (1.0, true),
+		]
+			.iter()
+			.enumerate()
+			.map(|(i, (x, touching))| Event::new(
+				time + Duration::from_millis(i as u64 * 5),
+				*x,
+				0.5,
+				if *touching { 0.5 } else { 0.0 },
+				*touching,
+				true))
+			.collect()
+	}
+
+	#[test]
+	fn strokes_split_at_pen_lifts() {
+		let path = three_strokes();
+
+		assert_eq!(path.segments().iter().map(Stroke::len).collect::<Vec<_>>(), [2, 2, 3, 2, 2]);
+		assert_eq!(path.strokes().iter().map(Stroke::len).collect::<Vec<_>>(), [2, 3, 2]);
+		assert!(path.strokes().iter().all(Stroke::is_ink));
+	}
+
+	#[test]
+	fn strokes_of_paths_starting_lifted() {
+		let time = Instant::now();
+		let path = [false, false, true, true]
+			.iter()
+			.enumerate()
+			.map(|(i, touching)| Event::new(
+				time + Duration::from_millis(i as u64),
+				0.5,
+				0.5,
+				0.5,
+				*touching,
+				true))
+			.collect::<EventPath>();
+
+		assert_eq!(path.segments().len(), 2);
+		assert!(!path.segments()[0].is_ink());
+		assert_eq!(path.strokes().len(), 1);
+		assert_eq!(path.strokes()[0].events(), &path.events[2..]);
+	}
+
+	#[test]
+	fn removing_the_middle_stroke_merges_hovering() {
+		let mut path = three_strokes();
+		let removed = path.remove_stroke(1).unwrap();
+
+		assert_eq!(removed.events().iter().map(Event::x).collect::<Vec<_>>(), [0.5, 0.6, 0.7]);
+		assert_eq!(path.events().count(), 8);
+		assert_eq!(path.segments().iter().map(Stroke::len).collect::<Vec<_>>(), [2, 4, 2]);
+		assert_eq!(
+			path.strokes().iter().map(|stroke| stroke.events()[0].x()).collect::<Vec<_>>(),
+			[0.1, 1.0]);
+	}
+
+	#[test]
+	fn replacing_a_stroke_with_more_events() {
+		let mut path = three_strokes();
+		let time = path.strokes()[1].start();
+		let events = (0..5)
+			.map(|i| Event::new(time + Duration::from_millis(i), 0.25, 0.75, 0.5, true, true))
+			.collect::<Vec<_>>();
+		let replaced = path.replace_stroke(1, events.iter().copied()).unwrap();
+
+		assert_eq!(replaced.len(), 3);
+		assert_eq!(path.events().count(), 13);
+		assert_eq!(path.strokes().iter().map(Stroke::len).collect::<Vec<_>>(), [2, 5, 2]);
+		assert_eq!(path.strokes()[1].events(), &events[..]);
+		assert_eq!(path.segments().len(), 5);
+	}
+
+	#[test]
+	fn replacing_a_stroke_with_fewer_events() {
+		let mut path = three_strokes();
+		let event = path.strokes()[0].events()[0];
+		path.replace_stroke(0, std::iter::once(event)).unwrap();
+
+		assert_eq!(path.events().count(), 10);
+		assert_eq!(path.strokes().iter().map(Stroke::len).collect::<Vec<_>>(), [1, 3, 2]);
+	}
+
+	#[test]
+	fn out_of_range_strokes_are_left_alone() {
+		let mut path = three_strokes();
+		let original = path.clone();
+		let event = path.strokes()[0].events()[0];
+
+		assert_eq!(path.remove_stroke(3), None);
+		assert_eq!(path.replace_stroke(3, std::iter::once(event)), None);
+		assert_eq!(path, original);
+
+		let mut empty = EventPath::new();
+		assert_eq!(empty.remove_stroke(0), None);
+		assert_eq!(empty, EventPath::new());
+	}
+}
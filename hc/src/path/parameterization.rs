@@ -0,0 +1,193 @@
+use serde::{Serialize, Deserialize};
+use stu::Event;
+
+/// The ways in which the progress along a trace may be mapped to the events
+/// in it.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Parameterization {
+	/// Every pair of consecutive events takes up the same share of the trace,
+	/// regardless of how far apart they are in either time or space.
+	#[default]
+	Index,
+	/// Events are placed according to the time they were reported at, so that
+	/// the trace keeps the rhythm of the signer.
+	Time,
+	/// Events are placed according to the distance travelled by the pen, so
+	/// that the trace moves at a constant speed. The distance is approximated
+	/// by the straight lines between the events, and measured in the space the
+	/// trace is drawn over, so that both axes count the same.
+	ArcLength,
+}
+
+/// The ways in which the movement of the pen between strokes may be handled.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Gaps {
+	/// The movement between strokes takes up as much of the trace as the
+	/// parameterization gives it.
+	#[default]
+	Keep,
+	/// The movement between strokes is shortened to take up no more of the
+	/// trace than a few of the segments within the strokes do, on average.
+	Compress,
+}
+impl Gaps {
+	/// The number of average stroke segments a compressed gap may take up.
+	const COMPRESSED_LENGTH: f64 = 4.0;
+}
+
+/// Computes the position of each of the given events along a trace, as an
+/// increasing sequence starting at zero.
+///
+/// The normalized positions of the events are scaled by the given dimensions
+/// of the space the trace is drawn over before distances are measured in it,
+/// as the axes of a screen that isn't square don't have the same length.
+///
+/// If the events can't be told apart under the given parameterization, such
+/// as when all of them were reported at the same time, they fall back to being
+/// evenly spaced.
+pub fn knots(
+	events: &[&Event],
+	parameterization: Parameterization,
+	gaps: Gaps,
+	size: (f64, f64)) -> Box<[f64]> {
+
+	if events.is_empty() { return Box::new([]) }
+
+	let mut lengths = events.windows(2)
+		.map(|pair| {
+			let (a, b) = (pair[0], pair[1]);
+			match parameterization {
+				Parameterization::Index => 1.0,
+				Parameterization::Time => b.time()
+					.saturating_duration_since(a.time())
+					.as_secs_f64(),
+				Parameterization::ArcLength => ((b.x() - a.x()) * size.0).hypot((b.y() - a.y()) * size.1),
+			}
+		})
+		.collect::<Vec<_>>();
+
+	if gaps == Gaps::Compress {
+		compress_gaps(events, &mut lengths[..]);
+	}
+
+	let total = lengths.iter().sum::<f64>();
+	if !(total > 0.0 && total.is_finite()) {
+		lengths.iter_mut().for_each(|length| *length = 1.0);
+	}
+
+	std::iter::once(0.0)
+		.chain(lengths.iter().scan(0.0, |position, length| {
+			*position += length;
+			Some(*position)
+		}))
+		.collect::<Vec<_>>()
+		.into_boxed_slice()
+}
+
+/// Shortens the runs of segments between strokes, given the lengths of all of
+/// the segments between the given events.
+fn compress_gaps(events: &[&Event], lengths: &mut [f64]) {
+	let in_stroke = |i: usize| events[i].touching() && events[i + 1].touching();
+
+	let (sum, count) = (0..lengths.len())
+		.filter(|i| in_stroke(*i))
+		.fold((0.0, 0usize), |(sum, count), i| (sum + lengths[i], count + 1));
+	if count == 0 {
+		/* Without any strokes, everything is a gap, and there is nothing to
+		 * make room for by compressing them. */
+		return
+	}
+	let limit = sum / count as f64 * Gaps::COMPRESSED_LENGTH;
+
+	let mut i = 0;
+	while i < lengths.len() {
+		if in_stroke(i) {
+			i += 1;
+			continue
+		}
+
+		let start = i;
+		while i < lengths.len() && !in_stroke(i) { i += 1 }
+
+		/* Scale the whole run down evenly, so that the shape of the movement
+		 * within the gap is kept. */
+		let run = &mut lengths[start..i];
+		let length = run.iter().sum::<f64>();
+		if length > limit {
+			let scale = limit / length;
+			run.iter_mut().for_each(|length| *length *= scale);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, Instant};
+
+	/// Events at the given positions, in milliseconds and normalized
+	/// coordinates, along with whether the pen touches the screen at them.
+	fn events(points: &[(u64, f64, f64, bool)]) -> Vec<Event> {
+		let start = Instant::now();
+		points.iter()
+			.map(|(time, x, y, touching)| Event::new(
+				start + Duration::from_millis(*time),
+				*x,
+				*y,
+				if *touching { 0.5 } else { 0.0 },
+				*touching,
+				true))
+			.collect()
+	}
+
+	#[test]
+	fn defaults_keep_even_spacing() {
+		let events = events(&[(0, 0.0, 0.0, true), (5, 0.5, 0.0, true), (100, 0.6, 0.0, true)]);
+		let events = events.iter().collect::<Vec<_>>();
+
+		assert_eq!(
+			&knots(&events, Default::default(), Default::default(), (1.0, 1.0))[..],
+			&[0.0, 1.0, 2.0]);
+	}
+
+	#[test]
+	fn time_follows_timestamps() {
+		let events = events(&[(0, 0.0, 0.0, true), (10, 0.5, 0.0, true), (40, 0.6, 0.0, true)]);
+		let events = events.iter().collect::<Vec<_>>();
+		let knots = knots(&events, Parameterization::Time, Gaps::Keep, (1.0, 1.0));
+
+		assert!((knots[1] - 0.010).abs() < 1e-9 && (knots[2] - 0.040).abs() < 1e-9, "{:?}", knots);
+	}
+
+	#[test]
+	fn arc_length_measures_both_axes_alike() {
+		/* On a screen twice as wide as it is tall, half of its width is as
+		 * long as all of its height. */
+		let events = events(&[(0, 0.0, 0.0, true), (1, 0.5, 0.0, true), (2, 0.5, 1.0, true)]);
+		let events = events.iter().collect::<Vec<_>>();
+		let knots = knots(&events, Parameterization::ArcLength, Gaps::Keep, (800.0, 400.0));
+
+		assert_eq!(&knots[..], &[0.0, 400.0, 800.0]);
+	}
+
+	#[test]
+	fn compressed_gaps_are_shortened() {
+		let events = events(&[
+			(0, 0.0, 0.0, true),
+			(1, 0.01, 0.0, true),
+			(2, 0.5, 0.0, false),
+			(3, 0.98, 0.0, true),
+			(4, 0.99, 0.0, true),
+		]);
+		let events = events.iter().collect::<Vec<_>>();
+		let knots = knots(&events, Parameterization::ArcLength, Gaps::Compress, (1.0, 1.0));
+		let lengths = knots.windows(2).map(|pair| pair[1] - pair[0]).collect::<Vec<_>>();
+
+		/* The strokes are untouched, while the gap between them takes up no
+		 * more than a few of their segments. */
+		assert!((lengths[0] - 0.01).abs() < 1e-9 && (lengths[3] - 0.01).abs() < 1e-9, "{:?}", lengths);
+		assert!((lengths[1] + lengths[2] - 0.01 * Gaps::COMPRESSED_LENGTH).abs() < 1e-9, "{:?}", lengths);
+	}
+}
@@ -0,0 +1,216 @@
+use serde::{Serialize, Deserialize};
+use stu::{Event, Stroke, Bounds, PressureCurve};
+use crate::path::{EventPath, EventCanvas, CanvasStyle};
+
+/// A length on a rendered picture, in either pixels or physical units.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Length {
+	/// A length in pixels, regardless of the resolution.
+	Pixels(f64),
+	/// A length in millimeters, converted to pixels at the resolution of the
+	/// picture.
+	Millimeters(f64),
+	/// A length in inches, converted to pixels at the resolution of the
+	/// picture.
+	Inches(f64),
+}
+impl Length {
+	/// This length in pixels, at the given resolution, in dots per inch.
+	pub fn pixels(self, dpi: f64) -> f64 {
+		match self {
+			Self::Pixels(pixels) => pixels,
+			Self::Millimeters(millimeters) => millimeters / 25.4 * dpi,
+			Self::Inches(inches) => inches * dpi,
+		}
+	}
+}
+
+/// The parameters of the rendering of a path into a picture.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+	/// The width of the picture.
+	pub width: Length,
+	/// The height of the picture.
+	pub height: Length,
+	/// The resolution of the picture, in dots per inch.
+	pub dpi: f64,
+	/// The space left blank between the ink and each of the edges.
+	pub padding: Length,
+	/// Whether the picture is framed around the ink, rather than around the
+	/// whole screen of the device.
+	pub crop: bool,
+	/// The style the strokes are rendered with. The widths of the strokes are
+	/// given in pixels of the screen of the device, and get scaled along with
+	/// the rest of the path.
+	pub style: CanvasStyle,
+}
+impl RenderOptions {
+	/// The dimensions of the picture, in whole pixels.
+	pub fn dimensions(&self) -> (u32, u32) {
+		let pixels = |length: Length| length.pixels(self.dpi).round().max(1.0) as u32;
+		(pixels(self.width), pixels(self.height))
+	}
+}
+impl Default for RenderOptions {
+	fn default() -> Self {
+		Self {
+			width: Length::Millimeters(60.0),
+			height: Length::Millimeters(25.0),
+			dpi: 300.0,
+			padding: Length::Millimeters(2.0),
+			crop: true,
+			style: Default::default(),
+		}
+	}
+}
+
+impl EventPath {
+	/// Renders this path into a new canvas, as described by the given options.
+	///
+	/// The path is taken to have been drawn on a screen with the given
+	/// dimensions, in pixels, which are used to keep the aspect ratio of the
+	/// path and to scale the widths of the strokes. The pressure recorded in
+	/// the path is shaped by the given curve before it sets their widths.
+	pub fn render(&self, screen: (u32, u32), options: &RenderOptions, curve: &PressureCurve) -> EventCanvas {
+		let (width, height) = options.dimensions();
+		let padding = options.padding.pixels(options.dpi).max(0.0);
+
+		/* Frame either the ink or the whole screen. */
+		let full = Bounds { left: 0.0, top: 0.0, right: 1.0, bottom: 1.0 };
+		let frame = if options.crop {
+			self.segments()
+				.iter()
+				.filter(|stroke| stroke.is_ink())
+				.map(Stroke::bounds)
+				.reduce(|a, b| a.union(&b))
+				.unwrap_or(full)
+		} else {
+			full
+		};
+
+		/* Positions are measured the same way the canvas measures them, with
+		 * the edges of the normalized space at the centers of the first and
+		 * last pixels, both on the screen and on the picture. */
+		let (sw, sh) = (
+			f64::from(screen.0.saturating_sub(1).max(1)),
+			f64::from(screen.1.saturating_sub(1).max(1)));
+		let (cw, ch) = (
+			f64::from(width - 1).max(1.0),
+			f64::from(height - 1).max(1.0));
+
+		/* Scale the frame uniformly, in screen pixels, so that it fits inside
+		 * the padding, and center it. */
+		let (fw, fh) = (frame.width() * sw, frame.height() * sh);
+		let (aw, ah) = (
+			(cw - 2.0 * padding).max(1.0),
+			(ch - 2.0 * padding).max(1.0));
+
+		let scale = match (fw > 0.0, fh > 0.0) {
+			(true, true) => (aw / fw).min(ah / fh),
+			(true, false) => aw / fw,
+			(false, true) => ah / fh,
+			(false, false) => 1.0,
+		};
+		let ox = (cw - fw * scale) / 2.0;
+		let oy = (ch - fh * scale) / 2.0;
+
+		let mut style = options.style;
+		style.min_width *= scale;
+		style.max_width *= scale;
+
+		let mut canvas = EventCanvas::with_style(width, height, style)
+			.with_pressure_curve(curve.clone());
+		for event in self.events() {
+			let x = ((event.x() - frame.left) * sw * scale + ox) / cw;
+			let y = ((event.y() - frame.top) * sh * scale + oy) / ch;
+
+			canvas.process(Event::new(
+				event.time(),
+				x,
+				y,
+				event.pressure(),
+				event.touching(),
+				event.hovering()));
+		}
+
+		canvas
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, Instant};
+
+	/// A path with a single stroke along the given positions.
+	fn path(points: &[(f64, f64)]) -> EventPath {
+		let start = Instant::now();
+		points.iter()
+			.enumerate()
+			.map(|(i, (x, y))| Event::new(
+				start + Duration::from_millis(i as u64),
+				*x,
+				*y,
+				0.5,
+				true,
+				true))
+			.collect()
+	}
+
+	/// Options for rendering the whole screen at its own size.
+	fn screen_options(width: u32, height: u32) -> RenderOptions {
+		RenderOptions {
+			width: Length::Pixels(f64::from(width)),
+			height: Length::Pixels(f64::from(height)),
+			padding: Length::Pixels(0.0),
+			crop: false,
+			style: CanvasStyle::monochrome(),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn rendering_at_screen_size_matches_the_canvas() {
+		let path = path(&[(0.0, 0.0), (0.3, 0.7), (0.8, 0.2), (1.0, 1.0)]);
+		let (width, height) = (64, 40);
+
+		let mut canvas = EventCanvas::new(width, height);
+		for event in path.events() {
+			canvas.process(*event);
+		}
+		let rendered = path.render((width, height), &screen_options(width, height), &PressureCurve::linear());
+
+		assert_eq!(rendered, canvas);
+	}
+
+	#[test]
+	fn corners_land_on_corner_pixels() {
+		let path = path(&[(0.0, 0.0), (1.0, 1.0)]);
+		let rendered = path.render((20, 10), &screen_options(40, 20), &PressureCurve::linear());
+
+		assert_eq!(rendered.get(0, 0), Some(true));
+		assert_eq!(rendered.get(39, 19), Some(true));
+	}
+
+	#[test]
+	fn cropped_ink_is_centered() {
+		let path = path(&[(0.4, 0.5), (0.6, 0.5)]);
+		let options = RenderOptions {
+			width: Length::Pixels(41.0),
+			height: Length::Pixels(21.0),
+			padding: Length::Pixels(10.0),
+			crop: true,
+			style: CanvasStyle::monochrome(),
+			..Default::default()
+		};
+		let rendered = path.render((100, 100), &options, &PressureCurve::linear());
+
+		/* The stroke spans the picture between the paddings, through its
+		 * middle row. */
+		let inked = (0..41).filter(|x| rendered.get(*x, 10) == Some(true)).collect::<Vec<_>>();
+		assert_eq!(inked, (10..=30).collect::<Vec<_>>());
+		assert!((0..41).all(|x| rendered.get(x, 9) == Some(false)));
+	}
+}
@@ -0,0 +1,326 @@
+use serde::{Serialize, Deserialize};
+use std::time::Duration;
+use crate::path::EventPath;
+use crate::screen::Region;
+
+/// The rules signatures have to follow to be accepted, each of which may be
+/// turned off by leaving it empty.
+///
+/// Sizes are fractions of the dimensions of the screen of the device, and times
+/// are in milliseconds. Only the strokes that left ink are taken into account.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationRules {
+	/// The smallest number of events the ink may have.
+	pub min_points: Option<usize>,
+	/// The shortest time between the first and the last event of the ink.
+	pub min_duration: Option<f64>,
+	/// The smallest width the bounding box of the ink may have.
+	pub min_width: Option<f64>,
+	/// The smallest height the bounding box of the ink may have.
+	pub min_height: Option<f64>,
+	/// The longest time the pen may spend away from the screen between two
+	/// strokes.
+	pub max_gap: Option<f64>,
+	/// The region of the screen the ink has to stay inside of. The signing
+	/// screen lifts the pen outside of its signing box before the events get
+	/// to the signature, so there is no point in checking against that box,
+	/// and no region is checked against unless one is given.
+	pub area: Option<Region>,
+}
+impl Default for ValidationRules {
+	fn default() -> Self {
+		Self {
+			min_points: Some(20),
+			min_duration: Some(300.0),
+			min_width: Some(0.1),
+			min_height: Some(0.02),
+			max_gap: Some(5000.0),
+			area: None,
+		}
+	}
+}
+impl ValidationRules {
+	/// Checks the given signature against these rules, returning the reasons
+	/// it was rejected for, if any.
+	pub fn validate(&self, path: &EventPath) -> Result<(), Vec<Rejection>> {
+		let mut points = 0;
+		let mut outside = 0;
+		let mut gap = Duration::default();
+		let mut bounds = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+		let mut first = None;
+		let mut last = None;
+
+		for stroke in path.strokes() {
+			let events = stroke.events();
+			let (start, end) = match (events.first(), events.last()) {
+				(Some(start), Some(end)) => (start.time(), end.time()),
+				_ => continue
+			};
+			if let Some(last) = last {
+				gap = gap.max(start.saturating_duration_since(last));
+			}
+			first = first.or(Some(start));
+			last = Some(end);
+
+			points += events.len();
+			for event in events {
+				bounds = (
+					bounds.0.min(event.x()),
+					bounds.1.min(event.y()),
+					bounds.2.max(event.x()),
+					bounds.3.max(event.y()));
+			}
+			if let Some(area) = self.area {
+				if events.iter().any(|event| !area.contains(event.x(), event.y())) {
+					outside += 1;
+				}
+			}
+		}
+		let duration = match (first, last) {
+			(Some(first), Some(last)) => millis(last.saturating_duration_since(first)),
+			_ => 0.0
+		};
+		let (width, height) = if points > 0 {
+			(bounds.2 - bounds.0, bounds.3 - bounds.1)
+		} else {
+			(0.0, 0.0)
+		};
+		let gap = millis(gap);
+
+		let mut rejections = Vec::new();
+		if let Some(min) = self.min_points {
+			if points < min {
+				rejections.push(Rejection::TooFewPoints { points, min });
+			}
+		}
+		if let Some(min) = self.min_duration {
+			if duration < min {
+				rejections.push(Rejection::TooShort { duration, min });
+			}
+		}
+		if let Some(min) = self.min_width {
+			if width < min {
+				rejections.push(Rejection::TooNarrow { width, min });
+			}
+		}
+		if let Some(min) = self.min_height {
+			if height < min {
+				rejections.push(Rejection::TooFlat { height, min });
+			}
+		}
+		if let Some(max) = self.max_gap {
+			if gap > max {
+				rejections.push(Rejection::IdleGap { gap, max });
+			}
+		}
+		if outside > 0 {
+			rejections.push(Rejection::OutsideArea { strokes: outside });
+		}
+
+		if rejections.is_empty() {
+			Ok(())
+		} else {
+			Err(rejections)
+		}
+	}
+}
+
+/// The given duration, in milliseconds.
+fn millis(duration: Duration) -> f64 {
+	duration.as_secs_f64() * 1000.0
+}
+
+/// Enumeration of the reasons for which signatures may be rejected, along with
+/// the values that broke the rules and the limits set by them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Rejection {
+	/// The ink has too few events.
+	TooFewPoints {
+		/// The number of events in the ink.
+		points: usize,
+		/// The smallest number of events the ink may have.
+		min: usize,
+	},
+	/// The signature was drawn too quickly.
+	TooShort {
+		/// The time between the first and the last event of the ink, in
+		/// milliseconds.
+		duration: f64,
+		/// The shortest time the ink may take, in milliseconds.
+		min: f64,
+	},
+	/// The ink is too narrow.
+	TooNarrow {
+		/// The width of the ink, as a fraction of the width of the screen.
+		width: f64,
+		/// The smallest width the ink may have, as a fraction of the width of
+		/// the screen.
+		min: f64,
+	},
+	/// The ink is too flat.
+	TooFlat {
+		/// The height of the ink, as a fraction of the height of the screen.
+		height: f64,
+		/// The smallest height the ink may have, as a fraction of the height
+		/// of the screen.
+		min: f64,
+	},
+	/// The pen was away from the screen for too long between strokes.
+	IdleGap {
+		/// The longest time the pen spent away from the screen between two
+		/// strokes, in milliseconds.
+		gap: f64,
+		/// The longest time the pen may spend away from the screen, in
+		/// milliseconds.
+		max: f64,
+	},
+	/// Strokes left the signing area.
+	OutsideArea {
+		/// The number of strokes with events outside of the area.
+		strokes: usize,
+	},
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+	use stu::Event;
+
+	/// A path made of the given strokes, each given as its events, in
+	/// milliseconds since the start of the path and normalized coordinates.
+	/// The pen is lifted, right where it is, between strokes.
+	fn path(strokes: &[&[(u64, f64, f64)]]) -> EventPath {
+		let start = Instant::now();
+		let event = |(time, x, y): (u64, f64, f64), touching: bool| Event::new(
+			start + Duration::from_millis(time),
+			x,
+			y,
+			if touching { 0.5 } else { 0.0 },
+			touching,
+			true);
+
+		strokes.iter()
+			.flat_map(|stroke| stroke.iter()
+				.map(move |point| event(*point, true))
+				.chain(stroke.last().map(|point| event(*point, false))))
+			.collect()
+	}
+
+	/// A stroke of the given number of events, evenly spread over the given
+	/// time and the given box, from its top left corner to its bottom right.
+	fn stroke(events: u64, start: u64, duration: u64, (x, y, width, height): (f64, f64, f64, f64)) -> Vec<(u64, f64, f64)> {
+		(0..events)
+			.map(|i| {
+				let t = i as f64 / (events - 1) as f64;
+				(start + (duration as f64 * t) as u64, x + width * t, y + height * t)
+			})
+			.collect()
+	}
+
+	/// Rules with all of the checks turned off.
+	fn off() -> ValidationRules {
+		ValidationRules {
+			min_points: None,
+			min_duration: None,
+			min_width: None,
+			min_height: None,
+			max_gap: None,
+			area: None,
+		}
+	}
+
+	#[test]
+	fn defaults_accept_ordinary_signatures() {
+		let a = stroke(30, 0, 600, (0.2, 0.4, 0.3, 0.1));
+		let b = stroke(30, 1000, 600, (0.5, 0.4, 0.2, 0.05));
+		assert_eq!(ValidationRules::default().validate(&path(&[&a, &b])), Ok(()));
+	}
+
+	#[test]
+	fn empty_rules_accept_anything() {
+		assert_eq!(off().validate(&EventPath::new()), Ok(()));
+		assert_eq!(off().validate(&path(&[&[(0, 0.5, 0.5)]])), Ok(()));
+	}
+
+	#[test]
+	fn too_few_points() {
+		let rules = ValidationRules { min_points: Some(20), ..off() };
+		let ink = stroke(20, 0, 100, (0.1, 0.1, 0.5, 0.5));
+
+		assert_eq!(rules.validate(&path(&[&ink])), Ok(()));
+		assert_eq!(
+			rules.validate(&path(&[&ink[1..]])),
+			Err(vec![Rejection::TooFewPoints { points: 19, min: 20 }]));
+	}
+
+	#[test]
+	fn too_short() {
+		let rules = ValidationRules { min_duration: Some(300.0), ..off() };
+
+		assert_eq!(rules.validate(&path(&[&stroke(10, 0, 300, (0.1, 0.1, 0.5, 0.5))])), Ok(()));
+		assert_eq!(
+			rules.validate(&path(&[&stroke(10, 0, 299, (0.1, 0.1, 0.5, 0.5))])),
+			Err(vec![Rejection::TooShort { duration: 299.0, min: 300.0 }]));
+	}
+
+	#[test]
+	fn too_narrow() {
+		let rules = ValidationRules { min_width: Some(0.25), ..off() };
+
+		assert_eq!(rules.validate(&path(&[&stroke(10, 0, 100, (0.25, 0.1, 0.25, 0.5))])), Ok(()));
+		assert_eq!(
+			rules.validate(&path(&[&stroke(10, 0, 100, (0.25, 0.1, 0.125, 0.5))])),
+			Err(vec![Rejection::TooNarrow { width: 0.125, min: 0.25 }]));
+	}
+
+	#[test]
+	fn too_flat() {
+		let rules = ValidationRules { min_height: Some(0.25), ..off() };
+
+		assert_eq!(rules.validate(&path(&[&stroke(10, 0, 100, (0.1, 0.25, 0.5, 0.25))])), Ok(()));
+		assert_eq!(
+			rules.validate(&path(&[&stroke(10, 0, 100, (0.1, 0.25, 0.5, 0.125))])),
+			Err(vec![Rejection::TooFlat { height: 0.125, min: 0.25 }]));
+	}
+
+	#[test]
+	fn idle_gap() {
+		let rules = ValidationRules { max_gap: Some(5000.0), ..off() };
+		let a = stroke(10, 0, 100, (0.1, 0.1, 0.2, 0.2));
+
+		let b = stroke(10, 5100, 100, (0.5, 0.1, 0.2, 0.2));
+		assert_eq!(rules.validate(&path(&[&a, &b])), Ok(()));
+
+		let b = stroke(10, 5101, 100, (0.5, 0.1, 0.2, 0.2));
+		assert_eq!(
+			rules.validate(&path(&[&a, &b])),
+			Err(vec![Rejection::IdleGap { gap: 5001.0, max: 5000.0 }]));
+	}
+
+	#[test]
+	fn outside_area() {
+		let area = Region { x: 0.25, y: 0.25, width: 0.5, height: 0.5 };
+		let rules = ValidationRules { area: Some(area), ..off() };
+		let inside = stroke(10, 0, 100, (0.25, 0.25, 0.25, 0.25));
+		let outside = stroke(10, 200, 100, (0.5, 0.5, 0.25, 0.25));
+
+		assert_eq!(rules.validate(&path(&[&inside])), Ok(()));
+		assert_eq!(
+			rules.validate(&path(&[&inside, &outside, &outside])),
+			Err(vec![Rejection::OutsideArea { strokes: 2 }]));
+	}
+
+	#[test]
+	fn every_broken_rule_is_reported() {
+		assert_eq!(
+			ValidationRules::default().validate(&path(&[&[(0, 0.5, 0.5)]])),
+			Err(vec![
+				Rejection::TooFewPoints { points: 1, min: 20 },
+				Rejection::TooShort { duration: 0.0, min: 300.0 },
+				Rejection::TooNarrow { width: 0.0, min: 0.1 },
+				Rejection::TooFlat { height: 0.0, min: 0.02 },
+			]));
+	}
+}
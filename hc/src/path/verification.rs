@@ -0,0 +1,395 @@
+use serde::{Serialize, Deserialize};
+use std::time::Duration;
+use crate::path::EventPath;
+
+/// The parameters of the comparison of signatures against enrolled references.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationOptions {
+	/// The number of samples signatures are resampled to before being compared,
+	/// evenly spaced over the time the pen spent on the screen.
+	pub samples: u32,
+	/// The largest distance between matching samples of two signatures, as a
+	/// fraction of their length, bounding how much their timing may differ.
+	pub window: f64,
+	/// The weight of the position of the pen in the distance between samples.
+	pub position: f64,
+	/// The weight of the pressure of the pen in the distance between samples.
+	pub pressure: f64,
+	/// The weight of the velocity of the pen in the distance between samples.
+	pub velocity: f64,
+	/// The distance between genuine signatures of the same person, used when
+	/// only a single reference is enrolled and the distance can't be learned
+	/// from the references themselves.
+	pub spread: f64,
+	/// How many times further than genuine signatures usually are from each
+	/// other a signature has to be for its similarity to drop to one half.
+	pub tolerance: f64,
+	/// The lowest similarity signatures may have to be accepted as matching
+	/// the references.
+	pub threshold: f64,
+}
+impl Default for VerificationOptions {
+	fn default() -> Self {
+		Self {
+			samples: 128,
+			window: 0.15,
+			position: 1.0,
+			pressure: 0.5,
+			velocity: 1.0,
+			spread: 0.125,
+			tolerance: 1.6,
+			threshold: 0.5,
+		}
+	}
+}
+
+/// The outcome of the verification of a signature.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Verification {
+	/// The mean dynamic time warping distance between the signature and the
+	/// references.
+	pub distance: f64,
+	/// How similar the signature is to the references, from zero to one.
+	pub similarity: f64,
+	/// Whether the signature is similar enough to be accepted.
+	pub accepted: bool,
+}
+
+/// A single sample of a signature, normalized for comparison.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+struct Sample {
+	/// The horizontal position of the pen, relative to the centroid of the
+	/// signature and scaled by its spread.
+	x: f64,
+	/// The vertical position of the pen, relative to the centroid of the
+	/// signature and scaled by its spread.
+	y: f64,
+	/// The pressure of the pen, relative to its mean pressure.
+	pressure: f64,
+	/// The horizontal velocity of the pen, relative to its mean speed.
+	vx: f64,
+	/// The vertical velocity of the pen, relative to its mean speed.
+	vy: f64,
+}
+
+/// The references of a signature enrolled for verification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enrollment {
+	/// The normalized references.
+	references: Vec<Vec<Sample>>,
+	/// The mean distance between pairs of references, if there is more than
+	/// one of them.
+	spread: Option<f64>,
+	/// The parameters of the comparison.
+	options: VerificationOptions,
+}
+impl Enrollment {
+	/// Enrolls the given references of a signature.
+	///
+	/// When more than one reference is given, the distance between genuine
+	/// signatures is learned from the distances between the references, which
+	/// calibrates the similarity of the signatures verified against them.
+	pub fn new<'a, I>(references: I, options: VerificationOptions) -> Result<Self, VerificationError>
+		where I: IntoIterator<Item = &'a EventPath> {
+
+		let references = references.into_iter()
+			.map(|path| normalize(path, &options))
+			.collect::<Result<Vec<_>, _>>()?;
+		if references.is_empty() {
+			return Err(VerificationError::NoReferences)
+		}
+
+		let mut distances = Vec::new();
+		for (i, a) in references.iter().enumerate() {
+			for b in &references[i + 1..] {
+				distances.push(dtw(a, b, &options));
+			}
+		}
+		let spread = if distances.is_empty() {
+			None
+		} else {
+			Some(distances.iter().sum::<f64>() / distances.len() as f64)
+		};
+
+		Ok(Self { references, spread, options })
+	}
+
+	/// Verifies a signature against the references.
+	///
+	/// The similarity is one half when the signature is as far from the
+	/// references as the tolerance times the distance between genuine
+	/// signatures, and falls off quadratically with the distance.
+	pub fn verify(&self, path: &EventPath) -> Result<Verification, VerificationError> {
+		let signature = normalize(path, &self.options)?;
+		let distance = self.references.iter()
+			.map(|reference| dtw(&signature, reference, &self.options))
+			.sum::<f64>() / self.references.len() as f64;
+
+		let spread = self.spread.unwrap_or(self.options.spread).max(f64::EPSILON);
+		let ratio = distance / (spread * self.options.tolerance);
+		let similarity = 1.0 / (1.0 + ratio * ratio);
+
+		Ok(Verification {
+			distance,
+			similarity,
+			accepted: similarity >= self.options.threshold,
+		})
+	}
+}
+
+/// Resamples the ink of a signature evenly over the time the pen spent on the
+/// screen, and normalizes it for position, scale, pressure and speed.
+fn normalize(path: &EventPath, options: &VerificationOptions) -> Result<Vec<Sample>, VerificationError> {
+	/* Lay the strokes end to end in time, leaving out the time the pen spent
+	 * away from the screen. */
+	let mut points = Vec::new();
+	let mut offset = Duration::default();
+	for stroke in path.strokes() {
+		let events = stroke.events();
+		let start = match events.first() {
+			Some(event) => event.time(),
+			None => continue
+		};
+		for event in events {
+			let time = offset + event.time().saturating_duration_since(start);
+			points.push((time.as_secs_f64(), event.x(), event.y(), event.pressure()));
+		}
+		if let Some(last) = events.last() {
+			offset += last.time().saturating_duration_since(start);
+		}
+	}
+
+	let duration = offset.as_secs_f64();
+	let count = options.samples.max(3) as usize;
+	if points.len() < 2 || duration <= 0.0 {
+		return Err(VerificationError::TooShort)
+	}
+
+	let mut samples = Vec::with_capacity(count);
+	let mut index = 0;
+	for i in 0..count {
+		let time = duration * i as f64 / (count - 1) as f64;
+		while index + 2 < points.len() && points[index + 1].0 <= time {
+			index += 1;
+		}
+
+		let (a, b) = (points[index], points[index + 1]);
+		let t = if b.0 > a.0 { ((time - a.0) / (b.0 - a.0)).clamp(0.0, 1.0) } else { 1.0 };
+		samples.push(Sample {
+			x: a.1 + (b.1 - a.1) * t,
+			y: a.2 + (b.2 - a.2) * t,
+			pressure: a.3 + (b.3 - a.3) * t,
+			..Default::default()
+		});
+	}
+
+	let n = count as f64;
+	let (cx, cy) = samples.iter().fold((0.0, 0.0), |(x, y), s| (x + s.x / n, y + s.y / n));
+	let scale = (samples.iter()
+		.map(|s| (s.x - cx).powi(2) + (s.y - cy).powi(2))
+		.sum::<f64>() / n)
+		.sqrt();
+	if scale <= f64::EPSILON {
+		return Err(VerificationError::TooShort)
+	}
+	let pressure = samples.iter().map(|s| s.pressure).sum::<f64>() / n;
+	for sample in &mut samples {
+		sample.x = (sample.x - cx) / scale;
+		sample.y = (sample.y - cy) / scale;
+		sample.pressure = if pressure > 0.0 { sample.pressure / pressure } else { 0.0 };
+	}
+
+	/* Take the velocity from the neighbouring samples, and scale it by the mean
+	 * speed, so that only the rhythm of the signature is compared. */
+	let velocities = (0..count)
+		.map(|i| {
+			let (a, b) = (samples[i.saturating_sub(1)], samples[(i + 1).min(count - 1)]);
+			(b.x - a.x, b.y - a.y)
+		})
+		.collect::<Vec<_>>();
+	let speed = velocities.iter().map(|(x, y)| x.hypot(*y)).sum::<f64>() / n;
+	for (sample, (vx, vy)) in samples.iter_mut().zip(velocities) {
+		if speed > 0.0 {
+			sample.vx = vx / speed;
+			sample.vy = vy / speed;
+		}
+	}
+
+	Ok(samples)
+}
+
+/// The dynamic time warping distance between two normalized signatures,
+/// constrained to the window in the given options, and divided by the length
+/// of the signatures.
+fn dtw(a: &[Sample], b: &[Sample], options: &VerificationOptions) -> f64 {
+	let cost = |a: &Sample, b: &Sample| (
+		options.position * ((a.x - b.x).powi(2) + (a.y - b.y).powi(2))
+		+ options.pressure * (a.pressure - b.pressure).powi(2)
+		+ options.velocity * ((a.vx - b.vx).powi(2) + (a.vy - b.vy).powi(2))
+	).sqrt();
+
+	let window = ((options.window * a.len().max(b.len()) as f64).ceil() as usize)
+		.max(a.len().abs_diff(b.len()));
+
+	let mut previous = vec![f64::INFINITY; b.len() + 1];
+	let mut current = vec![f64::INFINITY; b.len() + 1];
+	previous[0] = 0.0;
+	for i in 1..=a.len() {
+		current.iter_mut().for_each(|value| *value = f64::INFINITY);
+		let start = i.saturating_sub(window).max(1);
+		let end = (i + window).min(b.len());
+		for j in start..=end {
+			let best = previous[j].min(current[j - 1]).min(previous[j - 1]);
+			current[j] = cost(&a[i - 1], &b[j - 1]) + best;
+		}
+		std::mem::swap(&mut previous, &mut current);
+	}
+
+	previous[b.len()] / a.len().max(b.len()) as f64
+}
+
+/// Enumeration of the reasons for which signatures may fail to be compared.
+#[derive(Debug, thiserror::Error)]
+pub enum VerificationError {
+	/// No references were given to compare signatures against.
+	#[error("no reference signatures have been enrolled")]
+	NoReferences,
+	/// The signature has too little ink to be compared.
+	#[error("the signature is too short to be compared")]
+	TooShort,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+	use stu::Event;
+
+	/// A shape drawn by the pen, as its position at each point of the way
+	/// through it, from zero to one.
+	type Shape = fn(f64) -> (f64, f64);
+
+	/// A loopy, cursive-looking scrawl, standing in for a signature.
+	fn scrawl(s: f64) -> (f64, f64) {
+		let t = s * std::f64::consts::TAU;
+		(
+			0.2 + 0.6 * s + 0.06 * (3.0 * t).sin() + 0.03 * (7.0 * t + 1.0).sin(),
+			0.5 + 0.12 * (5.0 * t).cos() + 0.05 * (4.0 * t + 2.0).sin(),
+		)
+	}
+
+	/// A circle, nothing like the scrawl.
+	fn circle(s: f64) -> (f64, f64) {
+		let t = s * std::f64::consts::TAU;
+		(0.5 + 0.3 * t.cos(), 0.5 + 0.3 * t.sin())
+	}
+
+	/// A zigzag spanning the same width as the scrawl.
+	fn zigzag(s: f64) -> (f64, f64) {
+		let phase = (s * 6.0).fract();
+		(0.2 + 0.6 * s, 0.35 + 0.3 * (2.0 * phase - 1.0).abs())
+	}
+
+	/// Deterministic noise in the range from minus one to one.
+	fn noise(seed: u32, i: u32) -> f64 {
+		let x = (f64::from(seed) * 12.9898 + f64::from(i) * 78.233).sin() * 43758.5453;
+		2.0 * x.fract().abs() - 1.0
+	}
+
+	/// Draws the given shape in two strokes, with the shape bent by up to
+	/// `jitter` and every event off by a tenth of that, with the speed of the
+	/// pen changing by up to `wobble` along the way, and with the whole of it
+	/// taking `duration` seconds.
+	fn draw(shape: Shape, seed: u32, jitter: f64, wobble: f64, duration: f64) -> EventPath {
+		const EVENTS: u32 = 400;
+
+		let start = Instant::now();
+		let mut events = Vec::new();
+		let mut time = 0.0;
+		for i in 0..=EVENTS {
+			let s = f64::from(i) / f64::from(EVENTS);
+			let (x, y) = shape(s);
+			let bend = (
+				jitter * (noise(seed, 0) * 3.0 * s + noise(seed, 1)).sin(),
+				jitter * (noise(seed, 2) * 3.0 * s + noise(seed, 3)).cos(),
+			);
+			let touching = !(0.49..0.51).contains(&s);
+			let pressure = if touching { 0.5 + 0.2 * (s * 9.0).sin() } else { 0.0 };
+			events.push(Event::new(
+				start + Duration::from_secs_f64(time),
+				x + bend.0 + jitter / 10.0 * noise(seed, 2 * i + 4),
+				y + bend.1 + jitter / 10.0 * noise(seed, 2 * i + 5),
+				pressure,
+				touching,
+				true));
+
+			let speed = 1.0 + wobble * (s * 5.0 + f64::from(seed)).sin();
+			time += duration / f64::from(EVENTS) / speed;
+		}
+		events.into_iter().collect()
+	}
+
+	fn references(count: u32) -> Enrollment {
+		let references = (0..count)
+			.map(|seed| draw(scrawl, seed, 0.006, 0.05, 2.0))
+			.collect::<Vec<_>>();
+		Enrollment::new(references.iter(), VerificationOptions::default()).unwrap()
+	}
+
+	#[test]
+	fn jittered_copies_are_accepted() {
+		for enrollment in [references(1), references(4)] {
+			for seed in 10..16 {
+				let verification = enrollment.verify(&draw(scrawl, seed, 0.006, 0.05, 2.0)).unwrap();
+				assert!(verification.accepted, "{:?}", verification);
+			}
+		}
+	}
+
+	#[test]
+	fn time_scaled_copies_are_accepted() {
+		/* Signatures are compared over the time the pen spent on the screen,
+		 * so writing the same one faster or slower changes nothing. */
+		for enrollment in [references(1), references(4)] {
+			let expected = enrollment.verify(&draw(scrawl, 20, 0.006, 0.05, 2.0)).unwrap();
+			assert!(expected.accepted, "{:?}", expected);
+			for duration in [0.5, 1.0, 3.0, 8.0] {
+				let verification = enrollment.verify(&draw(scrawl, 20, 0.006, 0.05, duration)).unwrap();
+				assert!(verification.accepted, "{:?}", verification);
+				assert!((verification.distance - expected.distance).abs() < 1e-6);
+			}
+		}
+	}
+
+	#[test]
+	fn different_shapes_are_rejected() {
+		for enrollment in [references(1), references(4)] {
+			for shape in [circle as Shape, zigzag] {
+				let verification = enrollment.verify(&draw(shape, 30, 0.006, 0.05, 2.0)).unwrap();
+				assert!(!verification.accepted, "{:?}", verification);
+			}
+		}
+	}
+
+	#[test]
+	fn identical_signatures_are_closest() {
+		let reference = draw(scrawl, 0, 0.006, 0.05, 2.0);
+		let enrollment = Enrollment::new([&reference], VerificationOptions::default()).unwrap();
+
+		let verification = enrollment.verify(&reference).unwrap();
+		assert!(verification.distance.abs() < 1e-9);
+		assert!((verification.similarity - 1.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn empty_inputs_are_errors() {
+		assert!(matches!(
+			Enrollment::new(std::iter::empty(), VerificationOptions::default()),
+			Err(VerificationError::NoReferences)));
+		assert!(matches!(
+			Enrollment::new([&EventPath::new()], VerificationOptions::default()),
+			Err(VerificationError::TooShort)));
+		assert!(matches!(references(1).verify(&EventPath::new()), Err(VerificationError::TooShort)));
+	}
+}
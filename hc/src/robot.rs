@@ -1,4 +1,5 @@
-use crate::path::{EventPath, Point};
+use crate::path::{EventPath, Point, Interpolation, Parameterization, Gaps};
+use stu::PressureCurve;
 use std::time::{Duration, Instant};
 use std::num::NonZeroU32;
 use std::sync::atomic::AtomicBool;
@@ -12,6 +13,15 @@ static MOUSE_LOCK: AtomicBool = AtomicBool::new(false);
 pub struct Playback {
 	/// The path this structure is going to be playing back.
 	pub path: EventPath,
+	/// The method used to interpolate the strokes in the path.
+	pub interpolation: Interpolation,
+	/// How the time of the playback maps to the events in the path.
+	pub parameterization: Parameterization,
+	/// How the movement of the pen between strokes is played back.
+	pub gaps: Gaps,
+	/// The curve the pressure of the pen is shaped by, as it is when the ink
+	/// is drawn.
+	pub curve: PressureCurve,
 	/// The rectangular region that maps the output to the physical screen.
 	pub target: ScreenArea,
 	/// The amount of time that the path should take to get written down.
@@ -56,7 +66,10 @@ impl Playback {
 		std::thread::spawn(move || {
 			let mut x = 0.0;
 			let mut pressed = false;
-			let trace = self.path.trace();
+			let size = (f64::from(self.target.width), f64::from(self.target.height));
+			let trace = self.path
+				.trace(self.interpolation)
+				.with_parameterization(self.parameterization, self.gaps, size);
 
 			let dt = self.delta.div_f64(f64::from(self.steps.get()));
 			let dx = 1.0 / f64::from(self.steps.get());
@@ -71,6 +84,10 @@ impl Playback {
 				};
 				let (px, py) = self.map(point);
 
+				/* A pen resting on the screen with no pressure behind it, as
+				 * shaped by the pressure curve, leaves no ink. */
+				let touch = point.touch && self.curve.apply(point.pressure) > 0.0;
+
 				/* Build the input structure and send it. */
 				unsafe {
 					let mut input: winapi::um::winuser::INPUT =
@@ -88,10 +105,10 @@ impl Playback {
 					input.u.mi_mut().dwFlags =
 						  winapi::um::winuser::MOUSEEVENTF_ABSOLUTE
 						| winapi::um::winuser::MOUSEEVENTF_MOVE
-						| if !pressed && point.touch {
+						| if !pressed && touch {
 							  pressed = true;
 							  winapi::um::winuser::MOUSEEVENTF_LEFTDOWN
-						  } else if pressed && !point.touch {
+						  } else if pressed && !touch {
 							  pressed = false;
 							  winapi::um::winuser::MOUSEEVENTF_LEFTUP
 						  } else { 0 };
@@ -0,0 +1,585 @@
+use std::path::Path;
+use md5::{Md5, Digest};
+use serde::{Serialize, Deserialize};
+use crate::path::EventCanvas;
+use stu::{Tablet, Event, DeviceImage, EncodingMode, ScreenKind, ScreenSlot, Transform};
+
+/// Pushes the image at the given path to the idle screen of the given device.
+///
+/// The image is scaled to the dimensions of the screen of the device, as seen
+/// through the given transform, written to its first idle screen slot and then
+/// selected, so that it is kept across power cycles. The image is only written
+/// when the slot does not already hold it, as the onboard storage of the device
+/// has a limited number of write cycles.
+pub fn push_idle_screen(
+	device: &Tablet,
+	path: &Path,
+	transform: Transform) -> Result<(), ScreenError> {
+
+	let caps = device.capability()
+		.map_err(ScreenError::DeviceCommandFailed)?;
+	let mode = device.encoding()
+		.map_err(ScreenError::DeviceCommandFailed)?;
+
+	let (width, height) = transform.dimensions(caps.width(), caps.height());
+	let image = image::open(path)
+		.map_err(ScreenError::ImageLoadFailed)?;
+	let image = image.resize_exact(
+		width,
+		height,
+		image::imageops::FilterType::Triangle);
+	let image = image.to_rgb8();
+
+	let image = transform.image_from_fn(
+		mode,
+		caps.width(),
+		caps.height(),
+		|x, y| image.get_pixel(x, y).0);
+
+	/* Devices that can't tell us what they hold get the image written every
+	 * time, as there is no way to know whether it is there already. */
+	let slot = ScreenSlot::new(ScreenKind::Idle, 0).unwrap();
+	let hash: [u8; 16] = Md5::digest(image.data()).into();
+	let stored = device.screens()
+		.ok()
+		.and_then(|screens| screens.into_iter().find(|screen| screen.slot() == slot))
+		.and_then(|screen| screen.hash());
+	if stored != Some(hash) {
+		device.write_screen(slot, &image)
+			.map_err(ScreenError::DeviceCommandFailed)?;
+	}
+	device.select_screen(slot)
+		.map_err(ScreenError::DeviceCommandFailed)
+}
+
+/// Displays the ink in the given canvas on the screen of the given device, as
+/// seen through the given transform.
+pub fn display_ink(
+	device: &Tablet,
+	transform: Transform,
+	canvas: &EventCanvas) -> Result<(), stu::Error> {
+
+	let caps = device.capability()?;
+	let mode = device.encoding()?;
+
+	let image = transform.image_from_fn(mode, caps.width(), caps.height(), |x, y| {
+		if canvas.get(x, y).unwrap_or(false) {
+			[0, 0, 0]
+		} else {
+			[255, 255, 255]
+		}
+	});
+	device.write_image(&image)
+}
+
+/// A rectangular region of the screen of the device, in the normalized
+/// coordinate system used by [`stu::Event`].
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+	/// The position of the top left corner along the horizontal axis.
+	pub x: f64,
+	/// The position of the top left corner along the vertical axis.
+	pub y: f64,
+	/// The width of the rectangular region.
+	pub width: f64,
+	/// The height of the rectangular region.
+	pub height: f64,
+}
+impl Region {
+	/// Whether the given point lies inside of this region.
+	pub fn contains(&self, x: f64, y: f64) -> bool {
+		x >= self.x && x < self.x + self.width
+			&& y >= self.y && y < self.y + self.height
+	}
+
+	/// Converts this region to a rectangle on a screen with the given
+	/// dimensions, given as its left, top, right and bottom sides, in pixels.
+	pub fn to_pixels(self, width: u32, height: u32) -> (i32, i32, i32, i32) {
+		let w = f64::from(width);
+		let h = f64::from(height);
+
+		(
+			(self.x * w).round() as i32,
+			(self.y * h).round() as i32,
+			((self.x + self.width) * w).round() as i32,
+			((self.y + self.height) * h).round() as i32,
+		)
+	}
+}
+
+/// The buttons displayed on the screen of the device by the signing layout.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum PadButton {
+	/// Accepts the signature, painting it.
+	Ok,
+	/// Clears the signature, so that it can be signed again.
+	Clear,
+	/// Gives up on the signature, discarding it.
+	Cancel,
+}
+
+/// The layout of the signing screen displayed on the device, made up of a box
+/// the signature is written in and a row of buttons below it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SigningLayout {
+	/// The region the signature is written in.
+	signing_box: Region,
+	/// The regions of the buttons, in the order they are displayed in.
+	buttons: [(PadButton, Region); 3],
+}
+impl SigningLayout {
+	/// The margin between the elements of the layout and the edges of the
+	/// screen, in normalized coordinates.
+	const MARGIN: f64 = 0.03;
+
+	/// The height of the row of buttons, in normalized coordinates.
+	const BUTTON_HEIGHT: f64 = 0.18;
+
+	/// Creates the default signing layout.
+	pub fn new() -> Self {
+		let m = Self::MARGIN;
+		let h = Self::BUTTON_HEIGHT;
+
+		let signing_box = Region {
+			x: m,
+			y: m,
+			width: 1.0 - 2.0 * m,
+			height: 1.0 - 3.0 * m - h
+		};
+
+		let width = (1.0 - 4.0 * m) / 3.0;
+		let button = |i: f64| Region {
+			x: m + i * (width + m),
+			y: 1.0 - m - h,
+			width,
+			height: h
+		};
+
+		Self {
+			signing_box,
+			buttons: [
+				(PadButton::Ok, button(0.0)),
+				(PadButton::Clear, button(1.0)),
+				(PadButton::Cancel, button(2.0)),
+			]
+		}
+	}
+
+	/// The region the signature is written in.
+	pub fn signing_box(&self) -> Region {
+		self.signing_box
+	}
+
+	/// The buttons in this layout, along with the regions they occupy.
+	pub fn buttons(&self) -> impl Iterator<Item = (PadButton, Region)> + '_ {
+		self.buttons.iter().copied()
+	}
+
+	/// The button at the given point, if any.
+	pub fn button_at(&self, x: f64, y: f64) -> Option<PadButton> {
+		self.buttons()
+			.find(|(_, region)| region.contains(x, y))
+			.map(|(button, _)| button)
+	}
+
+	/// Restricts the ink of the given event to the signing box, by having the
+	/// pen be considered lifted whenever it is outside of it.
+	pub fn clip(&self, event: Event) -> Event {
+		if !event.touching() || self.signing_box.contains(event.x(), event.y()) {
+			return event
+		}
+
+		Event::new(
+			event.time(),
+			event.x(),
+			event.y(),
+			event.pressure(),
+			false,
+			event.hovering())
+	}
+}
+impl Default for SigningLayout {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// The signing screen displayed on the device, along with its layout.
+#[derive(Debug, Clone)]
+pub struct PadScreen {
+	/// The layout of the screen.
+	layout: SigningLayout,
+	/// The pixels of the background of the screen, in the orientation of the
+	/// view, as rows of 24-bit RGB values.
+	background: Box<[u8]>,
+	/// The orientation the screen is displayed in.
+	transform: Transform,
+	/// The encoding of the images sent to the device.
+	mode: EncodingMode,
+	/// The width of the screen of the device, in pixels.
+	width: u32,
+	/// The height of the screen of the device, in pixels.
+	height: u32,
+}
+impl PadScreen {
+	/// Renders the signing screen for the given device, as seen through the
+	/// given transform.
+	pub fn new(
+		device: &Tablet,
+		layout: SigningLayout,
+		transform: Transform) -> Result<Self, ScreenError> {
+
+		let caps = device.capability()
+			.map_err(ScreenError::DeviceCommandFailed)?;
+		let mode = device.encoding()
+			.map_err(ScreenError::DeviceCommandFailed)?;
+
+		let (width, height) = transform.dimensions(caps.width(), caps.height());
+		let background = unsafe {
+			render_layout(&layout, width, height)?
+		};
+
+		Ok(Self {
+			layout,
+			background,
+			transform,
+			mode,
+			width: caps.width(),
+			height: caps.height()
+		})
+	}
+
+	/// The layout of the screen.
+	pub fn layout(&self) -> &SigningLayout {
+		&self.layout
+	}
+
+	/// Encodes this screen for the device, with the ink in the given canvas, if
+	/// any, drawn over it, and the given button, if any, highlighted by having
+	/// its colors inverted. The canvas is expected to be in the orientation of
+	/// the view.
+	fn image(&self, ink: Option<&EventCanvas>, highlight: Option<PadButton>) -> DeviceImage {
+		let (width, height) = self.transform.dimensions(self.width, self.height);
+		let highlight = highlight.and_then(|highlight| self.layout.buttons()
+			.find(|(button, _)| *button == highlight)
+			.map(|(_, region)| region.to_pixels(width, height)));
+
+		self.transform.image_from_fn(self.mode, self.width, self.height, |x, y| {
+			let inverted = highlight
+				.map(|(left, top, right, bottom)| {
+					let (x, y) = (x as i32, y as i32);
+					x >= left && x < right && y >= top && y < bottom
+				})
+				.unwrap_or(false);
+
+			let pixel = if ink.and_then(|ink| ink.get(x, y)).unwrap_or(false) {
+				[0, 0, 0]
+			} else {
+				let i = (y as usize * width as usize + x as usize) * 3;
+				[self.background[i], self.background[i + 1], self.background[i + 2]]
+			};
+
+			if inverted {
+				pixel.map(|channel| !channel)
+			} else {
+				pixel
+			}
+		})
+	}
+
+	/// Displays this screen on the given device, with the ink in the given
+	/// canvas, if any, drawn over it, and the given button, if any, highlighted,
+	/// restricting the inking of the device to the signing box whenever the
+	/// device supports it.
+	pub fn display(
+		&self,
+		device: &Tablet,
+		ink: Option<&EventCanvas>,
+		highlight: Option<PadButton>) -> Result<(), stu::Error> {
+
+		device.write_image(&self.image(ink, highlight))?;
+
+		/* The layout is given in the orientation of the view, while the device
+		 * expects its inking area in its native orientation. */
+		let region = self.layout.signing_box();
+		let (x0, y0) = self.transform.invert(region.x, region.y);
+		let (x1, y1) = self.transform.invert(
+			region.x + region.width,
+			region.y + region.height);
+		let region = Region {
+			x: x0.min(x1),
+			y: y0.min(y1),
+			width: (x1 - x0).abs(),
+			height: (y1 - y0).abs()
+		};
+
+		let (left, top, right, bottom) = region.to_pixels(self.width, self.height);
+		let result = device.inking_area(
+			left.max(0) as u32,
+			top.max(0) as u32,
+			(right - left).max(0) as u32,
+			(bottom - top).max(0) as u32);
+
+		match result {
+			/* Devices that can't restrict the inking area will just ink over
+			 * the buttons, which is ugly, but harmless. */
+			Err(stu::Error::ClientError(stu::ClientError::UnsupportedReportId { .. })) =>
+				Ok(()),
+			other => other
+		}
+	}
+}
+
+/// Renders the background of the given layout using GDI, returning its pixels
+/// as rows of 24-bit RGB values.
+unsafe fn render_layout(
+	layout: &SigningLayout,
+	width: u32,
+	height: u32) -> Result<Box<[u8]>, ScreenError> {
+
+	use winapi::um::winuser as user;
+	use winapi::um::wingdi as gdi;
+	use winapi::um::errhandlingapi::GetLastError;
+
+	let dc = gdi::CreateCompatibleDC(std::ptr::null_mut());
+	if dc.is_null() {
+		return Err(ScreenError::Rendering(format!(
+			"CreateCompatibleDC({:p}) failed: 0x{:08x}",
+			std::ptr::null_mut::<()>(),
+			GetLastError())))
+	}
+
+	let mut info = std::mem::zeroed::<gdi::BITMAPINFO>();
+	info.bmiHeader.biSize = std::mem::size_of::<gdi::BITMAPINFOHEADER>() as _;
+	info.bmiHeader.biPlanes = 1;
+	info.bmiHeader.biBitCount = 32;
+	info.bmiHeader.biCompression = gdi::BI_RGB;
+	info.bmiHeader.biWidth = width as i32;
+	info.bmiHeader.biHeight = -(height as i32);
+
+	let mut buffer = std::ptr::null_mut();
+	let bitmap = gdi::CreateDIBSection(
+		dc,
+		&info,
+		gdi::DIB_RGB_COLORS,
+		&mut buffer,
+		std::ptr::null_mut(),
+		0);
+	if bitmap.is_null() || buffer.is_null() {
+		let error = GetLastError();
+		let _ = gdi::DeleteDC(dc);
+
+		return Err(ScreenError::Rendering(format!(
+			"CreateDIBSection({:p}, {:p}, 0x{:08x}, {:p}, {:p}, {}) failed: \
+				0x{:08x}",
+			dc,
+			&info,
+			gdi::DIB_RGB_COLORS,
+			&mut buffer,
+			std::ptr::null_mut::<()>(),
+			0,
+			error)))
+	}
+	let replaced_bitmap = gdi::SelectObject(dc, bitmap as _);
+
+	/* Use a font that scales with the size of the buttons. Antialiasing is
+	 * turned off, seeing as most devices can't display shades of gray. */
+	let face = "Segoe UI\0".encode_utf16().collect::<Vec<_>>();
+	let font = gdi::CreateFontW(
+		-((f64::from(height) * SigningLayout::BUTTON_HEIGHT * 0.4) as i32),
+		0,
+		0,
+		0,
+		gdi::FW_NORMAL,
+		0,
+		0,
+		0,
+		gdi::DEFAULT_CHARSET,
+		gdi::OUT_DEFAULT_PRECIS,
+		gdi::CLIP_DEFAULT_PRECIS,
+		gdi::NONANTIALIASED_QUALITY,
+		gdi::DEFAULT_PITCH,
+		face.as_ptr());
+	if font.is_null() {
+		let error = GetLastError();
+		let _ = gdi::SelectObject(dc, replaced_bitmap);
+		let _ = gdi::DeleteObject(bitmap as _);
+		let _ = gdi::DeleteDC(dc);
+
+		return Err(ScreenError::Rendering(format!(
+			"CreateFontW(\"Segoe UI\") failed: 0x{:08x}",
+			error)))
+	}
+	let replaced_font = gdi::SelectObject(dc, font as _);
+
+	/* Paint the background and the outlines of all the regions. */
+	let mut rect = winapi::shared::windef::RECT {
+		left: 0,
+		top: 0,
+		right: width as i32,
+		bottom: height as i32
+	};
+	let _ = user::FillRect(dc, &rect, gdi::GetStockObject(gdi::WHITE_BRUSH as _) as _);
+
+	let replaced_pen = gdi::SelectObject(dc, gdi::GetStockObject(gdi::BLACK_PEN as _));
+	let replaced_brush = gdi::SelectObject(dc, gdi::GetStockObject(gdi::NULL_BRUSH as _));
+	let _ = gdi::SetBkMode(dc, gdi::TRANSPARENT as _);
+	let _ = gdi::SetTextColor(dc, gdi::RGB(0, 0, 0));
+
+	let (left, top, right, bottom) = layout.signing_box().to_pixels(width, height);
+	let _ = gdi::Rectangle(dc, left, top, right, bottom);
+
+	for (button, region) in layout.buttons() {
+		let (left, top, right, bottom) = region.to_pixels(width, height);
+		let _ = gdi::Rectangle(dc, left, top, right, bottom);
+
+		let label = match button {
+			PadButton::Ok => crate::strings::pad::ok_btn(),
+			PadButton::Clear => crate::strings::pad::clear_btn(),
+			PadButton::Cancel => crate::strings::pad::cancel_btn(),
+		};
+		let label = label.encode_utf16().collect::<Vec<_>>();
+
+		rect.left = left;
+		rect.top = top;
+		rect.right = right;
+		rect.bottom = bottom;
+		let _ = user::DrawTextW(
+			dc,
+			label.as_ptr(),
+			label.len() as _,
+			&mut rect,
+			user::DT_CENTER | user::DT_VCENTER | user::DT_SINGLELINE);
+	}
+
+	/* Make sure GDI is done with the bitmap before we read from it. */
+	let _ = gdi::GdiFlush();
+
+	let pixels = std::slice::from_raw_parts(
+		buffer as *const u8,
+		width as usize * height as usize * 4);
+	let image = pixels
+		.chunks_exact(4)
+		.flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+		.collect::<Vec<_>>()
+		.into_boxed_slice();
+
+	/* Clean up. */
+	let _ = gdi::SelectObject(dc, replaced_brush);
+	let _ = gdi::SelectObject(dc, replaced_pen);
+	let _ = gdi::SelectObject(dc, replaced_font);
+	let _ = gdi::SelectObject(dc, replaced_bitmap);
+	let _ = gdi::DeleteObject(font as _);
+	let _ = gdi::DeleteObject(bitmap as _);
+	let _ = gdi::DeleteDC(dc);
+
+	Ok(image)
+}
+
+/// Enumeration of the reasons for which an image may fail to be pushed to the
+/// screen of a device.
+#[derive(Debug, thiserror::Error)]
+pub enum ScreenError {
+	/// The image file could not be loaded.
+	#[error("could not load image: {0}")]
+	ImageLoadFailed(image::ImageError),
+	/// The device has failed to process a command.
+	#[error("device command failed: {0}")]
+	DeviceCommandFailed(stu::Error),
+	/// The image could not be rendered.
+	#[error("could not render image: {0}")]
+	Rendering(String),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+
+	/// An event at the given position, touching the screen or not.
+	fn event(x: f64, y: f64, touching: bool) -> Event {
+		Event::new(Instant::now(), x, y, 0.5, touching, true)
+	}
+
+	#[test]
+	fn regions_are_half_open() {
+		let region = Region { x: 0.25, y: 0.5, width: 0.5, height: 0.25 };
+
+		assert!(region.contains(0.25, 0.5));
+		assert!(region.contains(0.5, 0.6));
+		assert!(region.contains(0.749, 0.749));
+		assert!(!region.contains(0.75, 0.6));
+		assert!(!region.contains(0.5, 0.75));
+		assert!(!region.contains(0.249, 0.6));
+		assert!(!region.contains(0.5, 0.499));
+	}
+
+	#[test]
+	fn regions_round_to_pixels() {
+		let region = Region { x: 0.25, y: 0.5, width: 0.5, height: 0.25 };
+		assert_eq!(region.to_pixels(800, 480), (200, 240, 600, 360));
+		assert_eq!(region.to_pixels(3, 3), (1, 2, 2, 2));
+	}
+
+	#[test]
+	fn every_button_is_hit_inside() {
+		let layout = SigningLayout::new();
+		for (button, region) in layout.buttons() {
+			let (x, y) = (region.x + region.width / 2.0, region.y + region.height / 2.0);
+			assert_eq!(layout.button_at(x, y), Some(button));
+		}
+		assert_eq!(
+			layout.buttons().map(|(button, _)| button).collect::<Vec<_>>(),
+			[PadButton::Ok, PadButton::Clear, PadButton::Cancel]);
+	}
+
+	#[test]
+	fn buttons_include_top_left_edges_only() {
+		let layout = SigningLayout::new();
+		for (button, region) in layout.buttons() {
+			let (right, bottom) = (region.x + region.width, region.y + region.height);
+			let middle = region.y + region.height / 2.0;
+
+			assert_eq!(layout.button_at(region.x, region.y), Some(button));
+			assert_ne!(layout.button_at(right, middle), Some(button));
+			assert_ne!(layout.button_at(region.x, bottom), Some(button));
+		}
+
+		/* The margins between the buttons belong to none of them. */
+		let ok = layout.buttons[0].1;
+		let gap = ok.x + ok.width + SigningLayout::MARGIN / 2.0;
+		assert_eq!(layout.button_at(gap, ok.y + ok.height / 2.0), None);
+	}
+
+	#[test]
+	fn signing_box_has_no_buttons() {
+		let layout = SigningLayout::new();
+		let area = layout.signing_box();
+
+		assert_eq!(layout.button_at(area.x + area.width / 2.0, area.y + area.height / 2.0), None);
+		assert_eq!(layout.button_at(area.x, area.y), None);
+		assert!(layout.buttons().all(|(_, region)| region.y >= area.y + area.height));
+	}
+
+	#[test]
+	fn clip_lifts_touches_outside_the_box() {
+		let layout = SigningLayout::new();
+		let area = layout.signing_box();
+		let inside = (area.x + area.width / 2.0, area.y + area.height / 2.0);
+		let outside = (0.5, area.y + area.height);
+
+		let kept = event(inside.0, inside.1, true);
+		assert_eq!(layout.clip(kept), kept);
+
+		let hovering = event(outside.0, outside.1, false);
+		assert_eq!(layout.clip(hovering), hovering);
+
+		let touching = event(outside.0, outside.1, true);
+		let clipped = layout.clip(touching);
+		assert!(!clipped.touching());
+		assert!(clipped.hovering());
+		assert_eq!(
+			(clipped.time(), clipped.x(), clipped.y(), clipped.pressure()),
+			(touching.time(), touching.x(), touching.y(), touching.pressure()));
+	}
+}
@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+use stu::{PressureCurve, Transform, Simplifier};
+use crate::path::{Interpolation, Parameterization, Gaps, CanvasStyle, RenderOptions, VerificationOptions, ValidationRules};
+use crate::export::{ImageFormat, SvgOptions, IsoVariant, SampleOptions};
+
+/// The name of the folder holding our files in the application data folder.
+const SETTINGS_FOLDER: &str = "HC-Wacom";
+
+/// The name of the file our settings are stored in.
+const SETTINGS_FILE: &str = "settings.json";
+
+/// The user-configurable settings of the application.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+	/// The image to be pushed to the idle screen of the device on connect, if
+	/// any, in either the BMP, PNG or JPEG formats.
+	pub idle_screen: Option<PathBuf>,
+	/// Whether the signing controls should be displayed on the screen of the
+	/// device, rather than only being available in the management window.
+	pub pad_controls: bool,
+	/// The method used to interpolate the strokes of signatures when they get
+	/// played back.
+	pub interpolation: Interpolation,
+	/// How the time of the playback of signatures maps to their events.
+	pub parameterization: Parameterization,
+	/// How the movement of the pen between strokes is played back.
+	pub gaps: Gaps,
+	/// The simplification applied to the strokes of signatures before they get
+	/// played back, if any.
+	pub simplification: Option<Simplification>,
+	/// The style signatures are rendered with in the preview.
+	pub canvas: CanvasStyle,
+	/// How signatures are saved to files.
+	pub export: ExportSettings,
+	/// The file holding the Ed25519 key signature packages are signed with, if
+	/// any. Packages are sealed without a signature otherwise.
+	pub signing_key: Option<PathBuf>,
+	/// The file holding the Ed25519 public key signature packages are expected
+	/// to be signed with, if any. When it is set, packages that are not signed
+	/// with it are rejected when they are opened. Otherwise, packages open with
+	/// a warning that who sealed them could not be told.
+	pub trusted_key: Option<PathBuf>,
+	/// How signature files get encrypted when they are saved, if at all.
+	pub encryption: Option<Encryption>,
+	/// The rules signatures have to follow before they can be painted or
+	/// saved.
+	pub validation: ValidationRules,
+	/// How signatures are checked against enrolled references before they get
+	/// painted.
+	pub verification: VerificationSettings,
+	/// How the end of signatures is detected, and what is done once they are
+	/// complete, if they are to be completed automatically at all.
+	pub completion: Option<CompletionSettings>,
+	/// The settings specific to each device, keyed by their vendor and product
+	/// identification numbers.
+	pub devices: BTreeMap<String, DeviceSettings>,
+}
+impl Settings {
+	/// The key under which the settings of the given device are stored.
+	fn device_key(info: &stu::Information) -> String {
+		format!("{:04x}:{:04x}", info.vendor(), info.product())
+	}
+
+	/// The settings specific to the given device.
+	pub fn device(&self, info: &stu::Information) -> DeviceSettings {
+		self.devices
+			.get(&Self::device_key(info))
+			.cloned()
+			.unwrap_or_default()
+	}
+
+	/// Changes the settings specific to the given device.
+	pub fn set_device(&mut self, info: &stu::Information, settings: DeviceSettings) {
+		self.devices.insert(Self::device_key(info), settings);
+	}
+
+	/// The path to the file the settings are stored in.
+	///
+	/// This is a file in the roaming application data folder of the user, if
+	/// one is available, and a file in the current working directory if not.
+	pub fn path() -> PathBuf {
+		match std::env::var_os("APPDATA") {
+			Some(data) => PathBuf::from(data)
+				.join(SETTINGS_FOLDER)
+				.join(SETTINGS_FILE),
+			None => PathBuf::from(SETTINGS_FILE)
+		}
+	}
+
+	/// Loads the settings from the settings file.
+	///
+	/// If the settings file does not exist, the default settings are returned.
+	pub fn load() -> Result<Self, SettingsError> {
+		let data = match std::fs::read(Self::path()) {
+			Ok(data) => data,
+			Err(what) if what.kind() == std::io::ErrorKind::NotFound =>
+				return Ok(Default::default()),
+			Err(what) => return Err(SettingsError::Io(what))
+		};
+
+		serde_json::from_slice(&data[..])
+			.map_err(SettingsError::Format)
+	}
+
+	/// Stores these settings to the settings file.
+	pub fn store(&self) -> Result<(), SettingsError> {
+		let path = Self::path();
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)
+				.map_err(SettingsError::Io)?;
+		}
+
+		let data = serde_json::to_vec_pretty(self)
+			.map_err(SettingsError::Format)?;
+		std::fs::write(path, data)
+			.map_err(SettingsError::Io)
+	}
+}
+
+/// The user-configurable settings specific to a device.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+	/// The response curve the pressure reported by the device is shaped by when
+	/// the ink is drawn. Signatures keep the pressure as it was reported.
+	pub pressure: PressureCurve,
+	/// The orientation the device is mounted in.
+	pub transform: Transform,
+}
+
+/// The settings of the simplification of the strokes of a signature.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Simplification {
+	/// The algorithm used to simplify the strokes.
+	pub method: Simplifier,
+	/// The largest distance the simplified strokes may stray from the original
+	/// ones, in units of the input grid of the device.
+	pub tolerance: f64,
+}
+
+/// The settings of the exporting of signatures to files.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+	/// How signatures are rendered when exported as images.
+	pub render: RenderOptions,
+	/// The format images are saved in when the file name doesn't pick one.
+	pub format: ImageFormat,
+	/// Whether images are saved with a transparent background, in the formats
+	/// that support it.
+	pub transparent: bool,
+	/// How signatures are written when exported as vector drawings.
+	pub svg: SvgOptions,
+	/// The variant of ISO/IEC 19794-7 records signatures are written in.
+	/// Records are read in whichever variant they were written in.
+	pub iso: IsoVariant,
+	/// How signatures are written when exported as tables of samples.
+	pub samples: SampleOptions,
+}
+
+/// The settings of the verification of signatures against enrolled references.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationSettings {
+	/// The files holding the reference signatures signatures are verified
+	/// against when no customer is picked, in any of the formats signatures
+	/// can be opened from. Signatures are not verified when there are none.
+	pub references: Vec<PathBuf>,
+	/// The files holding the reference signatures enrolled for each customer,
+	/// keyed by their identification. Signatures of a customer are verified
+	/// against their own references alone, and not at all when they have none.
+	pub customers: BTreeMap<String, Vec<PathBuf>>,
+	/// The folder references enrolled from the management window are saved
+	/// to. They are saved next to the settings file when it is not set.
+	pub folder: Option<PathBuf>,
+	/// How signatures are compared against the references.
+	pub options: VerificationOptions,
+}
+impl VerificationSettings {
+	/// The name of the folder next to the settings file references are saved
+	/// to by default.
+	const REFERENCES_FOLDER: &'static str = "references";
+
+	/// The files holding the references the signatures of the given customer,
+	/// or of no one in particular, are verified against.
+	pub fn references(&self, customer: Option<&str>) -> &[PathBuf] {
+		match customer {
+			Some(customer) => self.customers
+				.get(customer)
+				.map(|references| &references[..])
+				.unwrap_or(&[]),
+			None => &self.references[..]
+		}
+	}
+
+	/// The folder references enrolled from the management window are saved to.
+	pub fn folder(&self) -> PathBuf {
+		match &self.folder {
+			Some(folder) => folder.clone(),
+			None => Settings::path()
+				.parent()
+				.map(|parent| parent.join(Self::REFERENCES_FOLDER))
+				.unwrap_or_else(|| PathBuf::from(Self::REFERENCES_FOLDER))
+		}
+	}
+}
+
+/// The ways in which signature files may be encrypted when they are saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Encryption {
+	/// Files are encrypted with a key derived from a passphrase, which is asked
+	/// for every time a file is saved or opened.
+	Passphrase,
+	/// Files are encrypted with a raw 256-bit key.
+	Key {
+		/// The file holding the key.
+		key: PathBuf,
+	},
+	/// Files are encrypted to the holder of an X25519 private key.
+	Recipient {
+		/// The file holding the public key files are encrypted to.
+		public_key: PathBuf,
+		/// The file holding the private key files are decrypted with, if it is
+		/// available on this machine.
+		private_key: Option<PathBuf>,
+	},
+	/// Files are encrypted to the holder of an RSA private key.
+	RsaRecipient {
+		/// The file holding the public key files are encrypted to, in PEM or
+		/// DER encoding.
+		public_key: PathBuf,
+		/// The file holding the private key files are decrypted with, if it is
+		/// available on this machine, in PEM or DER encoding.
+		private_key: Option<PathBuf>,
+	},
+}
+
+/// The settings of the automatic detection of the end of signatures.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompletionSettings {
+	/// How long the pen may hover over the screen after being lifted before the
+	/// signature is considered complete, in milliseconds.
+	pub pen_up: u64,
+	/// How long the pen may be out of proximity after being lifted before the
+	/// signature is considered complete, in milliseconds.
+	pub out_of_proximity: u64,
+	/// What is done once the signature is complete.
+	pub action: CompletionAction,
+}
+impl Default for CompletionSettings {
+	fn default() -> Self {
+		Self {
+			pen_up: 3000,
+			out_of_proximity: 1000,
+			action: CompletionAction::Confirm,
+		}
+	}
+}
+
+/// The actions that may be taken once a signature is complete.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompletionAction {
+	/// The signer is asked to confirm the signature, by pressing the OK button
+	/// on the signing screen of the device, which gets highlighted, or in the
+	/// management window, when the signing screen is not displayed.
+	Confirm,
+	/// The signature is painted.
+	Paint,
+	/// The signature is saved to a file in the given folder, named after the
+	/// time it was saved at, in the format in the export settings.
+	Save {
+		/// The folder signatures are saved to.
+		folder: PathBuf,
+	},
+}
+
+/// Enumeration of the reasons for which the settings may fail to be loaded or
+/// stored.
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+	/// The settings file could not be accessed.
+	#[error("could not access the settings file: {0}")]
+	Io(std::io::Error),
+	/// The settings file is not in the expected format.
+	#[error("invalid settings file: {0}")]
+	Format(serde_json::Error),
+}
@@ -5,8 +5,131 @@ pub mod manager {
 	pub fn help_btn() -> &'static str { "Help" }
 	pub fn help() -> &'static str { "Nothing here but us chickens!" }
 	pub fn display_clear_btn() -> &'static str { "Clear" }
+	pub fn display_undo_btn() -> &'static str { "Undo" }
+	pub fn display_open_btn() -> &'static str { "Open" }
+	pub fn open_dialog_title() -> &'static str { "Open Signature" }
+	pub fn display_save_btn() -> &'static str { "Save" }
+	pub fn save_dialog_title() -> &'static str { "Save Signature" }
+	pub fn verification_title() -> &'static str { "Signature Verification" }
+	pub fn verification_rejected(verification: crate::path::Verification) -> String {
+		format!(
+			"The signature does not match the enrolled references \
+				(similarity of {:.0}%, distance of {:.3}).\n\n\
+				Paint it anyway?",
+			verification.similarity * 100.0,
+			verification.distance)
+	}
+	pub fn customer_placeholder() -> &'static str { "Customer" }
+	pub fn display_enroll_btn() -> &'static str { "Enroll" }
+	pub fn enroll_title() -> &'static str { "Reference Enrollment" }
+	pub fn enroll_no_customer() -> &'static str {
+		"Type in the customer the signature is a reference of before enrolling it."
+	}
+	pub fn enrolled(customer: &str, count: usize) -> String {
+		format!(
+			"The signature was enrolled as a reference of {}, who now has {} \
+				reference(s).",
+			customer,
+			count)
+	}
+	pub fn validation_title() -> &'static str { "Signature Quality" }
+	pub fn validation_rejected(rejections: &[crate::path::Rejection]) -> String {
+		use crate::path::Rejection;
+
+		let reasons = rejections.iter()
+			.map(|rejection| match *rejection {
+				Rejection::TooFewPoints { points, min } =>
+					format!("It has too few points ({} of at least {}).", points, min),
+				Rejection::TooShort { duration, min } =>
+					format!("It was drawn too quickly ({:.0} ms of at least {:.0} ms).", duration, min),
+				Rejection::TooNarrow { width, min } =>
+					format!("It is too narrow ({:.0}% of at least {:.0}% of the screen).", width * 100.0, min * 100.0),
+				Rejection::TooFlat { height, min } =>
+					format!("It is too flat ({:.0}% of at least {:.0}% of the screen).", height * 100.0, min * 100.0),
+				Rejection::IdleGap { gap, max } =>
+					format!("The pen was lifted for too long ({:.0} ms of at most {:.0} ms).", gap, max),
+				Rejection::OutsideArea { strokes } =>
+					format!("{} stroke(s) left the signing area.", strokes),
+			})
+			.map(|reason| format!(" - {}", reason))
+			.collect::<Vec<_>>()
+			.join("\n");
+
+		format!("The signature was rejected:\n\n{}\n\nPlease clear it and sign again.", reasons)
+	}
+	pub fn cancelled_title() -> &'static str { "Signature Cancelled" }
+	pub fn cancelled() -> &'static str { "The signer cancelled the signature, which has been discarded." }
+	pub fn completion_title() -> &'static str { "Signature Complete" }
+	pub fn completion_confirm() -> &'static str { "The signature is complete. Paint it?" }
+	pub fn anomalies_title() -> &'static str { "Suspicious Signature" }
+	pub fn anomalies_found(anomalies: &[(usize, crate::path::Anomaly)]) -> String {
+		use crate::path::Anomaly;
+
+		let count = |kind: Anomaly| anomalies.iter()
+			.filter(|(_, anomaly)| *anomaly == kind)
+			.count();
+
+		format!(
+			"The signature has {} repeated events and {} events out of order. \
+			They have been kept as they are.",
+			count(Anomaly::Duplicate),
+			count(Anomaly::OutOfOrder))
+	}
+	pub fn encrypted_filter() -> &'static str { "Encrypted Signature" }
+	pub fn document_dialog_title() -> &'static str { "Select the Signed Document" }
+	pub fn package_title() -> &'static str { "Signature Package" }
+	pub fn package_verified(package: &crate::export::VerifiedPackage) -> String {
+		let document = &package.package.metadata.document;
+		format!(
+			"The signature package was signed with the trusted key, and has not \
+				been modified since.\n\n\
+				Document ({}): {}\n\
+				Signed by: {}",
+			document.algorithm,
+			document.digest,
+			package.signer
+				.map(|key| crate::export::hex(key.as_bytes()))
+				.unwrap_or_else(|| "nobody".to_owned()))
+	}
+	pub fn package_untrusted(package: &crate::export::VerifiedPackage) -> String {
+		let document = &package.package.metadata.document;
+		let signer = match package.signer {
+			Some(key) => format!(
+				"The package was signed with a key that is not trusted, so it \
+					can't be told who signed it. Set the trusted key to check \
+					packages against it.\n\nSigned by: {}",
+				crate::export::hex(key.as_bytes())),
+			None => "The package is not signed, so anyone may have sealed it \
+				after modifying it.".to_owned()
+		};
+		format!(
+			"The signature package has not been modified by accident since it \
+				was sealed, but it can't be trusted.\n\n\
+				{}\n\n\
+				Document ({}): {}",
+			signer,
+			document.algorithm,
+			document.digest)
+	}
 	pub fn display_paint_btn() -> &'static str { "Paint" }
 	pub fn display_label() -> &'static str { "Display Controls" }
+	pub fn pressure_light() -> &'static str { "Light touch" }
+	pub fn pressure_normal() -> &'static str { "Normal touch" }
+	pub fn pressure_firm() -> &'static str { "Firm touch" }
+	pub fn orientation(degrees: u32, mirrored: bool) -> String {
+		if mirrored {
+			format!("Turned {}°, mirrored", degrees)
+		} else {
+			format!("Turned {}°", degrees)
+		}
+	}
+}
+
+/// Strings used in the signing screen displayed on the device.
+pub mod pad {
+	pub fn ok_btn() -> &'static str { "OK" }
+	pub fn clear_btn() -> &'static str { "Clear" }
+	pub fn cancel_btn() -> &'static str { "Cancel" }
 }
 
 /// Strings used in the device selection window.
@@ -17,6 +140,16 @@ pub mod selector {
 	pub fn accept() -> &'static str { "Connect" }
 }
 
+/// Strings used in the passphrase prompt window.
+pub mod passphrase {
+	pub fn title() -> &'static str { "Passphrase" }
+	pub fn description() -> &'static str { "Enter the passphrase protecting the signature." }
+	pub fn passphrase() -> &'static str { "Passphrase" }
+	pub fn confirmation() -> &'static str { "Confirm the passphrase" }
+	pub fn cancel() -> &'static str { "Cancel" }
+	pub fn accept() -> &'static str { "OK" }
+}
+
 /// Strings used in the area selection window.
 pub mod area {
 	pub fn tip() -> &'static str {
@@ -60,6 +193,52 @@ pub mod errors {
 			information.product(),
 			what, what)
 	}
+	pub fn settings_load_failed(
+		what: crate::settings::SettingsError) -> String {
+		format!(
+			"Could not load the settings, the defaults will be used instead: {}",
+			what)
+	}
+	pub fn export_failed(
+		what: crate::export::ExportError) -> String {
+		format!("Could not save the signature: {}", what)
+	}
+	pub fn import_failed(
+		what: crate::export::ImportError) -> String {
+		format!("Could not open the signature: {}", what)
+	}
+	pub fn passphrase_empty() -> &'static str { "The passphrase must not be empty." }
+	pub fn passphrase_mismatch() -> &'static str { "The passphrases do not match." }
+	pub fn passphrase_prompt_failed(
+		what: nwg::NwgError) -> String {
+		format!("Could not ask for the passphrase: {}", what)
+	}
+	pub fn reference_load_failed(
+		what: crate::export::ImportError) -> String {
+		format!(
+			"Could not open the reference signatures: {}.\n\nPaint the signature anyway?",
+			what)
+	}
+	pub fn verification_failed(
+		what: crate::path::VerificationError) -> String {
+		format!(
+			"Could not verify the signature: {}.\n\nPaint it anyway?",
+			what)
+	}
+	pub fn settings_store_failed(
+		what: crate::settings::SettingsError) -> String {
+		format!("Could not save the settings: {}", what)
+	}
+	pub fn idle_screen_failed(
+		what: crate::screen::ScreenError) -> String {
+		format!("Could not push the idle screen to the device: {}", what)
+	}
+	pub fn pad_screen_failed(
+		what: crate::screen::ScreenError) -> String {
+		format!(
+			"Could not display the signing controls on the device: {}",
+			what)
+	}
 	pub fn management_failed(
 		what: crate::window::ManagementError) -> String {
 		format!(
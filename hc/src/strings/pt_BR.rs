@@ -17,8 +17,132 @@ pub mod manager {
 		"
 	}
 	pub fn display_clear_btn() -> &'static str { "Limpar" }
+	pub fn display_undo_btn() -> &'static str { "Desfazer" }
+	pub fn display_open_btn() -> &'static str { "Abrir" }
+	pub fn open_dialog_title() -> &'static str { "Abrir Assinatura" }
+	pub fn display_save_btn() -> &'static str { "Salvar" }
+	pub fn save_dialog_title() -> &'static str { "Salvar Assinatura" }
+	pub fn verification_title() -> &'static str { "Verificação de Assinatura" }
+	pub fn verification_rejected(verification: crate::path::Verification) -> String {
+		format!(
+			"A assinatura não corresponde às referências cadastradas \
+				(semelhança de {:.0}%, distância de {:.3}).\n\n\
+				Assinar mesmo assim?",
+			verification.similarity * 100.0,
+			verification.distance)
+	}
+	pub fn customer_placeholder() -> &'static str { "Cliente" }
+	pub fn display_enroll_btn() -> &'static str { "Cadastrar" }
+	pub fn enroll_title() -> &'static str { "Cadastro de Referência" }
+	pub fn enroll_no_customer() -> &'static str {
+		"Digite o cliente de quem a assinatura é referência antes de cadastrá-la."
+	}
+	pub fn enrolled(customer: &str, count: usize) -> String {
+		format!(
+			"A assinatura foi cadastrada como referência de {}, que agora tem \
+				{} referência(s).",
+			customer,
+			count)
+	}
+	pub fn validation_title() -> &'static str { "Qualidade da Assinatura" }
+	pub fn validation_rejected(rejections: &[crate::path::Rejection]) -> String {
+		use crate::path::Rejection;
+
+		let reasons = rejections.iter()
+			.map(|rejection| match *rejection {
+				Rejection::TooFewPoints { points, min } =>
+					format!("Ela tem pontos de menos ({} de no mínimo {}).", points, min),
+				Rejection::TooShort { duration, min } =>
+					format!("Ela foi feita rápido demais ({:.0} ms de no mínimo {:.0} ms).", duration, min),
+				Rejection::TooNarrow { width, min } =>
+					format!("Ela é estreita demais ({:.0}% de no mínimo {:.0}% da tela).", width * 100.0, min * 100.0),
+				Rejection::TooFlat { height, min } =>
+					format!("Ela é baixa demais ({:.0}% de no mínimo {:.0}% da tela).", height * 100.0, min * 100.0),
+				Rejection::IdleGap { gap, max } =>
+					format!("A caneta ficou levantada por tempo demais ({:.0} ms de no máximo {:.0} ms).", gap, max),
+				Rejection::OutsideArea { strokes } =>
+					format!("{} traço(s) saíram da área de assinatura.", strokes),
+			})
+			.map(|reason| format!(" - {}", reason))
+			.collect::<Vec<_>>()
+			.join("\n");
+
+		format!("A assinatura foi rejeitada:\n\n{}\n\nLimpe-a e assine novamente.", reasons)
+	}
+	pub fn cancelled_title() -> &'static str { "Assinatura Cancelada" }
+	pub fn cancelled() -> &'static str { "O signatário cancelou a assinatura, que foi descartada." }
+	pub fn completion_title() -> &'static str { "Assinatura Concluída" }
+	pub fn completion_confirm() -> &'static str { "A assinatura foi concluída. Assinar agora?" }
+	pub fn anomalies_title() -> &'static str { "Assinatura Suspeita" }
+	pub fn anomalies_found(anomalies: &[(usize, crate::path::Anomaly)]) -> String {
+		use crate::path::Anomaly;
+
+		let count = |kind: Anomaly| anomalies.iter()
+			.filter(|(_, anomaly)| *anomaly == kind)
+			.count();
+
+		format!(
+			"A assinatura tem {} eventos repetidos e {} eventos fora de ordem. \
+			Eles foram mantidos como estão.",
+			count(Anomaly::Duplicate),
+			count(Anomaly::OutOfOrder))
+	}
+	pub fn encrypted_filter() -> &'static str { "Assinatura Criptografada" }
+	pub fn document_dialog_title() -> &'static str { "Selecionar o Documento Assinado" }
+	pub fn package_title() -> &'static str { "Pacote de Assinatura" }
+	pub fn package_verified(package: &crate::export::VerifiedPackage) -> String {
+		let document = &package.package.metadata.document;
+		format!(
+			"O pacote de assinatura foi assinado com a chave confiável, e não \
+				foi modificado desde então.\n\n\
+				Documento ({}): {}\n\
+				Assinado por: {}",
+			document.algorithm,
+			document.digest,
+			package.signer
+				.map(|key| crate::export::hex(key.as_bytes()))
+				.unwrap_or_else(|| "ninguém".to_owned()))
+	}
+	pub fn package_untrusted(package: &crate::export::VerifiedPackage) -> String {
+		let document = &package.package.metadata.document;
+		let signer = match package.signer {
+			Some(key) => format!(
+				"O pacote foi assinado com uma chave que não é confiável, então \
+					não é possível saber quem o assinou. Defina a chave \
+					confiável para verificar os pacotes com ela.\n\n\
+					Assinado por: {}",
+				crate::export::hex(key.as_bytes())),
+			None => "O pacote não está assinado, então qualquer um pode tê-lo \
+				selado depois de modificá-lo.".to_owned()
+		};
+		format!(
+			"O pacote de assinatura não foi modificado por acidente desde que \
+				foi selado, mas não é confiável.\n\n\
+				{}\n\n\
+				Documento ({}): {}",
+			signer,
+			document.algorithm,
+			document.digest)
+	}
 	pub fn display_paint_btn() -> &'static str { "Assinar" }
 	pub fn display_label() -> &'static str { "Oncologia Clínica - HC FMRP - USP" }
+	pub fn pressure_light() -> &'static str { "Toque leve" }
+	pub fn pressure_normal() -> &'static str { "Toque normal" }
+	pub fn pressure_firm() -> &'static str { "Toque firme" }
+	pub fn orientation(degrees: u32, mirrored: bool) -> String {
+		if mirrored {
+			format!("Girado {}°, espelhado", degrees)
+		} else {
+			format!("Girado {}°", degrees)
+		}
+	}
+}
+
+/// Strings used in the signing screen displayed on the device.
+pub mod pad {
+	pub fn ok_btn() -> &'static str { "OK" }
+	pub fn clear_btn() -> &'static str { "Limpar" }
+	pub fn cancel_btn() -> &'static str { "Cancelar" }
 }
 
 /// Strings used in the device selection window.
@@ -29,6 +153,16 @@ pub mod selector {
 	pub fn accept() -> &'static str { "Conectar" }
 }
 
+/// Strings used in the passphrase prompt window.
+pub mod passphrase {
+	pub fn title() -> &'static str { "Senha" }
+	pub fn description() -> &'static str { "Digite a senha que protege a assinatura." }
+	pub fn passphrase() -> &'static str { "Senha" }
+	pub fn confirmation() -> &'static str { "Confirme a senha" }
+	pub fn cancel() -> &'static str { "Cancelar" }
+	pub fn accept() -> &'static str { "OK" }
+}
+
 /// Strings used in the area selection window.
 pub mod area {
 	pub fn tip() -> &'static str {
@@ -75,6 +209,56 @@ pub mod errors {
 			information.product(),
 			what, what)
 	}
+	pub fn settings_load_failed(
+		what: crate::settings::SettingsError) -> String {
+		format!(
+			"Não foi possível carregar as configurações, os valores padrão \
+			serão usados: {}",
+			what)
+	}
+	pub fn export_failed(
+		what: crate::export::ExportError) -> String {
+		format!("Não foi possível salvar a assinatura: {}", what)
+	}
+	pub fn import_failed(
+		what: crate::export::ImportError) -> String {
+		format!("Não foi possível abrir a assinatura: {}", what)
+	}
+	pub fn passphrase_empty() -> &'static str { "A senha não pode estar vazia." }
+	pub fn passphrase_mismatch() -> &'static str { "As senhas não coincidem." }
+	pub fn passphrase_prompt_failed(
+		what: nwg::NwgError) -> String {
+		format!("Não foi possível pedir a senha: {}", what)
+	}
+	pub fn reference_load_failed(
+		what: crate::export::ImportError) -> String {
+		format!(
+			"Não foi possível abrir as assinaturas de referência: {}.\n\nAssinar mesmo assim?",
+			what)
+	}
+	pub fn verification_failed(
+		what: crate::path::VerificationError) -> String {
+		format!(
+			"Não foi possível verificar a assinatura: {}.\n\nAssinar mesmo assim?",
+			what)
+	}
+	pub fn settings_store_failed(
+		what: crate::settings::SettingsError) -> String {
+		format!("Não foi possível salvar as configurações: {}", what)
+	}
+	pub fn idle_screen_failed(
+		what: crate::screen::ScreenError) -> String {
+		format!(
+			"Não foi possível enviar a tela de descanso ao dispositivo: {}",
+			what)
+	}
+	pub fn pad_screen_failed(
+		what: crate::screen::ScreenError) -> String {
+		format!(
+			"Não foi possível mostrar os controles de assinatura no \
+			dispositivo: {}",
+			what)
+	}
 	pub fn management_failed(
 		what: crate::window::ManagementError) -> String {
 		format!(
@@ -1,24 +1,64 @@
-use stu::{Tablet, Queue, Capability};
+use stu::{Tablet, Capability, FilteredQueue, Pipeline, EventFilter, PressureCurve, Transform, Rotation};
+use stu::{CompletionDetector, SignatureComplete};
 use std::cell::RefCell;
-use crate::path::{EventPath, EventCanvas};
+use std::collections::BTreeMap;
+use crate::path::{EventPath, EventCanvas, Enrollment, SignatureFeatures};
 use crate::window::area::PickPhysicalAreaError;
 use crate::robot::Playback;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::num::NonZeroU32;
 use crate::window::AreaSelectionParameters;
+use crate::settings::{Settings, DeviceSettings, Encryption, CompletionAction};
+use crate::screen::{PadScreen, PadButton, SigningLayout};
+use crate::export::{ExportFormat, ExportError, ImportError, DeviceUnits, ContainerHeader};
+use crate::export::{ImageFormat, DocumentHash, CaptureMetadata, SignaturePackage};
+use crate::export::{EncryptionMethod, EncryptionKey, DecryptionKey, ENCRYPTED_EXTENSION, VerifiedPackage};
+use std::path::{Path, PathBuf};
+use stu::{ScreenKind, ScreenSlot};
 
 /// Manage the given tablet device.
-pub fn manage(device: Tablet) -> Result<(), ManagementError> {
+pub fn manage(device: Tablet, settings: Settings) -> Result<(), ManagementError> {
+	let device_settings = settings.device(&device.info());
 	let queue = device.queue()
-		.map_err(ManagementError::QueueCreationError)?;
+		.map_err(ManagementError::QueueCreationError)?
+		.filtered(event_pipeline(&device_settings));
 	let caps = device.capability()
 		.map_err(ManagementError::CapabilityQueryError)?;
 	let (tx, rx) = std::sync::mpsc::channel();
 
+	if let Some(path) = &settings.idle_screen {
+		/* Failing to push the idle screen is not fatal, the device is still
+		 * perfectly usable for collecting signatures without it. */
+		if let Err(what) = crate::screen::push_idle_screen(&device, path, device_settings.transform) {
+			nwg::error_message(
+				crate::strings::errors::title(),
+				&crate::strings::errors::idle_screen_failed(what));
+		}
+	}
+
+	let pad = if settings.pad_controls {
+		/* Fall back to controlling the signature from the window alone when
+		 * the signing screen can't be displayed on the device. */
+		match PadScreen::new(&device, SigningLayout::new(), device_settings.transform) {
+			Ok(pad) => Some(pad),
+			Err(what) => {
+				nwg::error_message(
+					crate::strings::errors::title(),
+					&crate::strings::errors::pad_screen_failed(what));
+				None
+			}
+		}
+	} else {
+		None
+	};
+
 	let window = ManagementWindow::new(
 		device,
 		queue,
 		caps,
+		device_settings.transform,
+		settings,
+		pad,
 		tx);
 	let _window = nwg::NativeUi::build_ui(window)
 		.map_err(ManagementError::WindowCreationError)?;
@@ -30,6 +70,89 @@ pub fn manage(device: Tablet) -> Result<(), ManagementError> {
 	}
 }
 
+/// Creates the pipeline the events from a device with the given settings go
+/// through before they get to the management window.
+fn event_pipeline(settings: &DeviceSettings) -> Pipeline {
+	Pipeline::new()
+		.with(settings.transform)
+		.with(stu::TouchDebounce::new(2))
+		.with(stu::Dedup::new())
+		.with(stu::HoverFilter::new())
+		.with(stu::OneEuro::new(1.0, 10.0))
+}
+
+/// The pressure response presets the user may pick from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum PressurePreset {
+	/// Makes light strokes heavier, for light-handed signers.
+	Light,
+	/// Leaves the pressure unchanged.
+	Normal,
+	/// Makes heavy strokes lighter, for heavy-handed signers.
+	Firm,
+}
+impl PressurePreset {
+	/// All of the presets, in the order they are displayed in.
+	const ALL: [Self; 3] = [Self::Light, Self::Normal, Self::Firm];
+
+	/// The pressure curve corresponding to this preset.
+	fn curve(self) -> PressureCurve {
+		match self {
+			Self::Light => PressureCurve::gamma(0.5),
+			Self::Normal => PressureCurve::linear(),
+			Self::Firm => PressureCurve::gamma(1.5),
+		}
+	}
+}
+
+/// A structure that wraps a preset and provides a display implementation.
+#[derive(Default)]
+struct PresetDisplay(Option<PressurePreset>);
+impl std::fmt::Display for PresetDisplay {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let name = match self.0.unwrap() {
+			PressurePreset::Light => crate::strings::manager::pressure_light(),
+			PressurePreset::Normal => crate::strings::manager::pressure_normal(),
+			PressurePreset::Firm => crate::strings::manager::pressure_firm(),
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+/// The orientations the user may mount the device in, made up of each of the
+/// rotations, both as they are and mirrored left to right. Mirroring top to
+/// bottom is the same as turning a mirrored view by half a turn, so it needs
+/// no entries of its own.
+fn orientations() -> Vec<Transform> {
+	Rotation::ALL
+		.iter()
+		.flat_map(|rotation| [false, true]
+			.iter()
+			.map(move |mirrored| Transform::rotated(*rotation).with_horizontal_flip(*mirrored)))
+		.collect()
+}
+
+/// Whether the given transforms map the screen of a device to the view in the
+/// same way, even if they are made up of different rotations and flips.
+fn same_orientation(a: Transform, b: Transform) -> bool {
+	[(0.0, 0.0), (1.0, 0.0)]
+		.iter()
+		.all(|(x, y)| a.apply(*x, *y) == b.apply(*x, *y))
+}
+
+/// A structure that wraps an orientation and provides a display implementation.
+#[derive(Default)]
+struct OrientationDisplay(Option<Transform>);
+impl std::fmt::Display for OrientationDisplay {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let transform = self.0.unwrap();
+		write!(f, "{}", crate::strings::manager::orientation(
+			transform.rotation().degrees(),
+			transform.flips_horizontally()))
+	}
+}
+
 /// Tries running a device command and kills the manager if it fails.
 macro_rules! mng_cmd_try {
 	($this:expr, $e:expr) => {
@@ -84,15 +207,88 @@ pub struct ManagementWindow {
 	)]
 	display_clear_btn: nwg::Button,
 
-	/// Button for painting the signature.
+	/// Button for undoing the last stroke of the signature.
 	#[nwg_control(
 		position: (110, 150)
 	)]
+	#[nwg_events(
+		OnButtonClick: [Self::on_undo_pressed]
+	)]
+	display_undo_btn: nwg::Button,
+
+	/// Button for opening a signature from a file.
+	#[nwg_control(
+		position: (210, 150)
+	)]
+	#[nwg_events(
+		OnButtonClick: [Self::on_open_pressed]
+	)]
+	display_open_btn: nwg::Button,
+
+	/// Dialog for picking the file a signature gets opened from.
+	#[nwg_resource(action: nwg::FileDialogAction::Open)]
+	open_dialog: nwg::FileDialog,
+
+	/// Button for saving the signature to a file.
+	#[nwg_control(
+		position: (310, 150)
+	)]
+	#[nwg_events(
+		OnButtonClick: [Self::on_save_pressed]
+	)]
+	display_save_btn: nwg::Button,
+
+	/// Dialog for picking the document a signature package is bound to.
+	#[nwg_resource(action: nwg::FileDialogAction::Open)]
+	document_dialog: nwg::FileDialog,
+
+	/// Button for painting the signature.
+	#[nwg_control(
+		position: (410, 150)
+	)]
 	#[nwg_events(
 		OnButtonClick: [Self::on_paint_pressed]
 	)]
 	display_paint_btn: nwg::Button,
 
+	/// Selection box for the pressure response of the device.
+	#[nwg_control(
+		position: (10, 7),
+		size: (140, 25)
+	)]
+	#[nwg_events(
+		OnComboxBoxSelection: [Self::on_pressure_selected]
+	)]
+	pressure_select: nwg::ComboBox<PresetDisplay>,
+
+	/// Selection box for the orientation the device is mounted in.
+	#[nwg_control(
+		position: (10, 7),
+		size: (140, 25)
+	)]
+	#[nwg_events(
+		OnComboxBoxSelection: [Self::on_orientation_selected]
+	)]
+	orientation_select: nwg::ComboBox<OrientationDisplay>,
+
+	/// Input for the identification of the customer signing, whose references
+	/// the signature is verified against and enrolled as.
+	#[nwg_control(
+		position: (10, 7),
+		size: (140, 25)
+	)]
+	customer_input: nwg::TextInput,
+
+	/// Button for enrolling the signature as a reference of the customer.
+	#[nwg_control(
+		position: (10, 7),
+		size: (80, 25)
+	)]
+	#[nwg_events(
+		OnButtonClick: [Self::on_enroll_pressed]
+	)]
+	display_enroll_btn: nwg::Button,
+
 	/// Button for accessing the help dialog box.
 	#[nwg_control()]
 	#[nwg_events(
@@ -117,8 +313,30 @@ pub struct ManagementWindow {
 
 	/// The device we're connected to.
 	device: Tablet,
+	/// The settings of the application.
+	settings: RefCell<Settings>,
+	/// The references signatures are verified against, keyed by the customer
+	/// they were enrolled for, if any, as built from the files in the settings.
+	enrollments: RefCell<BTreeMap<Option<String>, Enrollment>>,
+	/// The capabilities of the device we're connected to.
+	caps: Capability,
+	/// The orientation the device is viewed in.
+	transform: RefCell<Transform>,
+	/// The signing screen displayed on the device, if any.
+	pad: RefCell<Option<PadScreen>>,
+	/// Whether the pen was touching the device in the last event we processed.
+	pen_down: RefCell<bool>,
+	/// The detector of the end of signatures, if they are to be completed
+	/// automatically.
+	completion: RefCell<Option<CompletionDetector>>,
+	/// Whether the countdown to the completion of the signature was shown in
+	/// the preview the last time it was painted.
+	counting: RefCell<bool>,
+	/// Whether the signer is being asked to confirm the signature on the
+	/// signing screen of the device.
+	confirming: RefCell<bool>,
 	/// The queue though which we receive device updates.
-	queue: RefCell<Queue>,
+	queue: RefCell<FilteredQueue<Pipeline>>,
 
 	/// The path accumulated from the events generated by the tablet.
 	path: RefCell<EventPath>,
@@ -138,24 +356,52 @@ pub struct ManagementWindow {
 impl ManagementWindow {
 	fn new(
 		device: Tablet,
-		queue: Queue,
+		queue: FilteredQueue<Pipeline>,
 		caps: Capability,
+		transform: Transform,
+		settings: Settings,
+		pad: Option<PadScreen>,
 		fails: std::sync::mpsc::Sender<ManagementError>) -> Self {
 
+		let (width, height) = transform.dimensions(caps.width(), caps.height());
+		let canvas = EventCanvas::with_style(width, height, settings.canvas)
+			.with_pressure_curve(settings.device(&device.info()).pressure);
+		let completion = settings.completion.as_ref().map(|completion| CompletionDetector::new(
+			Duration::from_millis(completion.pen_up),
+			Duration::from_millis(completion.out_of_proximity)));
+
 		Self {
 			icon: Default::default(),
 			window: Default::default(),
 			display: Default::default(),
 			display_label: Default::default(),
 			display_clear_btn: Default::default(),
+			display_undo_btn: Default::default(),
+			display_open_btn: Default::default(),
+			open_dialog: Default::default(),
+			display_save_btn: Default::default(),
+			document_dialog: Default::default(),
 			display_paint_btn: Default::default(),
+			pressure_select: Default::default(),
+			orientation_select: Default::default(),
+			customer_input: Default::default(),
+			display_enroll_btn: Default::default(),
 			help_btn: Default::default(),
 			update: Default::default(),
 			locked: RefCell::new(false),
 			device,
+			settings: RefCell::new(settings),
+			enrollments: Default::default(),
+			caps,
+			transform: RefCell::new(transform),
+			pad: RefCell::new(pad),
+			pen_down: RefCell::new(false),
+			completion: RefCell::new(completion),
+			counting: RefCell::new(false),
+			confirming: RefCell::new(false),
 			queue: RefCell::new(queue),
 			path: Default::default(),
-			canvas: RefCell::new(EventCanvas::new(caps.width(), caps.height())),
+			canvas: RefCell::new(canvas),
 			display_paint_done: Default::default(),
 			fails
 		}
@@ -165,15 +411,32 @@ impl ManagementWindow {
 	fn lock(&self) {
 		mng_cmd_try!(self, self.device.inking(false));
 		self.display_clear_btn.set_enabled(false);
+		self.display_undo_btn.set_enabled(false);
+		self.display_open_btn.set_enabled(false);
+		self.display_save_btn.set_enabled(false);
 		self.display_paint_btn.set_enabled(false);
+		self.display_enroll_btn.set_enabled(false);
+		self.orientation_select.set_enabled(false);
+		self.customer_input.set_enabled(false);
 		*self.locked.borrow_mut() = true;
+
+		/* Whatever the signature was counting down to is being done now. */
+		if let Some(detector) = self.completion.borrow_mut().as_mut() {
+			detector.reset();
+		}
 	}
 
 	/// Unlocks all of the controls in this window.
 	fn unlock(&self) {
 		mng_cmd_try!(self, self.device.inking(true));
 		self.display_clear_btn.set_enabled(true);
+		self.display_undo_btn.set_enabled(true);
+		self.display_open_btn.set_enabled(true);
+		self.display_save_btn.set_enabled(true);
 		self.display_paint_btn.set_enabled(true);
+		self.display_enroll_btn.set_enabled(true);
+		self.orientation_select.set_enabled(true);
+		self.customer_input.set_enabled(true);
 		*self.locked.borrow_mut() = false;
 	}
 
@@ -183,16 +446,105 @@ impl ManagementWindow {
 		nwg::stop_thread_dispatch();
 	}
 
+	/// Clears the screen of the device, displaying the signing screen on it if
+	/// we have one.
+	fn reset_screen(&self) -> Result<(), stu::Error> {
+		match &*self.pad.borrow() {
+			Some(pad) => pad.display(&self.device, None, None),
+			None => self.device.clear()
+		}
+	}
+
+	/// Redraws the screen of the device, with the ink of our path drawn over
+	/// the signing screen, if we have one.
+	fn redraw_screen(&self) -> Result<(), stu::Error> {
+		/* The preview may be antialiased, which the device can't display, so
+		 * render the ink again in monochrome. */
+		let (width, height) = {
+			let preview = self.canvas.borrow();
+			(preview.width(), preview.height())
+		};
+		let mut canvas = EventCanvas::new(width, height)
+			.with_pressure_curve(self.pressure_curve());
+		for event in self.path.borrow().events() {
+			canvas.process(*event);
+		}
+
+		match &*self.pad.borrow() {
+			Some(pad) => {
+				let highlight = if *self.confirming.borrow() {
+					Some(PadButton::Ok)
+				} else {
+					None
+				};
+				pad.display(&self.device, Some(&canvas), highlight)
+			},
+			None => crate::screen::display_ink(&self.device, *self.transform.borrow(), &canvas)
+		}
+	}
+
 	/// Populates the data in the window controls.
 	fn init(&self) {
-		mng_cmd_try!(self, self.device.clear());
+		mng_cmd_try!(self, self.reset_screen());
 		mng_cmd_try!(self, self.device.inking(true));
 
 		self.window.set_text(&crate::strings::manager::title());
 		self.help_btn.set_text(&crate::strings::manager::help_btn());
 		self.display_paint_btn.set_text(&crate::strings::manager::display_paint_btn());
 		self.display_clear_btn.set_text(&crate::strings::manager::display_clear_btn());
+		self.display_undo_btn.set_text(crate::strings::manager::display_undo_btn());
+		self.display_open_btn.set_text(crate::strings::manager::display_open_btn());
+		self.display_save_btn.set_text(crate::strings::manager::display_save_btn());
 		self.display_label.set_text(&crate::strings::manager::display_label());
+		self.display_enroll_btn.set_text(crate::strings::manager::display_enroll_btn());
+		self.customer_input.set_placeholder_text(Some(crate::strings::manager::customer_placeholder()));
+
+		self.document_dialog.set_title(crate::strings::manager::document_dialog_title());
+
+		self.open_dialog.set_title(crate::strings::manager::open_dialog_title());
+		let filters = [
+			ExportFormat::InkMl,
+			ExportFormat::Iso19794,
+			ExportFormat::Csv,
+			ExportFormat::JsonLines,
+			ExportFormat::Container,
+			ExportFormat::Package]
+			.iter()
+			.map(|format| format!("{}(*.{})", format.name(), format.extension()))
+			.chain(std::iter::once(format!(
+				"{}(*.{})",
+				crate::strings::manager::encrypted_filter(),
+				ENCRYPTED_EXTENSION)))
+			.collect::<Vec<_>>()
+			.join("|");
+		let _ = self.open_dialog.set_filters(&filters);
+
+		let curve = self.settings.borrow().device(&self.device.info()).pressure;
+		for preset in PressurePreset::ALL.iter().copied() {
+			self.pressure_select
+				.collection_mut()
+				.push(PresetDisplay(Some(preset)));
+		}
+		self.pressure_select.sync();
+		self.pressure_select.set_selection(PressurePreset::ALL
+			.iter()
+			.position(|preset| preset.curve() == curve));
+
+		let transform = *self.transform.borrow();
+		for orientation in orientations() {
+			self.orientation_select
+				.collection_mut()
+				.push(OrientationDisplay(Some(orientation)));
+		}
+		self.orientation_select.sync();
+		self.orientation_select.set_selection(orientations()
+			.into_iter()
+			.position(|orientation| same_orientation(orientation, transform)));
+
+		/* Build the enrollment of the shared references up front, so that
+		 * their files are read, and any passphrase asked for, only once.
+		 * Failing to build it is only reported when it is needed. */
+		let _ = self.enrollment(None);
 
 		self.update(true);
 		self.update.start();
@@ -207,13 +559,578 @@ impl ManagementWindow {
 
 		self.canvas.borrow_mut().clear();
 		self.path.borrow_mut().clear();
+		self.queue.borrow_mut().filter().reset();
+		if let Some(detector) = self.completion.borrow_mut().as_mut() {
+			detector.reset();
+		}
+		*self.confirming.borrow_mut() = false;
+
+		mng_cmd_try!(self, self.reset_screen());
+		mng_cmd_try!(self, self.device.inking(true));
+
+		self.update(true);
+	}
+
+	/// Called when an intent for undoing the last stroke has been fired.
+	fn on_undo_pressed(&self) {
+		{
+			let mut path = self.path.borrow_mut();
+			let strokes = path.strokes().len();
+			if strokes == 0 { return }
+			path.remove_stroke(strokes - 1);
+		}
+
+		self.repaint_path();
+	}
+
+	/// Called when an intent for opening a signature from a file has been fired.
+	fn on_open_pressed(&self) {
+		if !self.open_dialog.run(Some(&self.window)) { return }
+		let path = match self.open_dialog.get_selected_item() {
+			Ok(path) => PathBuf::from(path),
+			Err(_) => return
+		};
+
+		match self.read_signature(&path) {
+			Ok((path, package)) => {
+				/* Packages not signed with the trusted key only show they were
+				 * not modified by accident, which the user is warned about. */
+				if let Some(package) = package {
+					let (content, icons) = if package.trusted {
+						(crate::strings::manager::package_verified(&package), nwg::MessageIcons::Info)
+					} else {
+						(crate::strings::manager::package_untrusted(&package), nwg::MessageIcons::Warning)
+					};
+					nwg::modal_message(&self.window, &nwg::MessageParams {
+						title: crate::strings::manager::package_title(),
+						content: &content,
+						buttons: nwg::MessageButtons::Ok,
+						icons,
+					});
+				}
 
-		mng_cmd_try!(self, self.device.clear());
+				*self.path.borrow_mut() = path;
+				self.report_anomalies();
+				self.repaint_path();
+			},
+			Err(ImportError::Cancelled) => {},
+			Err(what) => {
+				nwg::modal_error_message(
+					&self.window,
+					crate::strings::errors::title(),
+					&crate::strings::errors::import_failed(what));
+			}
+		}
+	}
+
+	/// Tells the user about the anomalous events in the signature, if there are
+	/// any.
+	fn report_anomalies(&self) {
+		let anomalies = self.path.borrow().anomalies();
+		if anomalies.is_empty() { return }
+
+		nwg::modal_info_message(
+			&self.window,
+			crate::strings::manager::anomalies_title(),
+			&crate::strings::manager::anomalies_found(&anomalies));
+	}
+
+	/// Reads a signature from the file at the given path, decrypting it if it
+	/// is encrypted, along with the package it came in, if it came in one.
+	fn read_signature(&self, path: &Path) -> Result<(EventPath, Option<VerifiedPackage>), ImportError> {
+		/* Formats are told apart by the extension of the file, with anything
+		 * else being taken to be an InkML document. Encrypted files are told
+		 * apart by the extension they had before being encrypted. */
+		let name = if path.extension() == Some(ENCRYPTED_EXTENSION.as_ref()) {
+			path.with_extension("")
+		} else {
+			path.to_path_buf()
+		};
+		let format = name.extension()
+			.and_then(|extension| extension.to_str())
+			.and_then(ExportFormat::from_extension);
+
+		let data = std::fs::read(path).map_err(ImportError::Io)?;
+		let data = match EncryptionMethod::of(&data) {
+			Some(method) => self.decryption_key(method)
+				.and_then(|key| crate::export::decrypt(&data, &key)
+					.map_err(ImportError::Encryption))?,
+			None => data
+		};
+
+		let path = match format {
+			Some(ExportFormat::Iso19794) => crate::export::decode_iso19794(&data)
+				.map_err(ImportError::Iso),
+			Some(ExportFormat::Csv) => crate::export::decode_csv(&String::from_utf8_lossy(&data))
+				.map_err(ImportError::Samples),
+			Some(ExportFormat::JsonLines) => crate::export::decode_json_lines(&String::from_utf8_lossy(&data))
+				.map_err(ImportError::Samples),
+			Some(ExportFormat::Container) => crate::export::read_container(&data[..])
+				.map(|(_, path)| path)
+				.map_err(ImportError::Container),
+			Some(ExportFormat::Package) => {
+				let trusted = self.settings.borrow().trusted_key.clone();
+				let trusted = trusted
+					.map(|key| std::fs::read(&key)
+						.map_err(ImportError::Io)
+						.and_then(|key| crate::export::load_verifying_key(&key)
+							.map_err(ImportError::Package)))
+					.transpose()?;
+
+				let package = crate::export::verify_package(&data, trusted.as_ref())
+					.map_err(ImportError::Package)?;
+				let path = package.package.path().map_err(ImportError::Container)?;
+
+				return Ok((path, Some(package)))
+			},
+			_ => crate::export::decode_inkml(&String::from_utf8_lossy(&data))
+				.map_err(ImportError::InkMl)
+		}?;
+
+		Ok((path, None))
+	}
+
+	/// Renders the preview and the screen of the device again from the events
+	/// in our path, after the path was changed as a whole.
+	fn repaint_path(&self) {
+		{
+			let path = self.path.borrow();
+			let mut canvas = self.canvas.borrow_mut();
+			canvas.clear();
+			for event in path.events() {
+				canvas.process(*event);
+			}
+		}
+
+		mng_cmd_try!(self, self.device.inking(false));
+		mng_cmd_try!(self, self.redraw_screen());
 		mng_cmd_try!(self, self.device.inking(true));
 
 		self.update(true);
 	}
 
+	/// Called when an intent for saving the signature to a file has been fired.
+	fn on_save_pressed(&self) {
+		if !self.validate_signature() { return }
+		self.report_anomalies();
+
+		let formats = ExportFormat::all();
+		let filters = formats.iter()
+			.map(|format| (format.name(), format.extension()))
+			.collect::<Vec<_>>();
+		let (path, filter) = match crate::window::pick_save_file(
+			&self.window,
+			crate::strings::manager::save_dialog_title(),
+			&filters) {
+
+			Some(picked) => picked,
+			None => return
+		};
+
+		self.save_signature(path, formats.get(filter).copied());
+	}
+
+	/// Called when an intent for enrolling the signature as a reference of the
+	/// customer has been fired.
+	fn on_enroll_pressed(&self) {
+		let customer = match self.customer() {
+			Some(customer) => customer,
+			None => {
+				nwg::modal_info_message(
+					&self.window,
+					crate::strings::manager::enroll_title(),
+					crate::strings::manager::enroll_no_customer());
+				return
+			}
+		};
+		if !self.validate_signature() { return }
+
+		/* References are kept in the container format, which holds on to
+		 * everything they are compared by, named after the customer and the
+		 * time they were enrolled at. */
+		let folder = self.settings.borrow().verification.folder();
+		if let Err(what) = std::fs::create_dir_all(&folder) {
+			nwg::modal_error_message(
+				&self.window,
+				crate::strings::errors::title(),
+				&crate::strings::errors::export_failed(ExportError::Io(what)));
+			return
+		}
+		let name = customer
+			.chars()
+			.map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+			.collect::<String>();
+		let millis = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|time| time.as_millis())
+			.unwrap_or(0);
+		let path = folder.join(format!(
+			"{}-{}.{}",
+			name,
+			millis,
+			ExportFormat::Container.extension()));
+		let path = match self.save_signature(path, None) {
+			Some(path) => path,
+			None => return
+		};
+
+		let count = {
+			let mut settings = self.settings.borrow_mut();
+			let references = settings.verification.customers
+				.entry(customer.clone())
+				.or_default();
+			references.push(path);
+			let count = references.len();
+
+			if let Err(what) = settings.store() {
+				nwg::modal_error_message(
+					&self.window,
+					crate::strings::errors::title(),
+					&crate::strings::errors::settings_store_failed(what));
+			}
+			count
+		};
+
+		/* The enrollment of the customer gets built again, with the new
+		 * reference, the next time one of their signatures is verified. */
+		self.enrollments.borrow_mut().remove(&Some(customer.clone()));
+
+		nwg::modal_info_message(
+			&self.window,
+			crate::strings::manager::enroll_title(),
+			&crate::strings::manager::enrolled(&customer, count));
+		self.on_clear_pressed();
+	}
+
+	/// The identification of the customer typed in the window, if any.
+	fn customer(&self) -> Option<String> {
+		let customer = self.customer_input.text();
+		let customer = customer.trim();
+		if customer.is_empty() {
+			None
+		} else {
+			Some(customer.to_owned())
+		}
+	}
+
+	/// Saves the signature to the file at the given path, in the format given
+	/// by its extension, telling the user when that fails.
+	///
+	/// Files named without the extension of a format we know are saved in the
+	/// given format, if any, or in the one in the settings otherwise, with its
+	/// extension added to their names. Returns the path the file was saved
+	/// to, if it was.
+	fn save_signature(&self, path: PathBuf, fallback: Option<ExportFormat>) -> Option<PathBuf> {
+		let export = self.settings.borrow().export;
+		let format = path.extension()
+			.and_then(|extension| extension.to_str())
+			.and_then(ExportFormat::from_extension);
+		let (path, format) = match format {
+			Some(format) => (path, format),
+			None => {
+				let format = fallback.unwrap_or(ExportFormat::Image(export.format));
+				let mut path = path.into_os_string();
+				path.push(".");
+				path.push(format.extension());
+
+				(PathBuf::from(path), format)
+			}
+		};
+
+		/* Encrypted files keep the extension of their format, with ours added
+		 * after it, and the key is asked for before anything else is done. */
+		let encryption = self.settings.borrow().encryption.clone();
+		let key = match encryption.map(|encryption| self.encryption_key(&encryption)).transpose() {
+			Err(ExportError::Cancelled) => return None,
+			key => key
+		};
+		let path = match key {
+			Ok(Some(_)) => {
+				let mut path = path.into_os_string();
+				path.push(".");
+				path.push(ENCRYPTED_EXTENSION);
+
+				PathBuf::from(path)
+			},
+			_ => path
+		};
+
+		let screen = {
+			let preview = self.canvas.borrow();
+			(preview.width(), preview.height())
+		};
+		let curve = self.pressure_curve();
+		let data = match format {
+			ExportFormat::Image(format) => {
+				let canvas = self.path.borrow().render(screen, &export.render, &curve);
+				crate::export::encode_image(
+					&canvas,
+					format,
+					export.render.dpi,
+					export.transparent)
+			},
+			ExportFormat::InkMl => Ok(crate::export::encode_inkml(
+				&self.path.borrow(),
+				&self.caps,
+				*self.transform.borrow()).into_bytes()),
+			ExportFormat::Iso19794 => crate::export::encode_iso19794(
+				&self.path.borrow(),
+				&self.caps,
+				*self.transform.borrow(),
+				export.iso)
+				.map_err(ExportError::Iso),
+			ExportFormat::Csv => Ok(crate::export::encode_csv(
+				&self.path.borrow(),
+				Some(DeviceUnits::new(&self.caps, *self.transform.borrow())),
+				&export.samples).into_bytes()),
+			ExportFormat::JsonLines => Ok(crate::export::encode_json_lines(
+				&self.path.borrow(),
+				Some(DeviceUnits::new(&self.caps, *self.transform.borrow())),
+				&export.samples).into_bytes()),
+			ExportFormat::Container => {
+				let header = ContainerHeader::new(&self.device.info(), &self.caps, *self.transform.borrow());
+				let mut data = Vec::new();
+
+				crate::export::write_container(&mut data, &header, &self.path.borrow())
+					.map(|_| data)
+					.map_err(ExportError::Io)
+			},
+			ExportFormat::Package => {
+				/* Packages are bound to the document being signed, which
+				 * gets picked before anything else is done. */
+				if !self.document_dialog.run(Some(&self.window)) { return None }
+				let document = match self.document_dialog.get_selected_item() {
+					Ok(document) => PathBuf::from(document),
+					Err(_) => return None
+				};
+				let key = self.settings.borrow().signing_key.clone();
+
+				let path = self.path.borrow();
+				let canvas = path.render(screen, &export.render, &curve);
+				let image = crate::export::encode_image(
+					&canvas,
+					ImageFormat::Png,
+					export.render.dpi,
+					export.transparent);
+				let document = std::fs::read(&document)
+					.map(|document| DocumentHash::sha256(&document))
+					.map_err(ExportError::Io);
+				let key = key
+					.map(|key| std::fs::read(&key)
+						.map_err(ExportError::Io)
+						.and_then(|key| crate::export::load_signing_key(&key)
+							.map_err(ExportError::Package)))
+					.transpose();
+
+				image.and_then(|image| document.and_then(|document| key.map(|key| {
+					let info = self.device.info();
+					let header = ContainerHeader::new(&info, &self.caps, *self.transform.borrow());
+					let metadata = CaptureMetadata::new(&info, &path, document);
+
+					SignaturePackage::new(metadata, &header, &path, image).seal(key.as_ref())
+				})))
+			},
+			ExportFormat::Features => {
+				let features = SignatureFeatures::new(&self.path.borrow(), self.physical_size());
+
+				Ok(serde_json::to_vec_pretty(&features).expect("features are always valid JSON"))
+			},
+			ExportFormat::Svg => Ok(crate::export::encode_svg(
+				&self.path.borrow(),
+				self.physical_size(),
+				screen,
+				&export.render.style,
+				&curve,
+				&export.svg,
+				export.transparent).into_bytes()),
+		};
+		let result = data
+			.and_then(|data| match key? {
+				Some(key) => crate::export::encrypt(&data, &key).map_err(ExportError::Encryption),
+				None => Ok(data)
+			})
+			.and_then(|data| std::fs::write(&path, data).map_err(ExportError::Io));
+
+		match result {
+			Ok(()) => Some(path),
+			Err(what) => {
+				nwg::modal_error_message(
+					&self.window,
+					crate::strings::errors::title(),
+					&crate::strings::errors::export_failed(what));
+				None
+			}
+		}
+	}
+
+	/// The curve the pressure of the pen is shaped by when the ink is drawn,
+	/// as set for the device.
+	fn pressure_curve(&self) -> PressureCurve {
+		self.settings.borrow().device(&self.device.info()).pressure
+	}
+
+	/// The physical dimensions of the screen of the device, in millimeters, as
+	/// seen in the view.
+	fn physical_size(&self) -> (f64, f64) {
+		let size = (self.caps.physical_width(), self.caps.physical_height());
+		if self.transform.borrow().rotation().is_sideways() {
+			(size.1, size.0)
+		} else {
+			size
+		}
+	}
+
+	/// Asks the user for the passphrase protecting signature files, keeping the
+	/// management window disabled while the prompt is open. Returns `None` if
+	/// the user cancels the prompt.
+	fn prompt_passphrase(&self, confirm: bool) -> Option<String> {
+		self.window.set_enabled(false);
+		let result = crate::window::prompt_passphrase(confirm);
+		self.window.set_enabled(true);
+		self.window.set_focus();
+
+		match result {
+			Ok(passphrase) => passphrase,
+			Err(what) => {
+				nwg::modal_error_message(
+					&self.window,
+					crate::strings::errors::title(),
+					&crate::strings::errors::passphrase_prompt_failed(what));
+				None
+			}
+		}
+	}
+
+	/// The key signature files get encrypted with, as set up in the settings.
+	fn encryption_key(&self, encryption: &Encryption) -> Result<EncryptionKey, ExportError> {
+		let read = |path: &PathBuf| std::fs::read(path).map_err(ExportError::Io);
+		let load = |path: &PathBuf| read(path)
+			.and_then(|key| crate::export::load_key(&key).map_err(ExportError::Encryption));
+
+		match encryption {
+			Encryption::Passphrase => self.prompt_passphrase(true)
+				.map(EncryptionKey::Passphrase)
+				.ok_or(ExportError::Cancelled),
+			Encryption::Key { key } => load(key).map(EncryptionKey::Key),
+			Encryption::Recipient { public_key, .. } => load(public_key)
+				.map(|key| EncryptionKey::Recipient(key.into())),
+			Encryption::RsaRecipient { public_key, .. } => read(public_key)
+				.and_then(|key| crate::export::load_rsa_public_key(&key).map_err(ExportError::Encryption))
+				.map(EncryptionKey::RsaRecipient),
+		}
+	}
+
+	/// The key signature files encrypted with the given method get decrypted
+	/// with, as set up in the settings.
+	fn decryption_key(&self, method: EncryptionMethod) -> Result<DecryptionKey, ImportError> {
+		let read = |path: &PathBuf| std::fs::read(path).map_err(ImportError::Io);
+		let load = |path: &PathBuf| read(path)
+			.and_then(|key| crate::export::load_key(&key).map_err(ImportError::Encryption));
+
+		let encryption = self.settings.borrow().encryption.clone();
+		match (method, encryption) {
+			(EncryptionMethod::Passphrase, _) => self.prompt_passphrase(false)
+				.map(DecryptionKey::Passphrase)
+				.ok_or(ImportError::Cancelled),
+			(EncryptionMethod::Key, Some(Encryption::Key { key })) => load(&key)
+				.map(DecryptionKey::Key),
+			(EncryptionMethod::Recipient, Some(Encryption::Recipient { private_key: Some(key), .. })) => load(&key)
+				.map(|key| DecryptionKey::Recipient(key.into())),
+			(EncryptionMethod::RsaRecipient, Some(Encryption::RsaRecipient { private_key: Some(key), .. })) => read(&key)
+				.and_then(|key| crate::export::load_rsa_private_key(&key).map_err(ImportError::Encryption))
+				.map(|key| DecryptionKey::RsaRecipient(Box::new(key))),
+			(method, _) => Err(ImportError::MissingKey(method))
+		}
+	}
+
+	/// Called when an orientation for the device has been picked.
+	fn on_orientation_selected(&self) {
+		let transform = match self.orientation_select.selection() {
+			Some(index) => orientations()[index],
+			None => return
+		};
+		if same_orientation(transform, *self.transform.borrow()) { return }
+
+		let info = self.device.info();
+		let (device, style, idle_screen) = {
+			let mut settings = self.settings.borrow_mut();
+			let mut device = settings.device(&info);
+			device.transform = transform;
+			settings.set_device(&info, device.clone());
+
+			if let Err(what) = settings.store() {
+				nwg::modal_error_message(
+					&self.window,
+					crate::strings::errors::title(),
+					&crate::strings::errors::settings_store_failed(what));
+			}
+			(device, settings.canvas, settings.idle_screen.clone())
+		};
+
+		/* Everything drawn in the coordinates of the view gets built again for
+		 * the new orientation. */
+		*self.transform.borrow_mut() = transform;
+		*self.queue.borrow_mut().filter() = event_pipeline(&device);
+
+		let (width, height) = transform.dimensions(self.caps.width(), self.caps.height());
+		*self.canvas.borrow_mut() = EventCanvas::with_style(width, height, style)
+			.with_pressure_curve(device.pressure);
+
+		let layout = self.pad.borrow().as_ref().map(|pad| *pad.layout());
+		if let Some(layout) = layout {
+			/* Fall back to controlling the signature from the window alone,
+			 * just like when the device was first opened. */
+			*self.pad.borrow_mut() = match PadScreen::new(&self.device, layout, transform) {
+				Ok(pad) => Some(pad),
+				Err(what) => {
+					nwg::modal_error_message(
+						&self.window,
+						crate::strings::errors::title(),
+						&crate::strings::errors::pad_screen_failed(what));
+					None
+				}
+			};
+		}
+		if let Some(path) = &idle_screen {
+			if let Err(what) = crate::screen::push_idle_screen(&self.device, path, transform) {
+				nwg::modal_error_message(
+					&self.window,
+					crate::strings::errors::title(),
+					&crate::strings::errors::idle_screen_failed(what));
+			}
+		}
+
+		/* The signature was written in the old orientation, so it makes no
+		 * sense in the new one. */
+		self.on_clear_pressed();
+	}
+
+	/// Called when a pressure response preset has been picked.
+	fn on_pressure_selected(&self) {
+		let preset = match self.pressure_select.selection() {
+			Some(index) => PressurePreset::ALL[index],
+			None => return
+		};
+		let info = self.device.info();
+		let mut settings = self.settings.borrow_mut();
+		let mut device = settings.device(&info);
+		device.pressure = preset.curve();
+		settings.set_device(&info, device);
+
+		if let Err(what) = settings.store() {
+			nwg::modal_error_message(
+				&self.window,
+				crate::strings::errors::title(),
+				&crate::strings::errors::settings_store_failed(what));
+		}
+		drop(settings);
+
+		/* The path keeps the pressure as it was captured, so the ink drawn so
+		 * far can be drawn again with the new curve. */
+		self.canvas.borrow_mut().set_pressure_curve(preset.curve());
+		self.repaint_path();
+	}
+
 	/// Called when an intent for opening the help dialog has been fired.
 	fn on_help_pressed(&self) {
 		nwg::modal_info_message(
@@ -224,9 +1141,22 @@ impl ManagementWindow {
 
 	/// Called when an intent for painting the device data has been fired.
 	fn on_paint_pressed(&self) {
+		if !self.validate_signature() || !self.verify_signature() { return }
+		self.report_anomalies();
 		self.lock();
 
-		let path = self.path.borrow().clone();
+		let mut path = self.path.borrow().clone();
+		let (interpolation, parameterization, gaps) = {
+			let settings = self.settings.borrow();
+			if let Some(simplification) = settings.simplification {
+				path.simplify(
+					simplification.method,
+					stu::Tolerance::device(simplification.tolerance, &self.caps, *self.transform.borrow()));
+			}
+
+			(settings.interpolation, settings.parameterization, settings.gaps)
+		};
+		let curve = self.pressure_curve();
 		let sender = self.display_paint_done.sender();
 
 		let canvas = self.canvas.borrow();
@@ -254,6 +1184,10 @@ impl ManagementWindow {
 
 			Playback {
 				path,
+				interpolation,
+				parameterization,
+				gaps,
+				curve,
 				target: area,
 				delta: Duration::from_secs(8),
 				steps: unsafe { NonZeroU32::new_unchecked(5000) }
@@ -261,26 +1195,217 @@ impl ManagementWindow {
 		});
 	}
 
+	/// Checks the signature against the rules for the quality of signatures in
+	/// the settings, telling the user why it was rejected, if it was. Returns
+	/// whether it was accepted.
+	fn validate_signature(&self) -> bool {
+		let rules = self.settings.borrow().validation;
+		match rules.validate(&self.path.borrow()) {
+			Ok(()) => true,
+			Err(rejections) => {
+				nwg::modal_message(&self.window, &nwg::MessageParams {
+					title: crate::strings::manager::validation_title(),
+					content: &crate::strings::manager::validation_rejected(&rejections),
+					buttons: nwg::MessageButtons::Ok,
+					icons: nwg::MessageIcons::Warning,
+				});
+				false
+			}
+		}
+	}
+
+	/// The enrollment signatures of the given customer, or of no one in
+	/// particular, are verified against, if they have any references. It is
+	/// built from the files in the settings the first time it is needed, and
+	/// kept for as long as its references stay the same.
+	///
+	/// Fails with the message to be shown to the user when the references
+	/// can't be read or enrolled.
+	fn enrollment(&self, customer: Option<&str>) -> Result<Option<Enrollment>, String> {
+		let key = customer.map(str::to_owned);
+		if let Some(enrollment) = self.enrollments.borrow().get(&key) {
+			return Ok(Some(enrollment.clone()))
+		}
+
+		let (references, options) = {
+			let settings = self.settings.borrow();
+			let verification = &settings.verification;
+			(verification.references(customer).to_vec(), verification.options)
+		};
+		if references.is_empty() { return Ok(None) }
+
+		let references = references
+			.iter()
+			.map(|path| self.read_signature(path).map(|(path, _)| path))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(crate::strings::errors::reference_load_failed)?;
+		let enrollment = Enrollment::new(references.iter(), options)
+			.map_err(crate::strings::errors::verification_failed)?;
+
+		self.enrollments.borrow_mut().insert(key, enrollment.clone());
+		Ok(Some(enrollment))
+	}
+
+	/// Verifies the signature against the references enrolled for the customer
+	/// typed in the window, or against the shared ones when there is none, and
+	/// asks the user whether to go on when it doesn't match them, or when it
+	/// can't be verified. Returns whether to go on.
+	fn verify_signature(&self) -> bool {
+		let customer = self.customer();
+		let message = match self.enrollment(customer.as_deref()) {
+			Ok(None) => return true,
+			Ok(Some(enrollment)) => match enrollment.verify(&self.path.borrow()) {
+				Ok(verification) if verification.accepted => return true,
+				Ok(verification) => crate::strings::manager::verification_rejected(verification),
+				Err(what) => crate::strings::errors::verification_failed(what),
+			},
+			Err(message) => message
+		};
+
+		let choice = nwg::modal_message(&self.window, &nwg::MessageParams {
+			title: crate::strings::manager::verification_title(),
+			content: &message,
+			buttons: nwg::MessageButtons::YesNo,
+			icons: nwg::MessageIcons::Warning,
+		});
+		choice == nwg::MessageChoice::Yes
+	}
+
 	/// Called when the painting of the signature has been completed.
 	fn on_paint_done(&self) {
 		self.unlock();
 	}
 
+	/// Called when a button on the signing screen of the device was pressed.
+	fn on_pad_button_pressed(&self, button: PadButton) {
+		match button {
+			PadButton::Ok => self.on_paint_pressed(),
+			PadButton::Clear => self.on_clear_pressed(),
+			PadButton::Cancel => self.on_pad_cancel_pressed(),
+		}
+	}
+
+	/// Called when the signer gave up on the signature from the signing screen
+	/// of the device, which discards it and lets the operator know about it.
+	fn on_pad_cancel_pressed(&self) {
+		self.on_clear_pressed();
+		nwg::modal_info_message(
+			&self.window,
+			crate::strings::manager::cancelled_title(),
+			crate::strings::manager::cancelled());
+	}
+
 	/// Pulls in events from the device and repaints the screen.
 	fn update(&self, force_repaint: bool) {
+		let pressed = self.process_events(force_repaint);
+
+		/* Stop asking for confirmation as soon as the signer touches the
+		 * screen again, be it to go on signing or to press a button. */
+		let resumed = *self.confirming.borrow() && *self.pen_down.borrow();
+		if resumed {
+			*self.confirming.borrow_mut() = false;
+			mng_cmd_try!(self, self.redraw_screen());
+		}
+
+		/* Only act on the buttons after we're done with the events, as their
+		 * actions need access to the state we've been working on. */
+		if let Some(button) = pressed {
+			self.on_pad_button_pressed(button);
+		}
+
+		let complete = self.completion
+			.borrow_mut()
+			.as_mut()
+			.and_then(|detector| detector.poll(Instant::now()));
+		if let Some(complete) = complete {
+			self.on_signature_complete(complete);
+		}
+	}
+
+	/// Called when the end of the signature has been detected.
+	fn on_signature_complete(&self, complete: SignatureComplete) {
+		let action = match &self.settings.borrow().completion {
+			Some(completion) => completion.action.clone(),
+			None => return
+		};
+
+		match action {
+			CompletionAction::Paint => self.on_paint_pressed(),
+			CompletionAction::Save { folder } => {
+				if !self.validate_signature() { return }
+
+				/* Name the file after the time the pen was last lifted. */
+				let now = SystemTime::now();
+				let signed = now
+					.checked_sub(Instant::now().saturating_duration_since(complete.lifted))
+					.unwrap_or(now);
+				let millis = signed
+					.duration_since(UNIX_EPOCH)
+					.map(|time| time.as_millis())
+					.unwrap_or(0);
+
+				self.save_signature(folder.join(format!("signature-{}", millis)), None);
+			},
+			CompletionAction::Confirm => match &*self.pad.borrow() {
+				Some(_) => {
+					*self.confirming.borrow_mut() = true;
+					mng_cmd_try!(self, self.redraw_screen());
+				},
+				None => {
+					let choice = nwg::modal_message(&self.window, &nwg::MessageParams {
+						title: crate::strings::manager::completion_title(),
+						content: crate::strings::manager::completion_confirm(),
+						buttons: nwg::MessageButtons::YesNo,
+						icons: nwg::MessageIcons::Question,
+					});
+					if choice == nwg::MessageChoice::Yes {
+						self.on_paint_pressed();
+					}
+				}
+			}
+		}
+	}
+
+	/// Pulls in events from the device and repaints the screen, returning the
+	/// button on the signing screen that was pressed, if any.
+	fn process_events(&self, force_repaint: bool) -> Option<PadButton> {
 		/* Process the input events. */
 		let mut queue = self.queue.borrow_mut();
 		let mut canvas = self.canvas.borrow_mut();
 		let mut path = self.path.borrow_mut();
+		let mut pen_down = self.pen_down.borrow_mut();
+		let mut completion = self.completion.borrow_mut();
 
 		let mut dirty = false;
+		let mut pressed = None;
 		let locked = self.locked.borrow();
 		loop {
 			match queue.try_recv() {
 				Ok(event) => {
+					let pen_pressed = event.touching() && !*pen_down;
+					*pen_down = event.touching();
+
 					if !*locked {
+						let event = match &*self.pad.borrow() {
+							Some(pad) => {
+								if pen_pressed {
+									pressed = pressed.or(pad.layout()
+										.button_at(event.x(), event.y()));
+								}
+								pad.layout().clip(event)
+							},
+							None => event
+						};
+
 						canvas.process(event);
-						path.process(event);
+						/* Anomalous events are kept all the same, as there
+						 * is nothing the signer could do about them, and the
+						 * user is told about them before the signature gets
+						 * painted or saved. */
+						let _ = path.process(event);
+						if let Some(detector) = completion.as_mut() {
+							detector.process(&event);
+						}
 
 						dirty = true;
 					}
@@ -291,14 +1416,26 @@ impl ManagementWindow {
 				Err(stu::TryRecvError::Failed(what)) => {
 					/* The polling process has failed. */
 					self.fail(ManagementError::DevicePollingFailed(what));
-					return
+					return None
 				}
 			}
 		}
 
+		/* Keep repainting the preview for as long as the countdown to the
+		 * completion of the signature is running, and once more after it
+		 * stops, to take it away. */
+		let progress = completion
+			.as_ref()
+			.and_then(|detector| detector.progress(Instant::now()));
+		let counting = progress.is_some() || *self.counting.borrow();
+		*self.counting.borrow_mut() = progress.is_some();
+
 		/* Update the display after the changes made by the events. */
-		if dirty || force_repaint {
-			let blob = canvas.to_bitmap();
+		if dirty || counting || force_repaint {
+			let blob = match progress {
+				Some(progress) => canvas.to_bitmap_with_countdown(1.0 - progress),
+				None => canvas.to_bitmap()
+			};
 			let bitmap = nwg::Bitmap::from_bin(&blob[..]).unwrap();
 
 			self.display.set_size(canvas.width(), canvas.height());
@@ -310,21 +1447,44 @@ impl ManagementWindow {
 			let (_, lbl_height) = self.display_label.size();
 
 			self.display_label.set_size(
-				canvas.width().saturating_sub(80),
+				canvas.width().saturating_sub(640),
 				lbl_height);
+			self.customer_input.set_position(
+				canvas.width().saturating_sub(620) as i32,
+				7);
+			self.display_enroll_btn.set_position(
+				canvas.width().saturating_sub(470) as i32,
+				7);
+			self.orientation_select.set_position(
+				canvas.width().saturating_sub(380) as i32,
+				7);
+			self.pressure_select.set_position(
+				canvas.width().saturating_sub(230) as i32,
+				7);
 			self.help_btn.set_position(
 				canvas.width().saturating_sub(90) as i32,
 				7);
-			self.display_clear_btn.set_size(
-				(canvas.width() / 2).saturating_sub(5),
-				btn_height);
-			self.display_paint_btn.set_size(
-				(canvas.width() / 2).saturating_sub(5),
-				btn_height);
+			let btn_width = canvas.width().saturating_sub(40) / 5;
+			self.display_clear_btn.set_size(btn_width, btn_height);
+			self.display_undo_btn.set_size(btn_width, btn_height);
+			self.display_undo_btn.set_position(
+				(20 + btn_width) as i32,
+				150);
+			self.display_open_btn.set_size(btn_width, btn_height);
+			self.display_open_btn.set_position(
+				(30 + 2 * btn_width) as i32,
+				150);
+			self.display_save_btn.set_size(btn_width, btn_height);
+			self.display_save_btn.set_position(
+				(40 + 3 * btn_width) as i32,
+				150);
+			self.display_paint_btn.set_size(btn_width, btn_height);
 			self.display_paint_btn.set_position(
-				(20 + (canvas.width() / 2).saturating_sub(5)) as i32,
+				(50 + 4 * btn_width) as i32,
 				150);
 		}
+
+		pressed
 	}
 
 	/// Called when an update to the pen display preview has been requested.
@@ -335,6 +1495,11 @@ impl ManagementWindow {
 	/// Called when the window has been told to close.
 	fn on_exit(&self) {
 		self.on_clear_pressed();
+		if self.settings.borrow().idle_screen.is_some() {
+			/* Leave the device displaying our idle screen. */
+			let slot = ScreenSlot::new(ScreenKind::Idle, 0).unwrap();
+			mng_cmd_try!(self, self.device.select_screen(slot));
+		}
 		nwg::stop_thread_dispatch();
 	}
 }
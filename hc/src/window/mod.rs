@@ -11,6 +11,12 @@ mod selector;
 /// Screen area selector window and logic.
 mod area;
 
+/// Passphrase prompt window and logic.
+mod passphrase;
+
+/// File saving dialog that tells which of its filters was picked.
+mod save;
+
 /// Initialize globals required by the windowing interface.
 pub fn init() {
 	nwg::init().expect("Could not initialize Win32 UI framework.");
@@ -36,3 +42,5 @@ pub fn init() {
 pub use manager::{manage, ManagementError};
 pub use selector::{pick_tablet, NoTabletConnector};
 pub use area::{pick_physical_area, PickPhysicalAreaError, AreaSelectionParameters};
+pub use passphrase::prompt_passphrase;
+pub use save::pick_save_file;
@@ -0,0 +1,151 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Prompt the user for the passphrase protecting signature files, optionally
+/// asking for it twice, to guard against typing mistakes when it is going to
+/// be used to encrypt a file.
+///
+/// Returns `None` when the user cancels the prompt.
+pub fn prompt_passphrase(confirm: bool) -> Result<Option<String>, nwg::NwgError> {
+	let channel = Rc::new(RefCell::new(None));
+	{
+		let prompt = PassphrasePrompt::new(confirm, channel.clone());
+		let _prompt = nwg::NativeUi::build_ui(prompt)?;
+		nwg::dispatch_thread_events();
+	}
+
+	let passphrase = channel.borrow_mut().take();
+	Ok(passphrase)
+}
+
+/// A modal message window containing fields for entering a passphrase.
+#[derive(nwd::NwgUi)]
+pub struct PassphrasePrompt {
+	/// The icon we're gonna be using for the window.
+	#[nwg_resource(source_bin: Some(crate::window::ICON))]
+	icon: nwg::Icon,
+
+	/// The top level window this controller is contained in.
+	#[nwg_control(
+		flags: "WINDOW",
+		center: true,
+		topmost: true,
+		icon: Some(&data.icon),
+		size: (400, 135)
+	)]
+	#[nwg_events(
+		OnInit: [Self::init],
+		OnWindowClose: [Self::on_cancel]
+	)]
+	window: nwg::Window,
+
+	/// The description of what should be done.
+	#[nwg_control(
+		size: (380, 20),
+		position: (10, 10)
+	)]
+	description: nwg::Label,
+
+	/// The field the passphrase is entered in.
+	#[nwg_control(
+		size: (380, 25),
+		position: (10, 35),
+		password: Some('*')
+	)]
+	passphrase: nwg::TextInput,
+
+	/// The field the passphrase is entered in again, when it is to be
+	/// confirmed.
+	#[nwg_control(
+		size: (380, 25),
+		position: (10, 65),
+		password: Some('*')
+	)]
+	confirmation: nwg::TextInput,
+
+	/// The cancel button.
+	///
+	/// Having this button be clicked indicates that the user does not wish to
+	/// enter a passphrase and that the operation should be aborted.
+	#[nwg_control(
+		position: (290, 100)
+	)]
+	#[nwg_events(
+		OnButtonClick: [Self::on_cancel]
+	)]
+	cancel: nwg::Button,
+
+	/// The accept button.
+	#[nwg_control(
+		position: (180, 100)
+	)]
+	#[nwg_events(
+		OnButtonClick: [Self::on_accept]
+	)]
+	accept: nwg::Button,
+
+	/// Whether the passphrase has to be entered twice.
+	confirm: bool,
+
+	/// The channel through which we will provide our answer.
+	channel: Rc<RefCell<Option<String>>>
+}
+impl PassphrasePrompt {
+	/// Create a new passphrase prompt structure.
+	fn new(confirm: bool, channel: Rc<RefCell<Option<String>>>) -> Self {
+		Self {
+			icon: Default::default(),
+			window: Default::default(),
+			description: Default::default(),
+			passphrase: Default::default(),
+			confirmation: Default::default(),
+			cancel: Default::default(),
+			accept: Default::default(),
+			confirm,
+			channel
+		}
+	}
+
+	/// Populates the data in the window controls.
+	fn init(&self) {
+		self.window.set_text(crate::strings::passphrase::title());
+		self.description.set_text(crate::strings::passphrase::description());
+		self.passphrase.set_placeholder_text(Some(crate::strings::passphrase::passphrase()));
+		self.confirmation.set_placeholder_text(Some(crate::strings::passphrase::confirmation()));
+		self.confirmation.set_visible(self.confirm);
+		self.accept.set_text(crate::strings::passphrase::accept());
+		self.cancel.set_text(crate::strings::passphrase::cancel());
+
+		self.window.set_visible(true);
+		self.passphrase.set_focus();
+	}
+
+	/// A source of cancellation intent has been fired.
+	fn on_cancel(&self) {
+		self.window.set_visible(false);
+		nwg::stop_thread_dispatch();
+	}
+
+	/// A source of acceptance intent has been fired.
+	fn on_accept(&self) {
+		let passphrase = self.passphrase.text();
+		if passphrase.is_empty() {
+			nwg::modal_error_message(
+				&self.window,
+				crate::strings::errors::title(),
+				crate::strings::errors::passphrase_empty());
+			return
+		}
+		if self.confirm && passphrase != self.confirmation.text() {
+			nwg::modal_error_message(
+				&self.window,
+				crate::strings::errors::title(),
+				crate::strings::errors::passphrase_mismatch());
+			return
+		}
+
+		*RefCell::borrow_mut(&self.channel) = Some(passphrase);
+		self.window.set_visible(false);
+		nwg::stop_thread_dispatch();
+	}
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+/// Asks the user for the file something gets saved to, offering the given
+/// filters, given by their names and extensions.
+///
+/// Unlike the file dialogs in the UI framework, this also tells which of the
+/// filters was selected when the dialog was closed, by its index in the given
+/// list, so that files named without an extension may be saved in the format
+/// the user picked. Returns `None` when the user cancels the dialog.
+pub fn pick_save_file(
+	parent: &nwg::Window,
+	title: &str,
+	filters: &[(&str, &str)]) -> Option<(PathBuf, usize)> {
+
+	use winapi::um::commdlg;
+	use std::os::windows::ffi::OsStringExt;
+
+	let wide = |text: &str| text.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+
+	/* Filters are given as pairs of null terminated strings, with the list
+	 * ending in an empty string. */
+	let mut filter = Vec::new();
+	for (name, extension) in filters {
+		filter.extend(wide(&format!("{}(*.{})", name, extension)));
+		filter.extend(wide(&format!("*.{}", extension)));
+	}
+	filter.push(0);
+
+	let title = wide(title);
+	let mut file = vec![0u16; 32768];
+
+	let mut dialog = unsafe { std::mem::zeroed::<commdlg::OPENFILENAMEW>() };
+	dialog.lStructSize = std::mem::size_of::<commdlg::OPENFILENAMEW>() as _;
+	dialog.hwndOwner = parent.handle.hwnd().unwrap_or(std::ptr::null_mut());
+	dialog.lpstrFilter = filter.as_ptr();
+	dialog.nFilterIndex = 1;
+	dialog.lpstrFile = file.as_mut_ptr();
+	dialog.nMaxFile = file.len() as _;
+	dialog.lpstrTitle = title.as_ptr();
+	dialog.Flags = commdlg::OFN_EXPLORER
+		| commdlg::OFN_OVERWRITEPROMPT
+		| commdlg::OFN_PATHMUSTEXIST
+		| commdlg::OFN_NOCHANGEDIR;
+
+	if unsafe { commdlg::GetSaveFileNameW(&mut dialog) } == 0 {
+		return None
+	}
+
+	let length = file.iter().position(|unit| *unit == 0).unwrap_or(file.len());
+	let path = PathBuf::from(std::ffi::OsString::from_wide(&file[..length]));
+
+	/* The index of the selected filter counts from one. */
+	Some((path, (dialog.nFilterIndex as usize).saturating_sub(1)))
+}
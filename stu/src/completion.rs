@@ -0,0 +1,294 @@
+use std::time::{Duration, Instant};
+use crate::Event;
+
+/// The reasons for which a signature may be considered complete.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CompletionReason {
+	/// The pen has been hovering over the screen for too long since it was
+	/// last lifted.
+	PenUp,
+	/// The pen has been away from the screen for too long since it was last
+	/// lifted.
+	OutOfProximity,
+}
+
+/// Notification that a signature has been completed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SignatureComplete {
+	/// The point in time the pen was last lifted from the screen.
+	pub lifted: Instant,
+	/// Why the signature is considered complete.
+	pub reason: CompletionReason,
+}
+
+/// Detects the end of signatures, by waiting for the pen to stay away from the
+/// screen for long enough after ink has been drawn.
+///
+/// The pen may hover over the screen for longer than it may be away from it,
+/// as a pen that is out of proximity is more likely to have been put down. Note
+/// that detectors learn whether the pen is hovering from the events they are
+/// given, so they should see at least the events in which that changes.
+#[derive(Debug, Clone)]
+pub struct CompletionDetector {
+	/// How long the pen may hover over the screen after being lifted.
+	pen_up: Duration,
+	/// How long the pen may be out of proximity after being lifted.
+	out_of_proximity: Duration,
+	/// Whether ink has been drawn since the last completion.
+	inked: bool,
+	/// The point in time the pen was lifted, if it has been since the ink was
+	/// last drawn.
+	lifted: Option<Instant>,
+	/// The point in time the pen left proximity, if it has since it was last
+	/// lifted.
+	left: Option<Instant>,
+}
+impl CompletionDetector {
+	/// Creates a new detector, which considers signatures complete once the pen
+	/// has been lifted for either the given hovering or the given out of
+	/// proximity timeout.
+	pub fn new(pen_up: Duration, out_of_proximity: Duration) -> Self {
+		Self {
+			pen_up,
+			out_of_proximity,
+			inked: false,
+			lifted: None,
+			left: None,
+		}
+	}
+
+	/// Processes the given event.
+	pub fn process(&mut self, event: &Event) {
+		if event.touching() {
+			self.inked = true;
+			self.lifted = None;
+			self.left = None;
+			return
+		}
+		if !self.inked { return }
+
+		self.lifted = self.lifted.or(Some(event.time()));
+		self.left = if event.hovering() {
+			None
+		} else {
+			self.left.or(Some(event.time()))
+		};
+	}
+
+	/// The point in time the signature will be complete, along with the
+	/// timeout counting down to it and the reason it will be complete for, if
+	/// the countdown is running. Timeouts too long to be counted from the time
+	/// the pen was lifted or left proximity at never run out.
+	fn deadline(&self) -> Option<(Instant, Duration, CompletionReason)> {
+		let lifted = self.lifted?;
+		let pen_up = lifted.checked_add(self.pen_up)
+			.map(|deadline| (deadline, self.pen_up, CompletionReason::PenUp));
+		let out_of_proximity = self.left
+			.and_then(|left| left.checked_add(self.out_of_proximity))
+			.map(|deadline| (deadline, self.out_of_proximity, CompletionReason::OutOfProximity));
+
+		match (pen_up, out_of_proximity) {
+			(Some(pen_up), Some(left)) if left.0 < pen_up.0 => Some(left),
+			(Some(pen_up), _) => Some(pen_up),
+			(None, left) => left
+		}
+	}
+
+	/// How far along the countdown to the completion of the signature is at the
+	/// given point in time, from zero to one, if it is running.
+	pub fn progress(&self, now: Instant) -> Option<f64> {
+		let (deadline, timeout, _) = self.deadline()?;
+		if timeout.is_zero() { return Some(1.0) }
+
+		let remaining = deadline.saturating_duration_since(now);
+		Some((1.0 - remaining.as_secs_f64() / timeout.as_secs_f64()).clamp(0.0, 1.0))
+	}
+
+	/// Checks whether the signature has been completed by the given point in
+	/// time, notifying of its completion only once.
+	pub fn poll(&mut self, now: Instant) -> Option<SignatureComplete> {
+		let lifted = self.lifted?;
+		let (deadline, _, reason) = self.deadline()?;
+		if now < deadline { return None }
+
+		self.reset();
+		Some(SignatureComplete { lifted, reason })
+	}
+
+	/// Resets the state of this detector, as if it had never seen any events.
+	pub fn reset(&mut self) {
+		self.inked = false;
+		self.lifted = None;
+		self.left = None;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// An event at the given number of milliseconds after the given origin.
+	fn event(origin: Instant, millis: u64, touching: bool, hovering: bool) -> Event {
+		Event::new(
+			origin + Duration::from_millis(millis),
+			0.5,
+			0.5,
+			if touching { 0.5 } else { 0.0 },
+			touching,
+			hovering)
+	}
+
+	/// A detector with a pen up timeout of one second and an out of proximity
+	/// timeout of a quarter second.
+	fn detector() -> CompletionDetector {
+		CompletionDetector::new(Duration::from_millis(1000), Duration::from_millis(250))
+	}
+
+	#[test]
+	fn nothing_completes_without_ink() {
+		let origin = Instant::now();
+		let mut detector = detector();
+		detector.process(&event(origin, 0, false, true));
+		detector.process(&event(origin, 10, false, false));
+
+		assert_eq!(detector.progress(origin + Duration::from_secs(5)), None);
+		assert_eq!(detector.poll(origin + Duration::from_secs(5)), None);
+	}
+
+	#[test]
+	fn hovering_completes_after_pen_up_timeout() {
+		let origin = Instant::now();
+		let at = |millis| origin + Duration::from_millis(millis);
+		let mut detector = detector();
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, true));
+		detector.process(&event(origin, 600, false, true));
+
+		assert_eq!(detector.progress(at(100)), Some(0.0));
+		assert_eq!(detector.progress(at(600)), Some(0.5));
+		assert_eq!(detector.poll(at(1099)), None);
+		assert_eq!(detector.poll(at(1100)), Some(SignatureComplete {
+			lifted: at(100),
+			reason: CompletionReason::PenUp,
+		}));
+
+		/* Signatures are only completed once. */
+		assert_eq!(detector.progress(at(1100)), None);
+		assert_eq!(detector.poll(at(5000)), None);
+	}
+
+	#[test]
+	fn leaving_proximity_completes_sooner() {
+		let origin = Instant::now();
+		let at = |millis| origin + Duration::from_millis(millis);
+		let mut detector = detector();
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, true));
+		detector.process(&event(origin, 200, false, false));
+
+		assert_eq!(detector.progress(at(325)), Some(0.5));
+		assert_eq!(detector.poll(at(449)), None);
+		assert_eq!(detector.poll(at(450)), Some(SignatureComplete {
+			lifted: at(100),
+			reason: CompletionReason::OutOfProximity,
+		}));
+	}
+
+	#[test]
+	fn leaving_proximity_late_keeps_pen_up_timeout() {
+		let origin = Instant::now();
+		let at = |millis| origin + Duration::from_millis(millis);
+		let mut detector = detector();
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, true));
+		detector.process(&event(origin, 1000, false, false));
+
+		assert_eq!(detector.poll(at(1100)), Some(SignatureComplete {
+			lifted: at(100),
+			reason: CompletionReason::PenUp,
+		}));
+	}
+
+	#[test]
+	fn coming_back_restarts_countdowns() {
+		let origin = Instant::now();
+		let at = |millis| origin + Duration::from_millis(millis);
+		let mut detector = detector();
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, true));
+		detector.process(&event(origin, 200, false, false));
+
+		/* Coming back into proximity stops the out of proximity countdown,
+		 * but the pen is still lifted. */
+		detector.process(&event(origin, 300, false, true));
+		assert_eq!(detector.poll(at(450)), None);
+		assert_eq!(detector.progress(at(600)), Some(0.5));
+
+		/* Touching the screen again stops every countdown, until the pen is
+		 * lifted once more. */
+		detector.process(&event(origin, 700, true, true));
+		assert_eq!(detector.progress(at(1100)), None);
+		assert_eq!(detector.poll(at(1100)), None);
+
+		detector.process(&event(origin, 800, false, true));
+		assert_eq!(detector.poll(at(1799)), None);
+		assert_eq!(detector.poll(at(1800)), Some(SignatureComplete {
+			lifted: at(800),
+			reason: CompletionReason::PenUp,
+		}));
+	}
+
+	#[test]
+	fn reset_forgets_the_ink() {
+		let origin = Instant::now();
+		let at = |millis| origin + Duration::from_millis(millis);
+		let mut detector = detector();
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, false));
+		detector.reset();
+
+		assert_eq!(detector.progress(at(200)), None);
+		assert_eq!(detector.poll(at(5000)), None);
+
+		/* Lifting the pen after a reset doesn't start a countdown, as there is
+		 * no ink to complete. */
+		detector.process(&event(origin, 200, false, true));
+		assert_eq!(detector.poll(at(5000)), None);
+	}
+
+	#[test]
+	fn zero_timeouts_complete_at_once() {
+		let origin = Instant::now();
+		let mut detector = CompletionDetector::new(Duration::ZERO, Duration::ZERO);
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, true));
+
+		assert_eq!(detector.progress(origin), Some(1.0));
+		assert!(detector.poll(origin + Duration::from_millis(100)).is_some());
+	}
+
+	#[test]
+	fn endless_timeouts_never_run_out() {
+		let origin = Instant::now();
+		let at = |millis| origin + Duration::from_millis(millis);
+		let mut detector = CompletionDetector::new(Duration::MAX, Duration::from_millis(250));
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, true));
+
+		assert_eq!(detector.progress(at(200)), None);
+		assert_eq!(detector.poll(at(1_000_000)), None);
+
+		/* The other timeout still runs out. */
+		detector.process(&event(origin, 200, false, false));
+		assert_eq!(detector.poll(at(450)), Some(SignatureComplete {
+			lifted: at(100),
+			reason: CompletionReason::OutOfProximity,
+		}));
+
+		let mut detector = CompletionDetector::new(Duration::MAX, Duration::MAX);
+		detector.process(&event(origin, 0, true, true));
+		detector.process(&event(origin, 100, false, false));
+		assert_eq!(detector.progress(at(200)), None);
+		assert_eq!(detector.poll(at(1_000_000)), None);
+	}
+}
@@ -47,6 +47,46 @@ pub enum ClientError {
 		/// The Report ID of the operation.
 		report_id: stu_sys::tagWacomGSS_ReportId,
 	},
+	/// A buffer given for a raw report access does not match the length of the
+	/// report, as declared by the device.
+	#[error("report \"{report_id}\" is {expected} bytes long, but the buffer \
+		is {found} bytes long")]
+	InvalidReportLength {
+		/// The Report ID of the operation.
+		report_id: stu_sys::tagWacomGSS_ReportId,
+		/// The length of the report, as declared by the device.
+		expected: usize,
+		/// The length of the buffer that was given.
+		found: usize,
+	},
+	/// A buffer given for a raw report write does not start with the Report ID
+	/// of the report it is being written as.
+	#[error("report \"{report_id}\" was given a buffer for report \"{found}\"")]
+	MismatchedReportId {
+		/// The Report ID of the operation.
+		report_id: stu_sys::tagWacomGSS_ReportId,
+		/// The Report ID found at the start of the buffer.
+		found: u8,
+	},
+	/// An image given to the device does not have the same dimensions as its
+	/// screen.
+	#[error("the image is {}x{} pixels, but the screen is {}x{} pixels",
+		found.0, found.1, expected.0, expected.1)]
+	InvalidImageDimensions {
+		/// The dimensions of the screen of the device.
+		expected: (u32, u32),
+		/// The dimensions of the image that was given.
+		found: (u32, u32),
+	},
+	/// A raw report access was refused by the API, which usually means the
+	/// report can't be accessed in the direction it was accessed in.
+	#[error("access to report \"{report_id}\" was refused: {reason}")]
+	RejectedReport {
+		/// The Report ID of the operation.
+		report_id: stu_sys::tagWacomGSS_ReportId,
+		/// The reason given by the API for refusing the access.
+		reason: String,
+	},
 	/// An incomplete or invalid report was generated by the API.
 	#[error("the device handed out an invalid report")]
 	InvalidReport
@@ -125,6 +165,22 @@ impl InternalError {
 		})
 	}
 
+	/// Converts this error, returned by a raw access to the report with the
+	/// given Report ID, to a general error.
+	///
+	/// Unlike with the dedicated functions, the parameters of raw accesses are
+	/// picked by the user, so errors other than exceptions are blamed on them
+	/// rather than on this crate, and get reported instead of raising a panic.
+	pub fn into_report_error(self, report_id: stu_sys::tagWacomGSS_ReportId) -> Error {
+		match self.code {
+			InternalErrorCode::Exception(_) => self.unwrap_to_general(),
+			other => Error::ClientError(ClientError::RejectedReport {
+				report_id,
+				reason: other.to_string()
+			})
+		}
+	}
+
 	/// Tries to create a wrapper around the error value from the Wacom STU API.
 	pub fn from_wacom_stu(what: std::os::raw::c_int) -> Result<(), Self> {
 		let code = match InternalErrorCode::from_wacom_stu(what) {
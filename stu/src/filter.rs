@@ -0,0 +1,694 @@
+use crate::{Event, Queue, Error, TryRecvError};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// A stage in the processing of a stream of events.
+///
+/// Filters receive events in the order they were generated, and, for each one
+/// of them, decide whether it should be kept in the stream, and what it should
+/// look like when it is. Filters may be chained together with a [`Pipeline`],
+/// and applied to either a [`Queue`], with [`Queue::filtered()`], or to any
+/// iterator over events, with [`EventIterator::filtered()`].
+///
+/// [`Pipeline`]: Pipeline
+/// [`Queue`]: Queue
+/// [`Queue::filtered()`]: Queue::filtered
+/// [`EventIterator::filtered()`]: EventIterator::filtered
+pub trait EventFilter {
+	/// Processes the given event, returning the event that should take its
+	/// place in the stream, if any.
+	fn filter(&mut self, event: Event) -> Option<Event>;
+
+	/// Resets the internal state of this filter, as if it had never seen any
+	/// events before.
+	fn reset(&mut self) {}
+}
+
+/// A chain of filters, applied one after the other.
+///
+/// An event is only kept in the stream if it is kept by all of the filters in
+/// the chain.
+#[derive(Default)]
+pub struct Pipeline {
+	/// The filters in this pipeline, in the order they are applied in.
+	stages: Vec<Box<dyn EventFilter>>,
+}
+impl Pipeline {
+	/// Creates a new, empty pipeline, which keeps all events unchanged.
+	pub fn new() -> Self {
+		Self {
+			stages: Vec::new()
+		}
+	}
+
+	/// Adds a filter to the end of this pipeline.
+	pub fn with<F>(mut self, filter: F) -> Self
+		where F: EventFilter + 'static {
+
+		self.stages.push(Box::new(filter));
+		self
+	}
+
+	/// The number of filters in this pipeline.
+	pub fn len(&self) -> usize {
+		self.stages.len()
+	}
+
+	/// Whether this pipeline has no filters in it.
+	pub fn is_empty(&self) -> bool {
+		self.stages.is_empty()
+	}
+}
+impl EventFilter for Pipeline {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		self.stages
+			.iter_mut()
+			.try_fold(event, |event, stage| stage.filter(event))
+	}
+
+	fn reset(&mut self) {
+		for stage in &mut self.stages {
+			stage.reset()
+		}
+	}
+}
+
+/// Drops events that are identical to the event right before them, save for
+/// their timestamps.
+#[derive(Debug, Clone, Default)]
+pub struct Dedup {
+	/// The last event that was kept.
+	last: Option<Event>,
+}
+impl Dedup {
+	/// Creates a new deduplication filter.
+	pub fn new() -> Self {
+		Self { last: None }
+	}
+}
+impl EventFilter for Dedup {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		let duplicate = match self.last {
+			Some(last) =>
+				   last.x() == event.x()
+				&& last.y() == event.y()
+				&& last.pressure() == event.pressure()
+				&& last.touching() == event.touching()
+				&& last.hovering() == event.hovering(),
+			None => false
+		};
+
+		if duplicate {
+			None
+		} else {
+			self.last = Some(event);
+			Some(event)
+		}
+	}
+
+	fn reset(&mut self) {
+		self.last = None
+	}
+}
+
+/// Drops events in which the pen is not touching the screen.
+///
+/// The first event after the pen has been lifted is always kept, so that
+/// consumers may still tell strokes apart from one another, and so is the
+/// first event after the pen enters or leaves proximity, so that they may
+/// still tell whether it is hovering over the screen.
+#[derive(Debug, Clone, Default)]
+pub struct HoverFilter {
+	/// Whether the pen was touching the screen in the last event.
+	touching: bool,
+	/// Whether the pen was hovering over the screen in the last event.
+	hovering: bool,
+}
+impl HoverFilter {
+	/// Creates a new hover filter.
+	pub fn new() -> Self {
+		Self { touching: false, hovering: false }
+	}
+}
+impl EventFilter for HoverFilter {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		let lifted = self.touching && !event.touching();
+		let moved = self.hovering != event.hovering();
+		self.touching = event.touching();
+		self.hovering = event.hovering();
+
+		if event.touching() || lifted || moved {
+			Some(event)
+		} else {
+			None
+		}
+	}
+
+	fn reset(&mut self) {
+		self.touching = false;
+		self.hovering = false;
+	}
+}
+
+/// Smooths out flickering in whether the pen is touching the screen, by only
+/// accepting a change after it has held for a number of consecutive events.
+///
+/// Events are never dropped by this filter. Instead, events that happen while
+/// a change has not yet been accepted are reported with the previous state.
+#[derive(Debug, Clone)]
+pub struct TouchDebounce {
+	/// The number of consecutive events a change must hold for.
+	samples: usize,
+	/// The currently accepted state of the pen.
+	touching: bool,
+	/// The number of consecutive events that disagreed with the accepted state.
+	pending: usize,
+}
+impl TouchDebounce {
+	/// Creates a new debounce filter, which accepts changes after they have
+	/// held for the given number of consecutive events.
+	pub fn new(samples: usize) -> Self {
+		Self {
+			samples: samples.max(1),
+			touching: false,
+			pending: 0
+		}
+	}
+}
+impl EventFilter for TouchDebounce {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		if event.touching() == self.touching {
+			self.pending = 0;
+			return Some(event)
+		}
+
+		self.pending += 1;
+		if self.pending >= self.samples {
+			self.touching = event.touching();
+			self.pending = 0;
+
+			return Some(event)
+		}
+
+		Some(Event::new(
+			event.time(),
+			event.x(),
+			event.y(),
+			event.pressure(),
+			self.touching,
+			event.hovering() || self.touching))
+	}
+
+	fn reset(&mut self) {
+		self.touching = false;
+		self.pending = 0;
+	}
+}
+
+/// Smooths out the position of the pen by averaging it over a number of the
+/// most recent events in the same stroke.
+#[derive(Debug, Clone)]
+pub struct MovingAverage {
+	/// The maximum number of events to average over.
+	window: usize,
+	/// The positions of the most recent events in the current stroke.
+	history: VecDeque<(f64, f64)>,
+}
+impl MovingAverage {
+	/// Creates a new moving average filter, averaging over the given number of
+	/// events.
+	pub fn new(window: usize) -> Self {
+		let window = window.max(1);
+		Self {
+			window,
+			history: VecDeque::with_capacity(window)
+		}
+	}
+}
+impl EventFilter for MovingAverage {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		if !event.touching() {
+			/* Don't smooth across pen lifts. */
+			self.history.clear();
+			return Some(event)
+		}
+
+		if self.history.len() == self.window {
+			self.history.pop_front();
+		}
+		self.history.push_back((event.x(), event.y()));
+
+		let n = self.history.len() as f64;
+		let (x, y) = self.history
+			.iter()
+			.fold((0.0, 0.0), |(ax, ay), (x, y)| (ax + x, ay + y));
+
+		Some(Event::new(
+			event.time(),
+			x / n,
+			y / n,
+			event.pressure(),
+			event.touching(),
+			event.hovering()))
+	}
+
+	fn reset(&mut self) {
+		self.history.clear()
+	}
+}
+
+/// Smooths out the position of the pen using the 1€ filter.
+///
+/// This filter is an adaptive low pass filter, whose cutoff frequency rises
+/// with the speed of the pen. This way, jitter gets filtered out heavily while
+/// the pen is moving slowly, while fast strokes suffer little lag. See Casiez,
+/// Roussel and Vogel, "1€ Filter: A Simple Speed-based Low-pass Filter for Noisy
+/// Input in Interactive Systems", CHI 2012.
+#[derive(Debug, Clone)]
+pub struct OneEuro {
+	/// The minimum cutoff frequency, in hertz.
+	min_cutoff: f64,
+	/// How much the cutoff frequency rises with the speed of the pen.
+	beta: f64,
+	/// The cutoff frequency for the estimation of the speed, in hertz.
+	derivative_cutoff: f64,
+	/// The state of the filter for each axis, along with the last event.
+	state: Option<(Event, OneEuroAxis, OneEuroAxis)>,
+}
+impl OneEuro {
+	/// The shortest interval assumed between events, which is the interval
+	/// between the reports of the fastest devices, at 200 reports per second.
+	///
+	/// Events are timestamped when the batch they come in is processed, rather
+	/// than when the device took them, so events in the same batch end up a
+	/// few microseconds apart, or even sharing timestamps. Taking those tiny
+	/// intervals at face value would make the speed of the pen look enormous,
+	/// and the filter would freeze.
+	const MIN_INTERVAL: Duration = Duration::from_millis(5);
+
+	/// Creates a new 1€ filter, with the given minimum cutoff frequency, in
+	/// hertz, and speed coefficient, in hertz per unit of normalized speed.
+	pub fn new(min_cutoff: f64, beta: f64) -> Self {
+		Self {
+			min_cutoff,
+			beta,
+			derivative_cutoff: 1.0,
+			state: None
+		}
+	}
+
+	/// Changes the cutoff frequency for the estimation of the speed of the pen,
+	/// in hertz. The default value is `1.0`.
+	pub fn with_derivative_cutoff(mut self, cutoff: f64) -> Self {
+		self.derivative_cutoff = cutoff;
+		self
+	}
+}
+impl EventFilter for OneEuro {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		if !event.touching() {
+			/* Don't smooth across pen lifts. */
+			self.state = None;
+			return Some(event)
+		}
+
+		let (last, mut x, mut y) = match self.state {
+			Some(state) => state,
+			None => {
+				self.state = Some((
+					event,
+					OneEuroAxis::new(event.x()),
+					OneEuroAxis::new(event.y())));
+				return Some(event)
+			}
+		};
+
+		let dt = event.time()
+			.saturating_duration_since(last.time())
+			.max(Self::MIN_INTERVAL)
+			.as_secs_f64();
+
+		let params = (self.min_cutoff, self.beta, self.derivative_cutoff);
+		let fx = x.filter(event.x(), dt, params);
+		let fy = y.filter(event.y(), dt, params);
+
+		self.state = Some((event, x, y));
+		Some(Event::new(
+			event.time(),
+			fx,
+			fy,
+			event.pressure(),
+			event.touching(),
+			event.hovering()))
+	}
+
+	fn reset(&mut self) {
+		self.state = None
+	}
+}
+
+/// The state of the 1€ filter along a single axis.
+#[derive(Debug, Copy, Clone)]
+struct OneEuroAxis {
+	/// The last filtered value.
+	value: f64,
+	/// The last filtered derivative.
+	derivative: f64,
+}
+impl OneEuroAxis {
+	/// Starts filtering an axis at the given value.
+	fn new(value: f64) -> Self {
+		Self {
+			value,
+			derivative: 0.0
+		}
+	}
+
+	/// The smoothing factor of an exponential filter with the given cutoff
+	/// frequency, for samples spaced by the given interval.
+	fn alpha(cutoff: f64, dt: f64) -> f64 {
+		let tau = 1.0 / (2.0 * std::f64::consts::PI * cutoff);
+		1.0 / (1.0 + tau / dt)
+	}
+
+	/// Filters a new value, taken the given interval after the last one.
+	fn filter(
+		&mut self,
+		value: f64,
+		dt: f64,
+		(min_cutoff, beta, derivative_cutoff): (f64, f64, f64)) -> f64 {
+
+		let derivative = (value - self.value) / dt;
+		let a = Self::alpha(derivative_cutoff, dt);
+		self.derivative += a * (derivative - self.derivative);
+
+		let cutoff = min_cutoff + beta * self.derivative.abs();
+		let a = Self::alpha(cutoff, dt);
+		self.value += a * (value - self.value);
+
+		self.value
+	}
+}
+
+/// Only considers the pen as touching the screen when it is pressed against it
+/// with at least the given pressure.
+#[derive(Debug, Copy, Clone)]
+pub struct PressureThreshold {
+	/// The minimum pressure, in normalized coordinates.
+	threshold: f64,
+}
+impl PressureThreshold {
+	/// Creates a new pressure threshold filter with the given minimum pressure,
+	/// in normalized coordinates.
+	pub fn new(threshold: f64) -> Self {
+		Self { threshold }
+	}
+}
+impl EventFilter for PressureThreshold {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		if !event.touching() || event.pressure() >= self.threshold {
+			return Some(event)
+		}
+
+		Some(Event::new(
+			event.time(),
+			event.x(),
+			event.y(),
+			event.pressure(),
+			false,
+			true))
+	}
+}
+
+/// Extension of iterators over events with the ability to apply filters.
+pub trait EventIterator: Iterator<Item = Event> + Sized {
+	/// Applies the given filter to the events in this iterator.
+	fn filtered<F>(self, filter: F) -> Filtered<Self, F>
+		where F: EventFilter {
+
+		Filtered {
+			inner: self,
+			filter
+		}
+	}
+}
+impl<I> EventIterator for I where I: Iterator<Item = Event> {}
+
+/// An iterator over the events of another iterator, with a filter applied.
+///
+/// This structure is obtained from the [`EventIterator::filtered()`] function.
+///
+/// [`EventIterator::filtered()`]: EventIterator::filtered
+#[derive(Debug, Clone)]
+pub struct Filtered<I, F> {
+	/// The iterator the events come from.
+	inner: I,
+	/// The filter being applied to the events.
+	filter: F,
+}
+impl<I, F> Filtered<I, F> {
+	/// The filter being applied to the events.
+	pub fn filter(&mut self) -> &mut F {
+		&mut self.filter
+	}
+
+	/// Takes the underlying iterator and filter back.
+	pub fn into_inner(self) -> (I, F) {
+		(self.inner, self.filter)
+	}
+}
+impl<I, F> Iterator for Filtered<I, F>
+	where I: Iterator<Item = Event>,
+		  F: EventFilter {
+
+	type Item = Event;
+	fn next(&mut self) -> Option<Self::Item> {
+		let filter = &mut self.filter;
+		self.inner
+			.by_ref()
+			.find_map(|event| filter.filter(event))
+	}
+}
+
+/// A report queue connected to a tablet device, with a filter applied to the
+/// events coming out of it.
+///
+/// This structure is obtained from the [`Queue::filtered()`] function.
+///
+/// [`Queue::filtered()`]: Queue::filtered
+pub struct FilteredQueue<F> {
+	/// The queue the events come from.
+	queue: Queue,
+	/// The filter being applied to the events.
+	filter: F,
+}
+impl<F> FilteredQueue<F>
+	where F: EventFilter {
+
+	/// Creates a new filtered queue.
+	pub(crate) fn new(queue: Queue, filter: F) -> Self {
+		Self { queue, filter }
+	}
+
+	/// The filter being applied to the events.
+	pub fn filter(&mut self) -> &mut F {
+		&mut self.filter
+	}
+
+	/// Takes the underlying queue and filter back.
+	pub fn into_inner(self) -> (Queue, F) {
+		(self.queue, self.filter)
+	}
+
+	/// Tries to receive a filtered event from the device.
+	///
+	/// This function returns immediately, regardless of whether a message is
+	/// available or not. See [`Queue::try_recv()`] for more details.
+	///
+	/// [`Queue::try_recv()`]: Queue::try_recv
+	pub fn try_recv(&mut self) -> Result<Event, TryRecvError> {
+		loop {
+			let event = self.queue.try_recv()?;
+			if let Some(event) = self.filter.filter(event) {
+				return Ok(event)
+			}
+		}
+	}
+
+	/// Tries to receive a filtered event from the device.
+	///
+	/// This function blocks until an event makes it through the filter. See
+	/// [`Queue::recv()`] for more details.
+	///
+	/// [`Queue::recv()`]: Queue::recv
+	pub fn recv(&mut self) -> Result<Event, Error> {
+		loop {
+			let event = self.queue.recv()?;
+			if let Some(event) = self.filter.filter(event) {
+				return Ok(event)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+
+	/// An event at the given time after the given origin.
+	fn event(
+		origin: Instant,
+		micros: u64,
+		(x, y, pressure): (f64, f64, f64),
+		touching: bool,
+		hovering: bool) -> Event {
+
+		Event::new(origin + Duration::from_micros(micros), x, y, pressure, touching, hovering)
+	}
+
+	/// Runs the given events through the given filter, collecting the ones that
+	/// were kept.
+	fn run<F: EventFilter>(filter: &mut F, events: &[Event]) -> Vec<Event> {
+		events.iter().filter_map(|event| filter.filter(*event)).collect()
+	}
+
+	#[test]
+	fn dedup_drops_repeated_events() {
+		let origin = Instant::now();
+		let events = [
+			event(origin, 0, (0.1, 0.1, 0.5), true, true),
+			event(origin, 1, (0.1, 0.1, 0.5), true, true),
+			event(origin, 2, (0.2, 0.1, 0.5), true, true),
+			event(origin, 3, (0.2, 0.1, 0.5), false, true),
+			event(origin, 4, (0.2, 0.1, 0.5), false, true),
+		];
+
+		let mut filter = Dedup::new();
+		let kept = run(&mut filter, &events);
+		assert_eq!(kept, vec![events[0], events[2], events[3]]);
+
+		filter.reset();
+		assert_eq!(filter.filter(events[4]), Some(events[4]));
+	}
+
+	#[test]
+	fn hover_filter_keeps_lifts_and_proximity_changes() {
+		let origin = Instant::now();
+		let at = |micros, touching, hovering| event(origin, micros, (0.5, 0.5, 0.0), touching, hovering);
+		let events = [
+			at(0, false, true),
+			at(1, false, true),
+			at(2, true, true),
+			at(3, true, true),
+			at(4, false, true),
+			at(5, false, true),
+			at(6, false, false),
+			at(7, false, false),
+			at(8, false, true),
+			at(9, false, true),
+		];
+
+		let kept = run(&mut HoverFilter::new(), &events);
+		assert_eq!(kept, vec![
+			events[0],
+			events[2],
+			events[3],
+			events[4],
+			events[6],
+			events[8],
+		]);
+	}
+
+	#[test]
+	fn touch_debounce_needs_consecutive_events() {
+		let origin = Instant::now();
+		let at = |micros, touching| event(origin, micros, (0.5, 0.5, 0.5), touching, true);
+		let events = [
+			at(0, true),
+			at(1, false),
+			at(2, true),
+			at(3, true),
+			at(4, true),
+			at(5, false),
+			at(6, false),
+		];
+
+		let touching = run(&mut TouchDebounce::new(2), &events)
+			.iter()
+			.map(|event| event.touching())
+			.collect::<Vec<_>>();
+		assert_eq!(touching, vec![false, false, false, true, true, true, false]);
+	}
+
+	#[test]
+	fn pressure_threshold_lifts_light_touches() {
+		let origin = Instant::now();
+		let light = event(origin, 0, (0.5, 0.5, 0.1), true, true);
+		let firm = event(origin, 1, (0.5, 0.5, 0.3), true, true);
+
+		let mut filter = PressureThreshold::new(0.2);
+		let light = filter.filter(light).unwrap();
+		assert!(!light.touching());
+		assert!(light.hovering());
+		assert_eq!(filter.filter(firm), Some(firm));
+	}
+
+	#[test]
+	fn one_euro_follows_events_in_the_same_batch() {
+		/* Events a microsecond apart, as they are when they get drained from
+		 * the same batch, must not freeze the filter in place. */
+		let origin = Instant::now();
+		let events = (0..80)
+			.map(|i| {
+				let x = 0.2 + 0.2 * (f64::from(i).min(40.0) / 40.0);
+				event(origin, u64::from(i as u32), (x, 0.5, 0.5), true, true)
+			})
+			.collect::<Vec<_>>();
+
+		let filtered = run(&mut OneEuro::new(1.0, 10.0), &events);
+		let last = filtered.last().unwrap();
+		assert!(last.x() > 0.35, "the filter froze at {}", last.x());
+	}
+
+	#[test]
+	fn one_euro_smooths_jitter() {
+		let origin = Instant::now();
+		let events = (0..100u64)
+			.map(|i| {
+				let x = 0.5 + if i % 2 == 0 { 0.002 } else { -0.002 };
+				event(origin, i * 5000, (x, 0.5, 0.5), true, true)
+			})
+			.collect::<Vec<_>>();
+
+		let filtered = run(&mut OneEuro::new(1.0, 10.0), &events);
+		let spread = filtered[50..]
+			.iter()
+			.map(|event| (event.x() - 0.5).abs())
+			.fold(0.0, f64::max);
+		assert!(spread < 0.001, "jitter of {} was left", spread);
+	}
+
+	#[test]
+	fn pipelines_chain_filters() {
+		let origin = Instant::now();
+		let events = [
+			event(origin, 0, (0.1, 0.1, 0.1), true, true),
+			event(origin, 1, (0.1, 0.1, 0.1), true, true),
+			event(origin, 2, (0.2, 0.1, 0.5), true, true),
+		];
+
+		let mut pipeline = Pipeline::new()
+			.with(Dedup::new())
+			.with(PressureThreshold::new(0.2))
+			.with(HoverFilter::new());
+		assert_eq!(pipeline.len(), 3);
+
+		/* The light touch is kept as the pen coming into proximity, but no
+		 * longer as the pen touching the screen. */
+		let kept = run(&mut pipeline, &events);
+		assert_eq!(kept.len(), 2);
+		assert!(!kept[0].touching());
+		assert_eq!(kept[1], events[2]);
+	}
+}
@@ -0,0 +1,172 @@
+/// The pixel formats in which images may be sent to a tablet device.
+///
+/// Which of these are supported depends on the model of the device, with the
+/// monochrome format being the only one supported by all of them. The best
+/// format available to a given device can be queried with
+/// [`Tablet::encoding()`].
+///
+/// [`Tablet::encoding()`]: crate::Tablet::encoding
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum EncodingMode {
+	/// One bit per pixel, with set bits being painted black and unset bits
+	/// being left white. Rows are padded to a whole number of bytes.
+	Monochrome,
+	/// Sixteen bits per pixel, with the pixel being packed as a little endian
+	/// RGB 5:6:5 value.
+	Rgb565,
+	/// Twenty four bits per pixel, with the pixel being laid out in the BGR
+	/// order expected by the device.
+	Rgb888,
+}
+impl EncodingMode {
+	/// Picks the best encoding mode out of the given Wacom STU encoding flags.
+	pub(crate) fn from_flags(flags: u8) -> Self {
+		let flags = u32::from(flags);
+		if flags & stu_sys::tagWacomGSS_EncodingFlag_WacomGSS_EncodingFlag_24bit as u32 != 0 {
+			Self::Rgb888
+		} else if flags & stu_sys::tagWacomGSS_EncodingFlag_WacomGSS_EncodingFlag_16bit as u32 != 0 {
+			Self::Rgb565
+		} else {
+			Self::Monochrome
+		}
+	}
+
+	/// The Wacom STU value corresponding to this encoding mode.
+	pub(crate) fn to_wacom_stu(self) -> u8 {
+		let mode = match self {
+			Self::Monochrome => stu_sys::tagWacomGSS_EncodingMode_WacomGSS_EncodingMode_1bit,
+			Self::Rgb565 => stu_sys::tagWacomGSS_EncodingMode_WacomGSS_EncodingMode_16bit,
+			Self::Rgb888 => stu_sys::tagWacomGSS_EncodingMode_WacomGSS_EncodingMode_24bit,
+		};
+		mode as u8
+	}
+
+	/// The number of bytes a row of pixels of the given width takes up.
+	fn row_length(self, width: u32) -> usize {
+		let width = width as usize;
+		match self {
+			Self::Monochrome => width / 8 + if width % 8 == 0 { 0 } else { 1 },
+			Self::Rgb565 => width * 2,
+			Self::Rgb888 => width * 3,
+		}
+	}
+}
+
+/// An image, encoded in a format that can be displayed by a tablet device.
+///
+/// Images are sent to the device as full screen updates, so an image must
+/// always have the same dimensions as the display of the device it is being
+/// sent to.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DeviceImage {
+	/// The encoding of the image data.
+	mode: EncodingMode,
+	/// The width of the image, in pixels.
+	width: u32,
+	/// The height of the image, in pixels.
+	height: u32,
+	/// The encoded image data.
+	data: Box<[u8]>,
+}
+impl DeviceImage {
+	/// Encodes an image from a function giving the RGB color of each pixel.
+	pub fn from_fn<F>(
+		mode: EncodingMode,
+		width: u32,
+		height: u32,
+		mut pixel: F) -> Self
+		where F: FnMut(u32, u32) -> [u8; 3] {
+
+		if width == 0 {
+			panic!("Tried to create a device image with no width.")
+		}
+		if height == 0 {
+			panic!("Tried to create a device image with no height.")
+		}
+
+		let row = mode.row_length(width);
+		let length = row.checked_mul(height as usize)
+			.expect("Device image size does not fit in a usize");
+
+		let mut data = vec![0u8; length].into_boxed_slice();
+		for y in 0..height {
+			let row = &mut data[y as usize * row..][..row];
+			for x in 0..width {
+				let [r, g, b] = pixel(x, y);
+				let i = x as usize;
+
+				match mode {
+					EncodingMode::Monochrome => {
+						/* Threshold the pixel on its luma, with the leftmost
+						 * pixel in the byte being its most significant bit. */
+						let luma =
+							  u32::from(r) * 299
+							+ u32::from(g) * 587
+							+ u32::from(b) * 114;
+						if luma < 128 * 1000 {
+							row[i / 8] |= 0x80 >> (i % 8);
+						}
+					},
+					EncodingMode::Rgb565 => {
+						let value =
+							  (u16::from(r) >> 3) << 11
+							| (u16::from(g) >> 2) << 5
+							| (u16::from(b) >> 3);
+						row[i * 2..][..2].copy_from_slice(&value.to_le_bytes());
+					},
+					EncodingMode::Rgb888 => {
+						row[i * 3..][..3].copy_from_slice(&[b, g, r]);
+					}
+				}
+			}
+		}
+
+		Self { mode, width, height, data }
+	}
+
+	/// Encodes an image from a buffer of 24-bit RGB pixel data, laid out row
+	/// by row, from the top left corner of the image.
+	///
+	/// # Panic
+	/// This function panics if the buffer is not exactly as large as an image
+	/// with the given dimensions.
+	pub fn from_rgb(
+		mode: EncodingMode,
+		width: u32,
+		height: u32,
+		pixels: &[u8]) -> Self {
+
+		let expected = width as usize * height as usize * 3;
+		if pixels.len() != expected {
+			panic!(
+				"Tried to create a {}x{} device image from {} bytes of pixel \
+				data, when {} bytes were expected.",
+				width, height, pixels.len(), expected)
+		}
+
+		Self::from_fn(mode, width, height, |x, y| {
+			let i = (y as usize * width as usize + x as usize) * 3;
+			[pixels[i], pixels[i + 1], pixels[i + 2]]
+		})
+	}
+
+	/// The encoding of the data in this image.
+	pub fn mode(&self) -> EncodingMode {
+		self.mode
+	}
+
+	/// The width of this image, in pixels.
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	/// The height of this image, in pixels.
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// The encoded data in this image.
+	pub fn data(&self) -> &[u8] {
+		&self.data[..]
+	}
+}
@@ -1,17 +1,61 @@
 /// Handling of errors from the Wacom STU interface.
 mod error;
-pub use error::{Exception, Error};
+pub use error::{Exception, Error, ClientError};
 
 /// Code dealing with the handling of reports from the device.
 mod report;
 pub use report::{Queue, Event, TryRecvError};
 
+/// Adapters for filtering and smoothing streams of events.
+mod filter;
+pub use filter::{
+	EventFilter,
+	EventIterator,
+	Filtered,
+	FilteredQueue,
+	Pipeline,
+	Dedup,
+	HoverFilter,
+	TouchDebounce,
+	MovingAverage,
+	OneEuro,
+	PressureThreshold
+};
+
+/// Detection of the end of signatures.
+mod completion;
+pub use completion::{CompletionDetector, CompletionReason, SignatureComplete};
+
+/// Response curves for the pressure of the pen.
+mod pressure;
+pub use pressure::PressureCurve;
+
+/// Rotation and mirroring of the orientation of the device.
+mod transform;
+pub use transform::{Rotation, Transform};
+
+/// Segmentation of sequences of events into strokes.
+mod stroke;
+pub use stroke::{Stroke, StrokeKind, Bounds};
+
+/// Simplification of strokes down to the events that shape them.
+mod simplify;
+pub use simplify::{Simplifier, Tolerance};
+
 /// Handles to memory managed by the Wacom STU allocator.
 mod handle;
 
-use std::collections::HashSet;
+/// Encoding of images for display on the device.
+mod image;
+pub use image::{EncodingMode, DeviceImage};
+
+/// Slots for images kept in the onboard storage of the device.
+mod rom;
+pub use rom::{ScreenKind, ScreenSlot, StoredScreen};
+
+use std::collections::BTreeMap;
 use crate::handle::Handle;
-use crate::error::{InternalError, ClientError};
+use crate::error::InternalError;
 
 use std::sync::{Arc, Mutex};
 
@@ -19,12 +63,18 @@ use std::sync::{Arc, Mutex};
 pub struct Tablet {
 	/// The raw handle to the tablet interface.
 	raw: Arc<RawTabletConnection>,
-	/// The list of reports types supported by this tablet.
-	supported_reports: HashSet<stu_sys::tagWacomGSS_ReportId>,
+	/// The information about the device this tablet is connected to.
+	info: Information,
+	/// The table of reports types supported by this tablet, along with the
+	/// length of each of them, in bytes.
+	supported_reports: BTreeMap<stu_sys::tagWacomGSS_ReportId, usize>,
 }
 impl Tablet {
 	/// Create a new Tablet instance from the given RawTablet interface.
-	pub(crate) fn wrap(raw: RawTabletConnection) -> Result<Self, Error> {
+	pub(crate) fn wrap(
+		raw: RawTabletConnection,
+		info: Information) -> Result<Self, Error> {
+
 		let supported_reports = {
 			let report_list = unsafe {
 				let mut list = std::ptr::null_mut();
@@ -50,13 +100,13 @@ impl Tablet {
 				}
 			};
 
-			let capacity = report_list.as_ref().map(|a| a.len()).unwrap_or(0);
-			let mut supported = HashSet::with_capacity(capacity);
+			let mut supported = BTreeMap::new();
 			if let Some(report_list) = report_list {
 				for i in 0..report_list.len() {
 					if report_list[i] != 0 {
-						/* Mark this report type as being supported. */
-						supported.insert(i as _);
+						/* Mark this report type as being supported, and keep
+						 * its length around for validating raw accesses. */
+						supported.insert(i as _, report_list[i] as usize);
 					}
 				}
 			}
@@ -66,6 +116,7 @@ impl Tablet {
 
 		Ok(Self {
 			raw: Arc::new(raw),
+			info,
 			supported_reports
 		})
 	}
@@ -74,11 +125,103 @@ impl Tablet {
 	fn check_support(&self, report_id: stu_sys::tagWacomGSS_ReportId)
 		-> Result<(), Error> {
 
-		if self.supported_reports.contains(&report_id) {
-			Ok(())
-		} else {
-			Err(Error::ClientError(ClientError::UnsupportedReportId { report_id }))
-		}
+		self.report_length(report_id).map(|_| ())
+	}
+
+	/// Information about the device this tablet is connected to.
+	pub fn info(&self) -> Information {
+		self.info
+	}
+
+	/// Gets the length of the report with the given Report ID, in bytes, if it
+	/// is supported by this device.
+	fn report_length(&self, report_id: stu_sys::tagWacomGSS_ReportId)
+		-> Result<usize, Error> {
+
+		report_length(&self.supported_reports, report_id)
+	}
+
+	/// Checks whether a buffer has the right size for the report with the given
+	/// Report ID, returning the length of the report if it does.
+	fn check_report_buffer(
+		&self,
+		report_id: stu_sys::tagWacomGSS_ReportId,
+		buffer: usize) -> Result<usize, Error> {
+
+		check_report_buffer(&self.supported_reports, report_id, buffer)
+	}
+
+	/// The table of reports supported by this device.
+	///
+	/// Entries in this table are sorted by their Report IDs, and only reports
+	/// the device has declared support for are listed.
+	pub fn supported_reports(&self) -> impl Iterator<Item = SupportedReport> + '_ {
+		self.supported_reports
+			.iter()
+			.map(|(id, length)| SupportedReport {
+				id: *id,
+				length: *length
+			})
+	}
+
+	/// Reads the feature report with the given Report ID off the device.
+	///
+	/// The given buffer must be exactly as long as the report, as listed in the
+	/// table returned by [`supported_reports()`]. Following the convention for
+	/// HID feature reports, the first byte of the buffer holds the Report ID,
+	/// and will be set by this function before the report is requested.
+	///
+	/// This function gives access to functionality for which this structure
+	/// does not provide a wrapper. Prefer the dedicated functions whenever they
+	/// are available.
+	///
+	/// [`supported_reports()`]: Self::supported_reports
+	pub fn get_report(
+		&self,
+		report_id: stu_sys::tagWacomGSS_ReportId,
+		buffer: &mut [u8]) -> Result<(), Error> {
+
+		let length = self.check_report_buffer(report_id, buffer.len())?;
+		buffer[0] = report_id as u8;
+
+		let result = self.raw.dispatch(|interface| unsafe {
+			stu_sys::WacomGSS_Interface_get(
+				interface,
+				buffer.as_mut_ptr(),
+				length as _)
+		});
+		InternalError::from_wacom_stu(result)
+			.map_err(|what| what.into_report_error(report_id))
+	}
+
+	/// Writes the feature report with the given Report ID to the device.
+	///
+	/// The given buffer must be exactly as long as the report, as listed in the
+	/// table returned by [`supported_reports()`]. Following the convention for
+	/// HID feature reports, the first byte of the buffer must hold the Report
+	/// ID.
+	///
+	/// This function gives access to functionality for which this structure
+	/// does not provide a wrapper. Prefer the dedicated functions whenever they
+	/// are available.
+	///
+	/// [`supported_reports()`]: Self::supported_reports
+	pub fn set_report(
+		&self,
+		report_id: stu_sys::tagWacomGSS_ReportId,
+		buffer: &[u8]) -> Result<(), Error> {
+
+		let length = self.check_report_buffer(report_id, buffer.len())?;
+		check_report_id(report_id, buffer)?;
+
+		let result = self.raw.dispatch(|interface| unsafe {
+			stu_sys::WacomGSS_Interface_set(
+				interface,
+				buffer.as_ptr(),
+				length as _)
+		});
+		InternalError::from_wacom_stu(result)
+			.map_err(|what| what.into_report_error(report_id))
 	}
 
 	/// Clear the screen of the device.
@@ -108,6 +251,35 @@ impl Tablet {
 			.map_err(InternalError::unwrap_to_general)
 	}
 
+	/// Restricts inking on the display to the given rectangular area, given in
+	/// pixels, with its origin in the top left corner of the screen.
+	pub fn inking_area(
+		&self,
+		x: u32,
+		y: u32,
+		width: u32,
+		height: u32) -> Result<(), Error> {
+
+		self.check_support(stu_sys::tagWacomGSS_ReportId_WacomGSS_ReportId_HandwritingDisplayArea)?;
+
+		let mut area = unsafe {
+			std::mem::zeroed::<stu_sys::WacomGSS_Rectangle>()
+		};
+		area.upperLeftXpixel = x.min(u32::from(u16::MAX)) as u16;
+		area.upperLeftYpixel = y.min(u32::from(u16::MAX)) as u16;
+		area.lowerRightXpixel = x.saturating_add(width).min(u32::from(u16::MAX)) as u16;
+		area.lowerRightYpixel = y.saturating_add(height).min(u32::from(u16::MAX)) as u16;
+
+		let result = self.raw.dispatch(|interface| unsafe {
+			stu_sys::WacomGSS_Protocol_setHandwritingDisplayArea(
+				interface,
+				std::mem::size_of::<stu_sys::WacomGSS_Rectangle>() as _,
+				&area)
+		});
+		InternalError::from_wacom_stu(result)
+			.map_err(InternalError::unwrap_to_general)
+	}
+
 	/// Get information on the layout and the capabilities of the device.
 	pub fn capability(&self) -> Result<Capability, Error> {
 		self.check_support(stu_sys::tagWacomGSS_ReportId_WacomGSS_ReportId_Capability)?;
@@ -131,10 +303,176 @@ impl Tablet {
 			display_height: u32::from(capability.screenHeight),
 			input_width: u32::from(capability.tabletMaxX),
 			input_height: u32::from(capability.tabletMaxY),
-			input_depth: u32::from(capability.tabletMaxPressure)
+			input_depth: u32::from(capability.tabletMaxPressure),
+			input_resolution: u32::from(capability.resolution),
+			encoding: capability.encodingFlag
 		})
 	}
 
+	/// Get the best encoding mode for images supported by the device.
+	pub fn encoding(&self) -> Result<EncodingMode, Error> {
+		let caps = self.capability()?;
+
+		/* Older devices don't report their encoding flags, so have the API
+		 * fill those in from what is known about their models. */
+		let flags = unsafe {
+			let mut flags = 0;
+			InternalError::from_wacom_stu({
+				stu_sys::WacomGSS_ProtocolHelper_simulateEncodingFlag(
+					self.info.product(),
+					caps.encoding,
+					&mut flags)
+			}).map_err(InternalError::unwrap_to_general)?;
+
+			flags
+		};
+
+		Ok(EncodingMode::from_flags(flags))
+	}
+
+	/// Checks whether an image can be displayed on the screen of the device.
+	fn check_image(&self, image: &DeviceImage) -> Result<(), Error> {
+		let caps = self.capability()?;
+		if image.width() != caps.width() || image.height() != caps.height() {
+			return Err(Error::ClientError(ClientError::InvalidImageDimensions {
+				expected: (caps.width(), caps.height()),
+				found: (image.width(), image.height())
+			}))
+		}
+
+		Ok(())
+	}
+
+	/// Display the given image on the screen of the device.
+	///
+	/// The image must have the same dimensions as the screen of the device.
+	pub fn write_image(&self, image: &DeviceImage) -> Result<(), Error> {
+		self.check_support(stu_sys::tagWacomGSS_ReportId_WacomGSS_ReportId_StartImageData)?;
+		self.check_image(image)?;
+
+		let result = self.raw.dispatch(|interface| unsafe {
+			stu_sys::WacomGSS_ProtocolHelper_writeImage(
+				interface,
+				image.mode().to_wacom_stu(),
+				image.data().as_ptr(),
+				image.data().len() as _)
+		});
+		InternalError::from_wacom_stu(result)
+			.map_err(InternalError::unwrap_to_general)
+	}
+
+	/// List the state of all of the image slots in the onboard storage of the
+	/// device.
+	pub fn screens(&self) -> Result<Vec<StoredScreen>, Error> {
+		self.check_support(stu_sys::tagWacomGSS_ReportId_WacomGSS_ReportId_RomImageHash)?;
+
+		let mut screens = Vec::new();
+		for kind in ScreenKind::ALL.iter().copied() {
+			for slot in ScreenSlot::all(kind) {
+				/* Select the slot we want the hash of, then read it back. */
+				let result = self.raw.dispatch(|interface| unsafe {
+					stu_sys::WacomGSS_Protocol_setRomImageHash(
+						interface,
+						kind.to_wacom_stu(),
+						false,
+						slot.number())
+				});
+				InternalError::from_wacom_stu(result)
+					.map_err(InternalError::unwrap_to_general)?;
+
+				let hash = unsafe {
+					let mut hash = std::mem::zeroed::<stu_sys::WacomGSS_RomImageHash>();
+
+					let result = self.raw.dispatch(|interface| {
+						stu_sys::WacomGSS_Protocol_getRomImageHash(
+							interface,
+							std::mem::size_of::<stu_sys::WacomGSS_RomImageHash>() as _,
+							&mut hash)
+					});
+					InternalError::from_wacom_stu(result)
+						.map_err(InternalError::unwrap_to_general)?;
+
+					hash
+				};
+
+				/* A non-zero result indicates the slot holds no image. */
+				let hash = if hash.result == 0 {
+					Some(hash.hash)
+				} else {
+					None
+				};
+				screens.push(StoredScreen::new(slot, hash));
+			}
+		}
+
+		Ok(screens)
+	}
+
+	/// Write the given image to a slot in the onboard storage of the device,
+	/// replacing the image that was previously kept in it.
+	///
+	/// The image must have the same dimensions as the screen of the device.
+	/// Keep in mind that the onboard storage of the device has a limited number
+	/// of write cycles, so this function should not be called more often than
+	/// it is needed.
+	pub fn write_screen(&self, slot: ScreenSlot, image: &DeviceImage)
+		-> Result<(), Error> {
+
+		self.check_support(stu_sys::tagWacomGSS_ReportId_WacomGSS_ReportId_RomStartImageData)?;
+		self.check_image(image)?;
+
+		let mut start = unsafe {
+			std::mem::zeroed::<stu_sys::WacomGSS_RomStartImageData>()
+		};
+		start.encodingMode = image.mode().to_wacom_stu();
+		start.operationModeType = slot.kind().to_wacom_stu();
+		start.imageType = false;
+		start.imageNumber = slot.number();
+
+		let result = self.raw.dispatch(|interface| unsafe {
+			stu_sys::WacomGSS_ProtocolHelper_writeRomImage(
+				interface,
+				std::mem::size_of::<stu_sys::WacomGSS_RomStartImageData>() as _,
+				&start,
+				image.data().as_ptr(),
+				image.data().len() as _)
+		});
+		InternalError::from_wacom_stu(result)
+			.map_err(InternalError::unwrap_to_general)
+	}
+
+	/// Delete the image kept in a slot in the onboard storage of the device.
+	pub fn delete_screen(&self, slot: ScreenSlot) -> Result<(), Error> {
+		self.check_support(stu_sys::tagWacomGSS_ReportId_WacomGSS_ReportId_RomImageDelete)?;
+
+		let result = self.raw.dispatch(|interface| unsafe {
+			stu_sys::WacomGSS_Protocol_setRomImageDelete(
+				interface,
+				stu_sys::tagWacomGSS_RomImageDeleteMode_WacomGSS_RomImageDeleteMode_Single as _,
+				slot.kind().to_wacom_stu(),
+				false,
+				slot.number())
+		});
+		InternalError::from_wacom_stu(result)
+			.map_err(InternalError::unwrap_to_general)
+	}
+
+	/// Select the image kept in a slot in the onboard storage of the device,
+	/// having it be displayed on the screen.
+	pub fn select_screen(&self, slot: ScreenSlot) -> Result<(), Error> {
+		self.check_support(stu_sys::tagWacomGSS_ReportId_WacomGSS_ReportId_RomImageDisplay)?;
+
+		let result = self.raw.dispatch(|interface| unsafe {
+			stu_sys::WacomGSS_Protocol_setRomImageDisplay(
+				interface,
+				slot.kind().to_wacom_stu(),
+				false,
+				slot.number())
+		});
+		InternalError::from_wacom_stu(result)
+			.map_err(InternalError::unwrap_to_general)
+	}
+
 	/// Opens a queue with which to receive events from the tablet.
 	pub fn queue(&self) -> Result<Queue, Error> {
 		let caps = self.capability()?;
@@ -142,6 +480,84 @@ impl Tablet {
 	}
 }
 
+/// Gets the length of the report with the given Report ID, in bytes, from the
+/// given table of reports supported by a device.
+fn report_length(
+	supported: &BTreeMap<stu_sys::tagWacomGSS_ReportId, usize>,
+	report_id: stu_sys::tagWacomGSS_ReportId) -> Result<usize, Error> {
+
+	match supported.get(&report_id) {
+		Some(length) => Ok(*length),
+		None => Err(Error::ClientError(ClientError::UnsupportedReportId { report_id }))
+	}
+}
+
+/// Checks whether a buffer has the right size for the report with the given
+/// Report ID, in the given table of reports supported by a device, returning
+/// the length of the report if it does.
+fn check_report_buffer(
+	supported: &BTreeMap<stu_sys::tagWacomGSS_ReportId, usize>,
+	report_id: stu_sys::tagWacomGSS_ReportId,
+	buffer: usize) -> Result<usize, Error> {
+
+	let length = report_length(supported, report_id)?;
+	if buffer != length {
+		return Err(Error::ClientError(ClientError::InvalidReportLength {
+			report_id,
+			expected: length,
+			found: buffer
+		}))
+	}
+
+	Ok(length)
+}
+
+/// Checks whether a buffer to be written as the report with the given Report ID
+/// starts with that Report ID.
+fn check_report_id(
+	report_id: stu_sys::tagWacomGSS_ReportId,
+	buffer: &[u8]) -> Result<(), Error> {
+
+	match buffer.first() {
+		Some(found) if stu_sys::tagWacomGSS_ReportId::from(*found) == report_id => Ok(()),
+		found => Err(Error::ClientError(ClientError::MismatchedReportId {
+			report_id,
+			found: found.copied().unwrap_or(0)
+		}))
+	}
+}
+
+/// An entry in the table of reports supported by a device.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SupportedReport {
+	/// The Report ID of this report.
+	id: stu_sys::tagWacomGSS_ReportId,
+	/// The length of this report, in bytes.
+	length: usize,
+}
+impl SupportedReport {
+	/// The Report ID of this report.
+	pub fn id(&self) -> stu_sys::tagWacomGSS_ReportId {
+		self.id
+	}
+
+	/// The length of this report, in bytes, including the leading Report ID.
+	pub fn length(&self) -> usize {
+		self.length
+	}
+}
+
+/// The number of millimeters in an inch.
+const MILLIMETERS_PER_INCH: f64 = 25.4;
+
+/// The resolution of the input grid assumed for devices whose capability
+/// report leaves the resolution field unset, in lines per inch.
+///
+/// This is a pitch of a hundredth of a millimeter, which is what the active
+/// area sizes in the STU data sheets work out to when divided by the size of
+/// the input grid the devices report.
+const FALLBACK_INPUT_RESOLUTION: u32 = 2540;
+
 /// The set of capabilities reported by the device.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Capability {
@@ -155,8 +571,36 @@ pub struct Capability {
 	input_height: u32,
 	/// The depth (of pressures) of the input polling grid.
 	input_depth: u32,
+	/// The resolution of the input polling grid, in lines per inch, or zero
+	/// if the device did not report it.
+	input_resolution: u32,
+	/// The image encoding flags reported by the device.
+	encoding: u8,
 }
 impl Capability {
+	/// Describes a device with the given layout, for when there is no device
+	/// at hand to report its own.
+	///
+	/// The display is given by its width and height, in pixels, the input grid
+	/// by its width, height and depth, and its resolution in lines per inch,
+	/// with zero standing for a resolution that is not known.
+	pub fn new(
+		display: (u32, u32),
+		input: (u32, u32, u32),
+		input_resolution: u32,
+		encoding: u8) -> Self {
+
+		Self {
+			display_width: display.0,
+			display_height: display.1,
+			input_width: input.0,
+			input_height: input.1,
+			input_depth: input.2,
+			input_resolution,
+			encoding
+		}
+	}
+
 	/// Width of the display screen, in pixels.
 	pub fn width(&self) -> u32 {
 		self.display_width
@@ -192,6 +636,39 @@ impl Capability {
 	pub fn input_grid_pressure(&self) -> u32 {
 		self.input_depth
 	}
+
+	/// The resolution of the input grid, in lines per inch.
+	///
+	/// This is the value reported by the device, unless it left it unset, in
+	/// which case [`FALLBACK_INPUT_RESOLUTION`] is assumed.
+	pub fn input_grid_resolution(&self) -> u32 {
+		if self.input_resolution == 0 {
+			FALLBACK_INPUT_RESOLUTION
+		} else {
+			self.input_resolution
+		}
+	}
+
+	/// The distance between adjacent cells of the input grid, in millimeters.
+	fn input_grid_pitch(&self) -> f64 {
+		MILLIMETERS_PER_INCH / f64::from(self.input_grid_resolution())
+	}
+
+	/// The physical width of the input grid, in millimeters.
+	///
+	/// The input grid covers the whole of the display screen, so this is also
+	/// the physical width of the screen.
+	pub fn physical_width(&self) -> f64 {
+		f64::from(self.input_width) * self.input_grid_pitch()
+	}
+
+	/// The physical height of the input grid, in millimeters.
+	///
+	/// The input grid covers the whole of the display screen, so this is also
+	/// the physical height of the screen.
+	pub fn physical_height(&self) -> f64 {
+		f64::from(self.input_height) * self.input_grid_pitch()
+	}
 }
 
 /// A wrapper around a a handle to an interface.
@@ -219,7 +696,7 @@ impl Drop for RawTabletConnection {
 }
 
 /// The structure containing information about a device.
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Information {
 	id_vendor: u16,
 	id_product: u16,
@@ -261,6 +738,7 @@ impl Connector {
 
 	/// Try to connect to the device this connector is targeting.
 	pub fn connect(self) -> Result<Tablet, Error> {
+		let info = self.info();
 		let interface = unsafe {
 			let mut interface = std::mem::zeroed();
 			InternalError::from_wacom_stu({
@@ -274,9 +752,11 @@ impl Connector {
 			interface
 		};
 
-		Tablet::wrap(RawTabletConnection {
-			interface: Mutex::new(interface)
-		})
+		Tablet::wrap(
+			RawTabletConnection {
+				interface: Mutex::new(interface)
+			},
+			info)
 	}
 }
 
@@ -326,4 +806,89 @@ pub fn list_devices() -> Connectors {
 		values: devices,
 		index: 0
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A table of supported reports with a single report, `0x21`, which is
+	/// two bytes long.
+	fn table() -> BTreeMap<stu_sys::tagWacomGSS_ReportId, usize> {
+		let mut table = BTreeMap::new();
+		table.insert(0x21, 2);
+		table
+	}
+
+	#[test]
+	fn report_buffer_length_is_validated() {
+		let table = table();
+		assert_eq!(check_report_buffer(&table, 0x21, 2).unwrap(), 2);
+
+		match check_report_buffer(&table, 0x21, 3) {
+			Err(Error::ClientError(ClientError::InvalidReportLength { report_id, expected, found })) => {
+				assert_eq!(report_id, 0x21);
+				assert_eq!(expected, 2);
+				assert_eq!(found, 3);
+			},
+			other => panic!("unexpected result: {:?}", other)
+		}
+	}
+
+	#[test]
+	fn unsupported_reports_are_rejected() {
+		match check_report_buffer(&table(), 0x22, 2) {
+			Err(Error::ClientError(ClientError::UnsupportedReportId { report_id })) =>
+				assert_eq!(report_id, 0x22),
+			other => panic!("unexpected result: {:?}", other)
+		}
+	}
+
+	#[test]
+	fn report_id_must_lead_the_buffer() {
+		assert!(check_report_id(0x21, &[0x21, 0x01]).is_ok());
+
+		match check_report_id(0x21, &[0x20, 0x01]) {
+			Err(Error::ClientError(ClientError::MismatchedReportId { report_id, found })) => {
+				assert_eq!(report_id, 0x21);
+				assert_eq!(found, 0x20);
+			},
+			other => panic!("unexpected result: {:?}", other)
+		}
+		assert!(check_report_id(0x21, &[]).is_err());
+	}
+
+	#[test]
+	fn supported_reports_keep_their_length() {
+		let report = SupportedReport { id: 0x21, length: 2 };
+		assert_eq!(report.id(), 0x21);
+		assert_eq!(report.length(), 2);
+	}
+
+	/// A capability with the given input grid width and resolution.
+	fn capability(input_width: u32, input_resolution: u32) -> Capability {
+		Capability {
+			display_width: 800,
+			display_height: 480,
+			input_width,
+			input_height: input_width / 2,
+			input_depth: 1023,
+			input_resolution,
+			encoding: 0
+		}
+	}
+
+	#[test]
+	fn physical_size_follows_the_reported_resolution() {
+		let caps = capability(10800, 1270);
+		assert_eq!(caps.input_grid_resolution(), 1270);
+		assert!((caps.physical_width() - 216.0).abs() < 1e-9);
+		assert!((caps.physical_height() - 108.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn physical_size_falls_back_when_resolution_is_unset() {
+		let caps = capability(10800, 0);
+		assert_eq!(caps.input_grid_resolution(), FALLBACK_INPUT_RESOLUTION);
+		assert!((caps.physical_width() - 108.0).abs() < 1e-9);
+	}
+}
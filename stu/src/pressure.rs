@@ -0,0 +1,322 @@
+use crate::{Event, EventFilter};
+
+/// A response curve, mapping the pressure reported by the device to the
+/// pressure reported to the application.
+///
+/// Both the input and the output of a curve are in the normalized coordinate
+/// system used by [`Event::pressure()`], and outputs are always clamped to it.
+/// Curves may be used as filters, in which case they remap the pressure of all
+/// of the events going through them.
+///
+/// [`Event::pressure()`]: Event::pressure
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Shape", into = "Shape"))]
+pub struct PressureCurve {
+	/// The shape of the curve.
+	shape: Shape,
+}
+impl PressureCurve {
+	/// The curve that leaves pressures unchanged.
+	pub fn linear() -> Self {
+		Self { shape: Shape::Linear }
+	}
+
+	/// A curve that raises pressures to the given power.
+	///
+	/// Exponents smaller than one make light strokes heavier, while exponents
+	/// greater than one make heavy strokes lighter. Exponents that are not
+	/// strictly positive are treated as one.
+	pub fn gamma(exponent: f64) -> Self {
+		let exponent = if exponent > 0.0 && exponent.is_finite() {
+			exponent
+		} else {
+			1.0
+		};
+
+		Self { shape: Shape::Gamma(exponent) }
+	}
+
+	/// A curve that linearly interpolates between the given points, given as
+	/// pairs of input and output pressures.
+	///
+	/// Inputs that lie before the first point or after the last point map to
+	/// the outputs of those points, respectively. If no points are given, the
+	/// resulting curve is linear.
+	pub fn piecewise<I>(points: I) -> Self
+		where I: IntoIterator<Item = (f64, f64)> {
+
+		let mut points = points.into_iter()
+			.filter(|(x, y)| x.is_finite() && y.is_finite())
+			.map(|(x, y)| (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)))
+			.collect::<Vec<_>>();
+		if points.is_empty() {
+			return Self::linear()
+		}
+
+		points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+		Self { shape: Shape::Piecewise(points) }
+	}
+
+	/// A cubic Bézier curve from `(0, 0)` to `(1, 1)`, shaped by the two given
+	/// control points, given as pairs of input and output pressures.
+	///
+	/// This is the same kind of curve used by most pen tablet drivers for
+	/// their pressure settings. The input coordinates of the control points
+	/// are clamped to the `[0, 1]` range, so that the curve is always a
+	/// function of the input pressure.
+	pub fn bezier(first: (f64, f64), second: (f64, f64)) -> Self {
+		let clamp = |(x, y): (f64, f64)| (
+			if x.is_finite() { x.clamp(0.0, 1.0) } else { 0.0 },
+			if y.is_finite() { y.clamp(0.0, 1.0) } else { 0.0 });
+
+		Self { shape: Shape::Bezier(clamp(first), clamp(second)) }
+	}
+
+	/// Maps the given pressure through this curve.
+	pub fn apply(&self, pressure: f64) -> f64 {
+		let p = pressure.clamp(0.0, 1.0);
+		let mapped = match &self.shape {
+			Shape::Linear => p,
+			Shape::Gamma(exponent) => p.powf(*exponent),
+			Shape::Piecewise(points) => {
+				let first = points[0];
+				let last = points[points.len() - 1];
+
+				if p <= first.0 {
+					first.1
+				} else if p >= last.0 {
+					last.1
+				} else {
+					let i = points.iter()
+						.position(|(x, _)| *x > p)
+						.unwrap();
+					let (x0, y0) = points[i - 1];
+					let (x1, y1) = points[i];
+
+					y0 + (y1 - y0) * (p - x0) / (x1 - x0)
+				}
+			},
+			Shape::Bezier(a, b) => {
+				/* The curve is monotonic along its input axis, so we can find
+				 * the parameter for the given input pressure by bisection. */
+				let bezier = |t: f64, p1: f64, p2: f64| {
+					let s = 1.0 - t;
+					3.0 * s * s * t * p1 + 3.0 * s * t * t * p2 + t * t * t
+				};
+
+				let mut low = 0.0;
+				let mut high = 1.0;
+				for _ in 0..48 {
+					let t = (low + high) / 2.0;
+					if bezier(t, a.0, b.0) < p {
+						low = t;
+					} else {
+						high = t;
+					}
+				}
+
+				bezier((low + high) / 2.0, a.1, b.1)
+			}
+		};
+
+		mapped.clamp(0.0, 1.0)
+	}
+}
+impl Default for PressureCurve {
+	fn default() -> Self {
+		Self::linear()
+	}
+}
+impl EventFilter for PressureCurve {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		Some(Event::new(
+			event.time(),
+			event.x(),
+			event.y(),
+			self.apply(event.pressure()),
+			event.touching(),
+			event.hovering()))
+	}
+}
+impl From<Shape> for PressureCurve {
+	fn from(shape: Shape) -> Self {
+		/* Go through the constructors, so that shapes coming from outside of
+		 * this module get validated. */
+		match shape {
+			Shape::Linear => Self::linear(),
+			Shape::Gamma(exponent) => Self::gamma(exponent),
+			Shape::Piecewise(points) => Self::piecewise(points),
+			Shape::Bezier(first, second) => Self::bezier(first, second),
+		}
+	}
+}
+impl From<PressureCurve> for Shape {
+	fn from(curve: PressureCurve) -> Self {
+		curve.shape
+	}
+}
+
+/// The shapes a pressure curve may take.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+enum Shape {
+	/// The identity function.
+	Linear,
+	/// A power function, with the given exponent.
+	Gamma(f64),
+	/// A piecewise linear function, through the given points, sorted by input.
+	Piecewise(Vec<(f64, f64)>),
+	/// A cubic Bézier curve, with the given control points.
+	Bezier((f64, f64), (f64, f64)),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Whether the given values are equal, to within rounding.
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-9
+	}
+
+	#[test]
+	fn linear_leaves_pressures_unchanged() {
+		let curve = PressureCurve::linear();
+		for p in [0.0, 0.25, 0.5, 1.0] {
+			assert_eq!(curve.apply(p), p);
+		}
+		assert_eq!(curve.apply(-0.5), 0.0);
+		assert_eq!(curve.apply(1.5), 1.0);
+		assert_eq!(PressureCurve::default(), curve);
+	}
+
+	#[test]
+	fn gamma_raises_to_exponent() {
+		let curve = PressureCurve::gamma(2.0);
+		assert!(close(curve.apply(0.5), 0.25));
+		assert!(close(curve.apply(0.0), 0.0));
+		assert!(close(curve.apply(1.0), 1.0));
+
+		let curve = PressureCurve::gamma(0.5);
+		assert!(close(curve.apply(0.25), 0.5));
+	}
+
+	#[test]
+	fn gamma_rejects_invalid_exponents() {
+		for exponent in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+			assert_eq!(PressureCurve::gamma(exponent), PressureCurve::gamma(1.0), "{}", exponent);
+		}
+	}
+
+	#[test]
+	fn piecewise_interpolates_between_points() {
+		let curve = PressureCurve::piecewise(vec![(0.2, 0.1), (0.6, 0.9)]);
+		assert!(close(curve.apply(0.4), 0.5));
+		assert!(close(curve.apply(0.3), 0.3));
+	}
+
+	#[test]
+	fn piecewise_holds_outside_points() {
+		let curve = PressureCurve::piecewise(vec![(0.2, 0.1), (0.6, 0.9)]);
+		assert!(close(curve.apply(0.0), 0.1));
+		assert!(close(curve.apply(0.2), 0.1));
+		assert!(close(curve.apply(0.6), 0.9));
+		assert!(close(curve.apply(1.0), 0.9));
+	}
+
+	#[test]
+	fn piecewise_clamps_and_sorts_points() {
+		let curve = PressureCurve::piecewise(vec![(2.0, 3.0), (-1.0, -1.0), (0.5, 0.25)]);
+		assert_eq!(curve, PressureCurve::piecewise(vec![(0.0, 0.0), (0.5, 0.25), (1.0, 1.0)]));
+		assert!(close(curve.apply(0.75), 0.625));
+	}
+
+	#[test]
+	fn piecewise_without_points_is_linear() {
+		assert_eq!(PressureCurve::piecewise(vec![]), PressureCurve::linear());
+		assert_eq!(
+			PressureCurve::piecewise(vec![(f64::NAN, 0.5), (0.5, f64::INFINITY)]),
+			PressureCurve::linear());
+	}
+
+	#[test]
+	fn bezier_keeps_endpoints() {
+		for (first, second) in [((0.0, 0.0), (1.0, 1.0)), ((0.1, 0.9), (0.2, 1.0)), ((0.9, 0.0), (1.0, 0.1))] {
+			let curve = PressureCurve::bezier(first, second);
+			assert!(curve.apply(0.0).abs() < 1e-6);
+			assert!((curve.apply(1.0) - 1.0).abs() < 1e-6);
+		}
+	}
+
+	#[test]
+	fn bezier_along_diagonal_is_linear() {
+		let curve = PressureCurve::bezier((1.0 / 3.0, 1.0 / 3.0), (2.0 / 3.0, 2.0 / 3.0));
+		for i in 0..=10 {
+			let p = f64::from(i) / 10.0;
+			assert!((curve.apply(p) - p).abs() < 1e-6, "{}", p);
+		}
+	}
+
+	#[test]
+	fn bezier_is_monotonic() {
+		for (first, second) in [((0.1, 0.9), (0.2, 1.0)), ((0.9, 0.0), (1.0, 0.1)), ((0.5, 0.0), (0.5, 1.0))] {
+			let curve = PressureCurve::bezier(first, second);
+			let mut last = 0.0;
+			for i in 0..=100 {
+				let value = curve.apply(f64::from(i) / 100.0);
+				assert!(value >= last - 1e-9, "{:?} {:?} at {}", first, second, i);
+				last = value;
+			}
+		}
+
+		/* Light strokes get heavier when the control points lie above the
+		 * diagonal. */
+		assert!(PressureCurve::bezier((0.1, 0.9), (0.2, 1.0)).apply(0.5) > 0.5);
+	}
+
+	#[test]
+	fn bezier_clamps_control_points() {
+		assert_eq!(
+			PressureCurve::bezier((-1.0, 2.0), (f64::NAN, 0.5)),
+			PressureCurve::bezier((0.0, 1.0), (0.0, 0.5)));
+	}
+
+	#[test]
+	fn shapes_are_validated() {
+		/* Deserialized curves are built from their shapes. */
+		assert_eq!(PressureCurve::from(Shape::Gamma(-2.0)), PressureCurve::gamma(1.0));
+		assert_eq!(PressureCurve::from(Shape::Piecewise(vec![])), PressureCurve::linear());
+		assert_eq!(
+			PressureCurve::from(Shape::Piecewise(vec![(1.0, 0.0), (0.0, 2.0)])),
+			PressureCurve::piecewise(vec![(0.0, 1.0), (1.0, 0.0)]));
+		assert_eq!(
+			PressureCurve::from(Shape::Bezier((2.0, 0.5), (0.5, -1.0))),
+			PressureCurve::bezier((1.0, 0.5), (0.5, 0.0)));
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn deserialized_curves_are_validated() {
+		let curve: PressureCurve = serde_json::from_str(r#"{"gamma":-2.0}"#).unwrap();
+		assert_eq!(curve, PressureCurve::gamma(1.0));
+
+		let curve: PressureCurve = serde_json::from_str(r#"{"piecewise":[[1.0,0.0],[0.0,2.0]]}"#).unwrap();
+		assert_eq!(curve, PressureCurve::piecewise(vec![(0.0, 1.0), (1.0, 0.0)]));
+
+		let curve = PressureCurve::bezier((0.1, 0.9), (0.2, 1.0));
+		let json = serde_json::to_string(&curve).unwrap();
+		assert_eq!(serde_json::from_str::<PressureCurve>(&json).unwrap(), curve);
+	}
+
+	#[test]
+	fn filters_remap_pressure_only() {
+		let event = Event::new(std::time::Instant::now(), 0.25, 0.75, 0.5, true, true);
+		let filtered = PressureCurve::gamma(2.0).filter(event).unwrap();
+
+		assert!(close(filtered.pressure(), 0.25));
+		assert_eq!((filtered.time(), filtered.x(), filtered.y()), (event.time(), event.x(), event.y()));
+		assert_eq!((filtered.touching(), filtered.hovering()), (true, true));
+	}
+}
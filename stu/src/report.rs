@@ -1,4 +1,4 @@
-use crate::{Tablet, Error, Capability, RawTabletConnection};
+use crate::{Tablet, Error, Capability, RawTabletConnection, EventFilter, FilteredQueue};
 use crate::error::{InternalError, ClientError};
 use crate::handle::Handle;
 use std::collections::VecDeque;
@@ -30,6 +30,29 @@ pub struct Event {
 	close: bool,
 }
 impl Event {
+	/// Creates a new event from its parts.
+	///
+	/// The position and the pressure are given in the normalized coordinate
+	/// system used by events, and are clamped to it.
+	pub fn new(
+		timestamp: Instant,
+		x: f64,
+		y: f64,
+		pressure: f64,
+		touching: bool,
+		hovering: bool) -> Self {
+
+		Self {
+			timestamp,
+			position: (
+				x.clamp(0.0, 1.0),
+				y.clamp(0.0, 1.0),
+				pressure.clamp(0.0, 1.0)),
+			touching,
+			close: hovering
+		}
+	}
+
 	/// The point in time in which this event was generated.
 	pub fn time(&self) -> Instant {
 		self.timestamp
@@ -231,6 +254,13 @@ impl Queue {
 		self.handler.queue.pop_front()
 			.ok_or(Error::ClientError(ClientError::InvalidReport))
 	}
+
+	/// Applies the given filter to the events coming out of this queue.
+	pub fn filtered<F>(self, filter: F) -> FilteredQueue<F>
+		where F: EventFilter {
+
+		FilteredQueue::new(self, filter)
+	}
 }
 
 /// The raw type holding a pointer to a Wacom STU API queue.
@@ -0,0 +1,99 @@
+/// The roles an image kept in the onboard storage of a device may play.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ScreenKind {
+	/// The image displayed by the device while it is starting up.
+	Startup,
+	/// The image displayed by the device while no application is using it.
+	Idle,
+}
+impl ScreenKind {
+	/// All of the kinds of screens, in the order they are listed in.
+	pub const ALL: [Self; 2] = [Self::Startup, Self::Idle];
+
+	/// The number of slots available to images of this kind.
+	pub fn slots(self) -> u8 {
+		match self {
+			Self::Startup => 1,
+			Self::Idle => 10,
+		}
+	}
+
+	/// The Wacom STU operation mode under which images of this kind are kept.
+	pub(crate) fn to_wacom_stu(self) -> u8 {
+		let mode = match self {
+			Self::Startup => stu_sys::tagWacomGSS_OperationModeType_WacomGSS_OperationModeType_Normal,
+			Self::Idle => stu_sys::tagWacomGSS_OperationModeType_WacomGSS_OperationModeType_SlideShow,
+		};
+		mode as u8
+	}
+}
+
+/// A slot in the onboard storage of a device, in which an image may be kept.
+///
+/// Slots are only available on models with onboard storage, such as the
+/// STU-540. On those devices, images written to a slot persist across power
+/// cycles, until they get either replaced or deleted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ScreenSlot {
+	/// The role of the image kept in this slot.
+	kind: ScreenKind,
+	/// The index of this slot among the slots of the same kind.
+	number: u8,
+}
+impl ScreenSlot {
+	/// Creates a handle to the slot with the given number for images of the
+	/// given kind, if such a slot exists.
+	pub fn new(kind: ScreenKind, number: u8) -> Option<Self> {
+		if number < kind.slots() {
+			Some(Self { kind, number })
+		} else {
+			None
+		}
+	}
+
+	/// Iterates over all of the slots available to images of the given kind.
+	pub fn all(kind: ScreenKind) -> impl Iterator<Item = Self> {
+		(0..kind.slots()).map(move |number| Self { kind, number })
+	}
+
+	/// The role of the image kept in this slot.
+	pub fn kind(&self) -> ScreenKind {
+		self.kind
+	}
+
+	/// The index of this slot among the slots of the same kind.
+	pub fn number(&self) -> u8 {
+		self.number
+	}
+}
+
+/// The state of a slot in the onboard storage of a device.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct StoredScreen {
+	/// The slot this state refers to.
+	slot: ScreenSlot,
+	/// The MD5 hash of the image kept in the slot, if any.
+	hash: Option<[u8; 16]>,
+}
+impl StoredScreen {
+	/// Creates a new record of the state of a slot.
+	pub(crate) fn new(slot: ScreenSlot, hash: Option<[u8; 16]>) -> Self {
+		Self { slot, hash }
+	}
+
+	/// The slot this state refers to.
+	pub fn slot(&self) -> ScreenSlot {
+		self.slot
+	}
+
+	/// Whether there are no images kept in this slot.
+	pub fn is_empty(&self) -> bool {
+		self.hash.is_none()
+	}
+
+	/// The MD5 hash of the image kept in this slot, as computed by the device,
+	/// if there is an image kept in it.
+	pub fn hash(&self) -> Option<[u8; 16]> {
+		self.hash
+	}
+}
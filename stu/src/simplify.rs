@@ -0,0 +1,328 @@
+use crate::{Event, Stroke, Capability, Transform};
+use std::collections::BinaryHeap;
+use std::cmp::Ordering;
+
+/// The largest distance a simplified stroke may stray from the original one.
+///
+/// Distances may either be measured in the normalized coordinate system used
+/// by [`Event`], or in the units of the input grid of a device. The latter is
+/// usually preferable, as the normalized coordinate system stretches distances
+/// differently along each axis on devices whose screens aren't square.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tolerance {
+	/// The largest allowed distance, in the scaled coordinate system.
+	distance: f64,
+	/// The scale applied to normalized coordinates along each axis before
+	/// distances are measured.
+	scale: (f64, f64),
+}
+impl Tolerance {
+	/// A tolerance given in the normalized coordinate system used by events.
+	pub fn normalized(distance: f64) -> Self {
+		Self { distance: distance.max(0.0), scale: (1.0, 1.0) }
+	}
+
+	/// A tolerance given in units of the input grid of a device with the given
+	/// capabilities, for events in the orientation given by the transform.
+	pub fn device(distance: f64, caps: &Capability, transform: Transform) -> Self {
+		let (width, height) = transform.dimensions(
+			caps.input_grid_width(),
+			caps.input_grid_height());
+
+		Self {
+			distance: distance.max(0.0),
+			scale: (f64::from(width), f64::from(height))
+		}
+	}
+
+	/// The position of the given event in the scaled coordinate system.
+	fn point(&self, event: &Event) -> (f64, f64) {
+		(event.x() * self.scale.0, event.y() * self.scale.1)
+	}
+}
+
+/// The algorithms available for simplifying strokes.
+///
+/// Simplification only ever drops events, so the events that are kept still
+/// carry their original pressure and timing. The first and the last events of
+/// a stroke are always kept.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Simplifier {
+	/// The Ramer-Douglas-Peucker algorithm, which keeps every event whose
+	/// distance to the simplified stroke would exceed the tolerance, and so
+	/// bounds the distance between the two strokes.
+	RamerDouglasPeucker,
+	/// The Visvalingam-Whyatt algorithm, which repeatedly drops the event that
+	/// forms the triangle of smallest area with its neighbours, for as long as
+	/// that area is smaller than the square of the tolerance. This tends to
+	/// give smoother results than Ramer-Douglas-Peucker for the same number of
+	/// events, but does not strictly bound the distance between strokes.
+	VisvalingamWhyatt,
+}
+impl Simplifier {
+	/// Simplifies the given run of events, returning the events that are kept.
+	pub fn simplify(self, events: &[Event], tolerance: Tolerance) -> Vec<Event> {
+		if events.len() <= 2 {
+			return events.to_vec()
+		}
+
+		let points = events.iter()
+			.map(|event| tolerance.point(event))
+			.collect::<Vec<_>>();
+		let keep = match self {
+			Self::RamerDouglasPeucker => ramer_douglas_peucker(&points[..], tolerance.distance),
+			Self::VisvalingamWhyatt => visvalingam_whyatt(&points[..], tolerance.distance),
+		};
+
+		events.iter()
+			.zip(keep)
+			.filter(|(_, keep)| *keep)
+			.map(|(event, _)| *event)
+			.collect()
+	}
+}
+
+impl Stroke {
+	/// Simplifies this stroke with the given algorithm and tolerance.
+	pub fn simplified(&self, simplifier: Simplifier, tolerance: Tolerance) -> Stroke {
+		Stroke::split(simplifier.simplify(self.events(), tolerance))
+			.pop()
+			.expect("simplified strokes always have at least one event")
+	}
+}
+
+/// Picks the points to be kept by the Ramer-Douglas-Peucker algorithm.
+fn ramer_douglas_peucker(points: &[(f64, f64)], tolerance: f64) -> Vec<bool> {
+	let mut keep = vec![false; points.len()];
+	keep[0] = true;
+	keep[points.len() - 1] = true;
+
+	/* Use an explicit stack, as strokes may be long enough for the recursion
+	 * to get uncomfortably deep. */
+	let mut stack = vec![(0, points.len() - 1)];
+	while let Some((first, last)) = stack.pop() {
+		if last - first < 2 { continue }
+
+		let (farthest, distance) = (first + 1..last)
+			.map(|i| (i, segment_distance(points[i], points[first], points[last])))
+			.fold((first, -1.0), |best, current| {
+				if current.1 > best.1 { current } else { best }
+			});
+
+		if distance > tolerance {
+			keep[farthest] = true;
+			stack.push((first, farthest));
+			stack.push((farthest, last));
+		}
+	}
+
+	keep
+}
+
+/// A point in the priority queue of the Visvalingam-Whyatt algorithm.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Candidate {
+	/// The effective area of the point when it was queued.
+	area: f64,
+	/// The index of the point.
+	index: usize,
+}
+impl Eq for Candidate {}
+impl Ord for Candidate {
+	fn cmp(&self, other: &Self) -> Ordering {
+		/* Reversed, so that the smallest area sits on top of the heap. */
+		other.area.partial_cmp(&self.area)
+			.unwrap_or(Ordering::Equal)
+			.then_with(|| other.index.cmp(&self.index))
+	}
+}
+impl PartialOrd for Candidate {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Picks the points to be kept by the Visvalingam-Whyatt algorithm.
+fn visvalingam_whyatt(points: &[(f64, f64)], tolerance: f64) -> Vec<bool> {
+	let threshold = tolerance * tolerance;
+	let len = points.len();
+
+	/* Keep the points in a doubly linked list, so that the neighbours of a
+	 * point can be found after some of the points around it are dropped. */
+	let mut previous = (0..len).map(|i| i.wrapping_sub(1)).collect::<Vec<_>>();
+	let mut next = (1..=len).collect::<Vec<_>>();
+	let mut areas = vec![f64::INFINITY; len];
+	let mut keep = vec![true; len];
+
+	let area = |a: usize, b: usize, c: usize| triangle_area(points[a], points[b], points[c]);
+
+	let mut heap = BinaryHeap::new();
+	for (i, slot) in areas.iter_mut().enumerate().take(len - 1).skip(1) {
+		*slot = area(i - 1, i, i + 1);
+		heap.push(Candidate { area: *slot, index: i });
+	}
+
+	while let Some(Candidate { area: smallest, index }) = heap.pop() {
+		/* Entries whose area has since changed are stale, and get skipped. */
+		if !keep[index] || smallest != areas[index] { continue }
+		if smallest >= threshold { break }
+
+		keep[index] = false;
+		let (before, after) = (previous[index], next[index]);
+		next[before] = after;
+		previous[after] = before;
+
+		/* Neighbours never get a smaller area than the point that was just
+		 * dropped, so that points are dropped in a consistent order. */
+		for neighbour in [before, after] {
+			if neighbour == 0 || neighbour == len - 1 { continue }
+
+			let updated = area(previous[neighbour], neighbour, next[neighbour])
+				.max(smallest);
+			areas[neighbour] = updated;
+			heap.push(Candidate { area: updated, index: neighbour });
+		}
+	}
+
+	keep
+}
+
+/// The distance between a point and the line segment between two others.
+fn segment_distance(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+	let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+	let length = dx * dx + dy * dy;
+	if length == 0.0 {
+		return (point.0 - start.0).hypot(point.1 - start.1)
+	}
+
+	let t = (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length)
+		.clamp(0.0, 1.0);
+	let (x, y) = (start.0 + t * dx, start.1 + t * dy);
+
+	(point.0 - x).hypot(point.1 - y)
+}
+
+/// The area of the triangle formed by the given points.
+fn triangle_area(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+	((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1)).abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, Instant};
+
+	/// Touching events at the given positions, a millisecond apart.
+	fn events(points: &[(f64, f64)]) -> Vec<Event> {
+		let start = Instant::now();
+		points.iter()
+			.enumerate()
+			.map(|(i, (x, y))| Event::new(
+				start + Duration::from_millis(i as u64),
+				*x,
+				*y,
+				0.5,
+				true,
+				true))
+			.collect()
+	}
+
+	fn positions(events: &[Event]) -> Vec<(f64, f64)> {
+		events.iter().map(|event| (event.x(), event.y())).collect()
+	}
+
+	/// A stroke that goes straight, with a little noise, then takes a sharp
+	/// turn and goes straight again.
+	const STROKE: [(f64, f64); 9] = [
+		(0.0, 0.0),
+		(0.1, 0.001),
+		(0.2, -0.001),
+		(0.3, 0.0005),
+		(0.4, 0.0),
+		(0.4, 0.1),
+		(0.4, 0.2),
+		(0.401, 0.3),
+		(0.4, 0.4),
+	];
+
+	const SIMPLIFIERS: [Simplifier; 2] = [
+		Simplifier::RamerDouglasPeucker,
+		Simplifier::VisvalingamWhyatt,
+	];
+
+	#[test]
+	fn short_runs_are_kept() {
+		for simplifier in SIMPLIFIERS.iter().copied() {
+			let events = events(&[(0.0, 0.0), (1.0, 1.0)]);
+			assert_eq!(simplifier.simplify(&events, Tolerance::normalized(10.0)), events);
+			assert!(simplifier.simplify(&[], Tolerance::normalized(10.0)).is_empty());
+		}
+	}
+
+	#[test]
+	fn noise_is_dropped_and_corners_kept() {
+		let events = events(&STROKE);
+		for simplifier in SIMPLIFIERS.iter().copied() {
+			let kept = simplifier.simplify(&events, Tolerance::normalized(0.05));
+			assert_eq!(positions(&kept), [(0.0, 0.0), (0.4, 0.0), (0.4, 0.4)], "{:?}", simplifier);
+		}
+	}
+
+	#[test]
+	fn zero_tolerance_keeps_every_turn() {
+		let events = events(&STROKE);
+
+		/* Only the events along perfectly straight lines may go, as they are
+		 * at no distance from the simplified stroke. */
+		let kept = Simplifier::RamerDouglasPeucker.simplify(&events, Tolerance::normalized(0.0));
+		assert_eq!(kept.len(), STROKE.len() - 1);
+		assert!(!positions(&kept).contains(&(0.4, 0.1)));
+
+		/* Their triangles have no area, which is not smaller than zero. */
+		let kept = Simplifier::VisvalingamWhyatt.simplify(&events, Tolerance::normalized(0.0));
+		assert_eq!(kept, events);
+	}
+
+	#[test]
+	fn ramer_douglas_peucker_bounds_the_distance() {
+		let events = events(&STROKE);
+		let tolerance = 0.05;
+		let kept = Simplifier::RamerDouglasPeucker.simplify(&events, Tolerance::normalized(tolerance));
+		let kept = positions(&kept);
+
+		for point in positions(&events) {
+			let distance = kept.windows(2)
+				.map(|pair| segment_distance(point, pair[0], pair[1]))
+				.fold(f64::INFINITY, f64::min);
+			assert!(distance <= tolerance, "{:?} is {} away", point, distance);
+		}
+	}
+
+	#[test]
+	fn distances_are_measured_in_the_scaled_space() {
+		/* A bump that is small in normalized coordinates, but large along a
+		 * long axis. */
+		let events = events(&[(0.0, 0.0), (0.5, 0.01), (1.0, 0.0)]);
+		let tall = Tolerance { distance: 5.0, scale: (100.0, 1000.0) };
+		let wide = Tolerance { distance: 5.0, scale: (1000.0, 100.0) };
+		assert_eq!(Simplifier::RamerDouglasPeucker.simplify(&events, tall).len(), 3);
+		assert_eq!(Simplifier::RamerDouglasPeucker.simplify(&events, wide).len(), 2);
+
+		/* Areas scale along with both axes at once. */
+		let large = Tolerance { distance: 5.0, scale: (100.0, 100.0) };
+		let small = Tolerance { distance: 5.0, scale: (10.0, 10.0) };
+		assert_eq!(Simplifier::VisvalingamWhyatt.simplify(&events, large).len(), 3);
+		assert_eq!(Simplifier::VisvalingamWhyatt.simplify(&events, small).len(), 2);
+	}
+
+	#[test]
+	fn strokes_stay_whole() {
+		let stroke = Stroke::split(events(&STROKE)).pop().unwrap();
+		let simplified = stroke.simplified(Simplifier::VisvalingamWhyatt, Tolerance::normalized(0.01));
+
+		assert_eq!(simplified.events().first(), stroke.events().first());
+		assert_eq!(simplified.events().last(), stroke.events().last());
+	}
+}
@@ -0,0 +1,257 @@
+use crate::Event;
+use std::time::{Duration, Instant};
+
+/// The kinds of runs of events a stroke may be made up of.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum StrokeKind {
+	/// The pen was touching the screen through all of the events, leaving ink.
+	Ink,
+	/// The pen was lifted through all of the events, either hovering over the
+	/// screen or away from it.
+	Hover,
+}
+
+/// The axis-aligned bounding box of a set of points, in the normalized
+/// coordinate system used by [`Event`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+	/// The smallest position along the horizontal axis.
+	pub left: f64,
+	/// The smallest position along the vertical axis.
+	pub top: f64,
+	/// The largest position along the horizontal axis.
+	pub right: f64,
+	/// The largest position along the vertical axis.
+	pub bottom: f64,
+}
+impl Bounds {
+	/// The width of the bounding box.
+	pub fn width(&self) -> f64 {
+		self.right - self.left
+	}
+
+	/// The height of the bounding box.
+	pub fn height(&self) -> f64 {
+		self.bottom - self.top
+	}
+
+	/// The smallest bounding box containing both this and the given box.
+	pub fn union(&self, other: &Self) -> Self {
+		Self {
+			left: self.left.min(other.left),
+			top: self.top.min(other.top),
+			right: self.right.max(other.right),
+			bottom: self.bottom.max(other.bottom),
+		}
+	}
+}
+
+/// A maximal run of consecutive events during which the pen was either always
+/// touching the screen or always lifted from it.
+///
+/// Strokes are never empty, and the events in them are kept in the order they
+/// were given in, which is expected to be the order in which they happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+	/// Whether this stroke left ink on the screen.
+	kind: StrokeKind,
+	/// The events in this stroke.
+	events: Box<[Event]>,
+}
+impl Stroke {
+	/// Splits the given sequence of events into strokes, at every point where
+	/// the pen either touches the screen or gets lifted from it.
+	pub fn split<I>(events: I) -> Vec<Self>
+		where I: IntoIterator<Item = Event> {
+
+		let mut strokes = Vec::new();
+		let mut current = Vec::new();
+		let mut touching = false;
+
+		for event in events {
+			if !current.is_empty() && event.touching() != touching {
+				strokes.push(Self::from_run(touching, std::mem::take(&mut current)));
+			}
+
+			touching = event.touching();
+			current.push(event);
+		}
+		if !current.is_empty() {
+			strokes.push(Self::from_run(touching, current));
+		}
+
+		strokes
+	}
+
+	/// Creates a stroke from a run of events that are known to be non-empty
+	/// and to agree on whether the pen was touching the screen.
+	fn from_run(touching: bool, events: Vec<Event>) -> Self {
+		Self {
+			kind: if touching { StrokeKind::Ink } else { StrokeKind::Hover },
+			events: events.into_boxed_slice()
+		}
+	}
+
+	/// Whether this stroke left ink on the screen.
+	pub fn kind(&self) -> StrokeKind {
+		self.kind
+	}
+
+	/// Whether the pen was touching the screen during this stroke.
+	pub fn is_ink(&self) -> bool {
+		self.kind == StrokeKind::Ink
+	}
+
+	/// The events in this stroke, in the order they happened in.
+	pub fn events(&self) -> &[Event] {
+		&self.events[..]
+	}
+
+	/// The number of events in this stroke.
+	pub fn len(&self) -> usize {
+		self.events.len()
+	}
+
+	/// Whether this stroke has no events in it. Always false, as strokes are
+	/// never empty.
+	pub fn is_empty(&self) -> bool {
+		self.events.is_empty()
+	}
+
+	/// The time of the first event in this stroke.
+	pub fn start(&self) -> Instant {
+		self.events[0].time()
+	}
+
+	/// The time of the last event in this stroke.
+	pub fn end(&self) -> Instant {
+		self.events[self.events.len() - 1].time()
+	}
+
+	/// The amount of time between the first and the last events in this stroke.
+	pub fn duration(&self) -> Duration {
+		self.end().saturating_duration_since(self.start())
+	}
+
+	/// The distance travelled by the pen during this stroke, in normalized
+	/// coordinates, as the sum of the straight lines between its events.
+	pub fn length(&self) -> f64 {
+		self.events.windows(2)
+			.map(|pair| (pair[1].x() - pair[0].x()).hypot(pair[1].y() - pair[0].y()))
+			.sum()
+	}
+
+	/// The bounding box of the positions of the pen during this stroke.
+	pub fn bounds(&self) -> Bounds {
+		let first = &self.events[0];
+		let initial = Bounds {
+			left: first.x(),
+			top: first.y(),
+			right: first.x(),
+			bottom: first.y(),
+		};
+
+		self.events[1..].iter().fold(initial, |bounds, event| Bounds {
+			left: bounds.left.min(event.x()),
+			top: bounds.top.min(event.y()),
+			right: bounds.right.max(event.x()),
+			bottom: bounds.bottom.max(event.y()),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Events at the given positions, in milliseconds after the same origin and
+	/// normalized coordinates, along with whether the pen touches the screen.
+	fn events(points: &[(u64, f64, f64, bool)]) -> Vec<Event> {
+		let origin = Instant::now();
+		points.iter()
+			.map(|(millis, x, y, touching)| Event::new(
+				origin + Duration::from_millis(*millis),
+				*x,
+				*y,
+				if *touching { 0.5 } else { 0.0 },
+				*touching,
+				true))
+			.collect()
+	}
+
+	#[test]
+	fn split_at_pen_lifts() {
+		let events = events(&[
+			(0, 0.1, 0.1, true),
+			(5, 0.2, 0.1, true),
+			(10, 0.3, 0.1, false),
+			(15, 0.4, 0.1, true),
+			(20, 0.5, 0.1, true),
+			(25, 0.6, 0.1, true),
+		]);
+		let strokes = Stroke::split(events.iter().copied());
+
+		assert_eq!(
+			strokes.iter().map(Stroke::kind).collect::<Vec<_>>(),
+			[StrokeKind::Ink, StrokeKind::Hover, StrokeKind::Ink]);
+		assert_eq!(strokes[0].events(), &events[0..2]);
+		assert_eq!(strokes[1].events(), &events[2..3]);
+		assert_eq!(strokes[2].events(), &events[3..6]);
+	}
+
+	#[test]
+	fn split_starting_lifted() {
+		let events = events(&[
+			(0, 0.1, 0.1, false),
+			(5, 0.2, 0.1, false),
+			(10, 0.3, 0.1, true),
+		]);
+		let strokes = Stroke::split(events.iter().copied());
+
+		assert_eq!(strokes.len(), 2);
+		assert!(!strokes[0].is_ink());
+		assert_eq!(strokes[0].len(), 2);
+		assert!(strokes[1].is_ink());
+		assert_eq!(strokes[1].len(), 1);
+	}
+
+	#[test]
+	fn split_nothing() {
+		assert!(Stroke::split(std::iter::empty()).is_empty());
+	}
+
+	#[test]
+	fn measures() {
+		let events = events(&[
+			(10, 0.1, 0.5, true),
+			(20, 0.4, 0.9, true),
+			(70, 0.4, 0.2, true),
+		]);
+		let stroke = Stroke::split(events.iter().copied()).pop().unwrap();
+
+		assert_eq!(stroke.start(), events[0].time());
+		assert_eq!(stroke.end(), events[2].time());
+		assert_eq!(stroke.duration(), Duration::from_millis(60));
+		assert!((stroke.length() - 1.2).abs() < 1e-9);
+		assert_eq!(stroke.bounds(), Bounds { left: 0.1, top: 0.2, right: 0.4, bottom: 0.9 });
+		assert!((stroke.bounds().width() - 0.3).abs() < 1e-9);
+		assert!((stroke.bounds().height() - 0.7).abs() < 1e-9);
+	}
+
+	#[test]
+	fn single_events_have_no_extent() {
+		let events = events(&[(0, 0.3, 0.6, true)]);
+		let stroke = Stroke::split(events.iter().copied()).pop().unwrap();
+
+		assert_eq!(stroke.duration(), Duration::default());
+		assert_eq!(stroke.length(), 0.0);
+		assert_eq!(stroke.bounds(), Bounds { left: 0.3, top: 0.6, right: 0.3, bottom: 0.6 });
+	}
+
+	#[test]
+	fn bounds_union() {
+		let a = Bounds { left: 0.1, top: 0.5, right: 0.3, bottom: 0.6 };
+		let b = Bounds { left: 0.2, top: 0.1, right: 0.4, bottom: 0.3 };
+		assert_eq!(a.union(&b), Bounds { left: 0.1, top: 0.1, right: 0.4, bottom: 0.6 });
+	}
+}
@@ -0,0 +1,287 @@
+use crate::{Event, EventFilter, DeviceImage, EncodingMode};
+
+/// The clockwise rotations that may be applied to the screen of a device.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Rotation {
+	/// The native, landscape orientation of the device.
+	#[default]
+	None,
+	/// A quarter turn, clockwise.
+	Quarter,
+	/// A half turn.
+	Half,
+	/// Three quarters of a turn clockwise, or a quarter turn counterclockwise.
+	ThreeQuarters,
+}
+impl Rotation {
+	/// All of the rotations, in clockwise order.
+	pub const ALL: [Self; 4] = [Self::None, Self::Quarter, Self::Half, Self::ThreeQuarters];
+
+	/// The angle of this rotation, in degrees.
+	pub fn degrees(self) -> u32 {
+		match self {
+			Self::None => 0,
+			Self::Quarter => 90,
+			Self::Half => 180,
+			Self::ThreeQuarters => 270,
+		}
+	}
+
+	/// Whether this rotation swaps the horizontal and vertical axes.
+	pub fn is_sideways(self) -> bool {
+		matches!(self, Self::Quarter | Self::ThreeQuarters)
+	}
+}
+
+/// A transformation between the native orientation of a device and the
+/// orientation it is being viewed in.
+///
+/// Devices always report their events and expect their images in their native
+/// orientation. When a device is mounted rotated or upside down, this structure
+/// maps between the coordinates of the device, as seen by the hardware, and
+/// the coordinates of the view, as seen by the user. The view is obtained from
+/// the device by first rotating it clockwise and then flipping it.
+///
+/// Transforms may be used as filters, in which case they map the position of
+/// all of the events going through them to the coordinates of the view.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Transform {
+	/// The clockwise rotation applied to the device.
+	rotation: Rotation,
+	/// Whether the view is mirrored along the horizontal axis.
+	flip_horizontal: bool,
+	/// Whether the view is mirrored along the vertical axis.
+	flip_vertical: bool,
+}
+impl Transform {
+	/// The transform that leaves the native orientation unchanged.
+	pub fn identity() -> Self {
+		Self::default()
+	}
+
+	/// Creates a transform with the given rotation and no flips.
+	pub fn rotated(rotation: Rotation) -> Self {
+		Self { rotation, ..Self::default() }
+	}
+
+	/// Changes whether the view is mirrored left to right.
+	pub fn with_horizontal_flip(mut self, flip: bool) -> Self {
+		self.flip_horizontal = flip;
+		self
+	}
+
+	/// Changes whether the view is mirrored top to bottom.
+	pub fn with_vertical_flip(mut self, flip: bool) -> Self {
+		self.flip_vertical = flip;
+		self
+	}
+
+	/// The clockwise rotation applied to the device.
+	pub fn rotation(&self) -> Rotation {
+		self.rotation
+	}
+
+	/// Whether the view is mirrored left to right.
+	pub fn flips_horizontally(&self) -> bool {
+		self.flip_horizontal
+	}
+
+	/// Whether the view is mirrored top to bottom.
+	pub fn flips_vertically(&self) -> bool {
+		self.flip_vertical
+	}
+
+	/// Whether this transform leaves the native orientation unchanged.
+	pub fn is_identity(&self) -> bool {
+		*self == Self::identity()
+	}
+
+	/// The dimensions of the view of a device screen with the given dimensions.
+	pub fn dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+		if self.rotation.is_sideways() {
+			(height, width)
+		} else {
+			(width, height)
+		}
+	}
+
+	/// Maps a point in the normalized coordinates of the device to the
+	/// normalized coordinates of the view.
+	pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+		let (x, y) = match self.rotation {
+			Rotation::None => (x, y),
+			Rotation::Quarter => (1.0 - y, x),
+			Rotation::Half => (1.0 - x, 1.0 - y),
+			Rotation::ThreeQuarters => (y, 1.0 - x),
+		};
+
+		(
+			if self.flip_horizontal { 1.0 - x } else { x },
+			if self.flip_vertical { 1.0 - y } else { y },
+		)
+	}
+
+	/// Maps a point in the normalized coordinates of the view back to the
+	/// normalized coordinates of the device.
+	pub fn invert(&self, x: f64, y: f64) -> (f64, f64) {
+		let x = if self.flip_horizontal { 1.0 - x } else { x };
+		let y = if self.flip_vertical { 1.0 - y } else { y };
+
+		match self.rotation {
+			Rotation::None => (x, y),
+			Rotation::Quarter => (y, 1.0 - x),
+			Rotation::Half => (1.0 - x, 1.0 - y),
+			Rotation::ThreeQuarters => (1.0 - y, x),
+		}
+	}
+
+	/// Maps a pixel on a device screen with the given dimensions to the pixel
+	/// it gets displayed as in the view.
+	///
+	/// Unlike [`apply()`], this function maps pixels exactly, with no rounding.
+	///
+	/// [`apply()`]: Self::apply
+	pub fn apply_pixel(&self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+		let (x, y) = match self.rotation {
+			Rotation::None => (x, y),
+			Rotation::Quarter => (height - 1 - y, x),
+			Rotation::Half => (width - 1 - x, height - 1 - y),
+			Rotation::ThreeQuarters => (y, width - 1 - x),
+		};
+		let (width, height) = self.dimensions(width, height);
+
+		(
+			if self.flip_horizontal { width - 1 - x } else { x },
+			if self.flip_vertical { height - 1 - y } else { y },
+		)
+	}
+
+	/// Encodes an image for a device screen with the given dimensions, from a
+	/// function giving the RGB color of each pixel of the view.
+	///
+	/// This is the counterpart of [`DeviceImage::from_fn()`] for images drawn
+	/// in the orientation of the view. The function is called with coordinates
+	/// in the view, whose dimensions are given by [`dimensions()`].
+	///
+	/// [`DeviceImage::from_fn()`]: DeviceImage::from_fn
+	/// [`dimensions()`]: Self::dimensions
+	pub fn image_from_fn<F>(
+		&self,
+		mode: EncodingMode,
+		width: u32,
+		height: u32,
+		mut pixel: F) -> DeviceImage
+		where F: FnMut(u32, u32) -> [u8; 3] {
+
+		DeviceImage::from_fn(mode, width, height, |x, y| {
+			let (x, y) = self.apply_pixel(x, y, width, height);
+			pixel(x, y)
+		})
+	}
+}
+impl EventFilter for Transform {
+	fn filter(&mut self, event: Event) -> Option<Event> {
+		let (x, y) = self.apply(event.x(), event.y());
+		Some(Event::new(
+			event.time(),
+			x,
+			y,
+			event.pressure(),
+			event.touching(),
+			event.hovering()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Every transform, made up of each of the rotations with every
+	/// combination of flips.
+	fn transforms() -> impl Iterator<Item = Transform> {
+		Rotation::ALL.iter().flat_map(|rotation| {
+			[(false, false), (true, false), (false, true), (true, true)]
+				.iter()
+				.map(move |(horizontal, vertical)| Transform::rotated(*rotation)
+					.with_horizontal_flip(*horizontal)
+					.with_vertical_flip(*vertical))
+		})
+	}
+
+	const POINTS: [(f64, f64); 5] = [
+		(0.0, 0.0),
+		(1.0, 0.0),
+		(0.25, 0.75),
+		(0.5, 0.5),
+		(0.9, 0.1),
+	];
+
+	#[test]
+	fn invert_undoes_apply() {
+		for transform in transforms() {
+			for (x, y) in POINTS.iter().copied() {
+				let (vx, vy) = transform.apply(x, y);
+				let (dx, dy) = transform.invert(vx, vy);
+				assert!((dx - x).abs() < 1e-12 && (dy - y).abs() < 1e-12,
+					"{:?} maps ({}, {}) back to ({}, {})", transform, x, y, dx, dy);
+
+				let (dx, dy) = transform.invert(x, y);
+				let (vx, vy) = transform.apply(dx, dy);
+				assert!((vx - x).abs() < 1e-12 && (vy - y).abs() < 1e-12,
+					"{:?} maps ({}, {}) back to ({}, {})", transform, x, y, vx, vy);
+			}
+		}
+	}
+
+	#[test]
+	fn rotations_turn_clockwise() {
+		/* The top left corner of the device goes around the corners of the
+		 * view clockwise. */
+		let corners = Rotation::ALL
+			.iter()
+			.map(|rotation| Transform::rotated(*rotation).apply(0.0, 0.0))
+			.collect::<Vec<_>>();
+		assert_eq!(corners, [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+	}
+
+	#[test]
+	fn pixels_agree_with_points() {
+		let (width, height) = (8, 5);
+		for transform in transforms() {
+			let (vw, vh) = transform.dimensions(width, height);
+			assert_eq!(
+				(vw, vh),
+				if transform.rotation().is_sideways() { (height, width) } else { (width, height) });
+
+			for y in 0..height {
+				for x in 0..width {
+					/* Map the center of the pixel. */
+					let (u, v) = transform.apply(
+						(f64::from(x) + 0.5) / f64::from(width),
+						(f64::from(y) + 0.5) / f64::from(height));
+					let expected = (
+						(u * f64::from(vw) - 0.5).round() as u32,
+						(v * f64::from(vh) - 0.5).round() as u32);
+
+					assert_eq!(transform.apply_pixel(x, y, width, height), expected, "{:?}", transform);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn filters_move_events_only() {
+		let time = std::time::Instant::now();
+		let mut transform = Transform::rotated(Rotation::Quarter);
+		let event = transform.filter(Event::new(time, 0.25, 0.75, 0.5, true, true)).unwrap();
+
+		assert_eq!((event.x(), event.y()), (0.25, 0.25));
+		assert_eq!(event.time(), time);
+		assert_eq!(event.pressure(), 0.5);
+		assert!(event.touching() && event.hovering());
+	}
+}